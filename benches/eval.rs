@@ -0,0 +1,120 @@
+//! Compares the compiled [`Sampler`] with evaluating the graph recursively,
+//! which evaluates a node once for every node that uses it like the boxed closures did,
+//! and sampling single points with sampling rows of points.
+//!
+//! Run with `cargo bench --bench eval`.
+
+use std::{hint::black_box, time::Instant};
+
+use noise_functions::Sample;
+use noise_functions_playground::graph::{
+    ir::Program, Graph, InPinId, Node, NodeId, OutPinId, Registers, Sampler,
+};
+
+/// A fractal that is used by `width` nodes whose values are added up.
+fn wide_graph(width: usize) -> (Graph, OutPinId) {
+    let mut graph = Graph::new();
+    let pos = [0.0; 2];
+
+    let perlin = graph.insert_node(pos, Node::Perlin);
+    let fractal = graph.insert_node(
+        pos,
+        Node::Fractal {
+            octaves: 4,
+            gain: 0.5,
+            lacunarity: 2.0,
+            weighted_strength: 0.0,
+        },
+    );
+    graph.connect(output(perlin), input(fractal, 0));
+
+    let mut sum = graph.insert_node(pos, Node::Number { value: 0.0 });
+
+    for i in 0..width {
+        let node = graph.insert_node(
+            pos,
+            Node::Mul {
+                lhs: 0.0,
+                rhs: i as f32,
+            },
+        );
+        graph.connect(output(fractal), input(node, 0));
+
+        let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+        graph.connect(output(sum), input(add, 0));
+        graph.connect(output(node), input(add, 1));
+        sum = add;
+    }
+
+    (graph, output(sum))
+}
+
+fn output(node: NodeId) -> OutPinId {
+    OutPinId { node, output: 0 }
+}
+
+fn input(node: NodeId, input: usize) -> InPinId {
+    InPinId { node, input }
+}
+
+/// Returns the nanoseconds per sample.
+fn time(size: usize, sample: impl Fn([f32; 2]) -> f32) -> f64 {
+    let start = Instant::now();
+
+    for y in 0..size {
+        for x in 0..size {
+            black_box(sample([x as f32 * 0.01, y as f32 * 0.01]));
+        }
+    }
+
+    start.elapsed().as_nanos() as f64 / (size * size) as f64
+}
+
+/// Returns the nanoseconds per sample when sampling a row at once.
+fn time_rows(size: usize, sampler: &Sampler) -> f64 {
+    let xs: Vec<f32> = (0..size).map(|x| x as f32 * 0.01).collect();
+    let mut ys = vec![0.0; size];
+    let mut row = vec![0.0; size];
+    let mut registers = Registers::default();
+    let start = Instant::now();
+
+    for y in 0..size {
+        ys.fill(y as f32 * 0.01);
+        sampler.sample_batch(&xs, &ys, 0, &mut row, &mut registers);
+        black_box(&row);
+    }
+
+    start.elapsed().as_nanos() as f64 / (size * size) as f64
+}
+
+fn main() {
+    // `cargo test --all-targets` runs this too, so it only checks that it works unless benchmarking
+    let size = if std::env::args().any(|arg| arg == "--bench") {
+        256
+    } else {
+        4
+    };
+
+    println!(
+        "{:>6} {:>12} {:>12} {:>12} {:>8}",
+        "width", "recursive", "compiled", "rows", "speedup"
+    );
+
+    for width in [1, 4, 16, 64] {
+        let (graph, pin) = wide_graph(width);
+        let program = Program::new(&graph, pin).unwrap();
+        let sampler = Sampler::new(&graph, pin).unwrap();
+
+        let recursive = time(size, |point| program.sample(point, 0));
+        let compiled = time(size, |point| sampler.sample_with_seed(point, 0));
+        let rows = time_rows(size, &sampler);
+
+        println!(
+            "{width:>6} {:>9.0} ns {:>9.0} ns {:>9.0} ns {:>7.1}x",
+            recursive,
+            compiled,
+            rows,
+            recursive / rows
+        );
+    }
+}
@@ -1,37 +1,79 @@
 use std::sync::mpsc::{self, Receiver, Sender};
 
-use egui_snarl::{OutPinId, Snarl};
-use noise_functions::Sample;
+use egui_snarl::Snarl;
 use serde::{Deserialize, Serialize};
 
-use crate::{message_box::MessageBox, nodes_snarl};
+use crate::{
+    codegen::{self, shader},
+    graph::{self, Graph, OutPinId},
+    message_box::MessageBox,
+    nodes_snarl,
+    presets::{Preset, PresetLibrary},
+    render,
+};
 
 const GIT_VERSION: &str = git_version::git_version!();
 
+const PRESETS_KEY: &str = "presets";
+
 pub struct App {
     settings: Settings,
     preview_texture: egui::TextureHandle,
     message_box: MessageBox,
     channel: Receiver<Message>,
-
-    #[cfg_attr(not(target_arch = "wasm32"), expect(dead_code))]
     channel_sender: Sender<Message>,
+    new_preset_name: String,
+    new_preset_category: String,
+    exported_code: Option<(ExportLanguage, String)>,
+    grid_cache: render::GridCache,
+    /// Why the previewed node can't be evaluated.
+    preview_error: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum ExportLanguage {
+    Rust,
+    Shader(shader::Language),
+}
+
+impl ExportLanguage {
+    const ALL: [ExportLanguage; 3] = [
+        ExportLanguage::Rust,
+        ExportLanguage::Shader(shader::Language::Wgsl),
+        ExportLanguage::Shader(shader::Language::Glsl),
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ExportLanguage::Rust => "Rust",
+            ExportLanguage::Shader(language) => language.name(),
+        }
+    }
+
+    fn usage(self) -> &'static str {
+        match self {
+            ExportLanguage::Rust => "Requires the noise-functions crate.",
+            ExportLanguage::Shader(_) => "Call `noise(p, seed)` from your shader.",
+        }
+    }
 }
 
 enum Message {
-    #[cfg(target_arch = "wasm32")]
     Error(String),
+    ImportPresets(PresetLibrary),
+    OpenGraph(Graph),
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct Settings {
-    snarl: Snarl<nodes_snarl::Node>,
+    snarl: Snarl<graph::Node>,
     snarl_viewer: nodes_snarl::Viewer,
     preview_value_min: f32,
     preview_value_max: f32,
     preview_texture_size: usize,
     preview_texture_scale: f32,
+    show_presets: bool,
 }
 
 impl Default for Settings {
@@ -43,6 +85,7 @@ impl Default for Settings {
             preview_value_max: 1.0,
             preview_texture_size: 256,
             preview_texture_scale: 3.0,
+            show_presets: false,
         }
     }
 }
@@ -64,8 +107,20 @@ impl App {
             message_box: Default::default(),
             channel: receiver,
             channel_sender: sender,
+            new_preset_name: String::new(),
+            new_preset_category: String::new(),
+            exported_code: None,
+            grid_cache: Default::default(),
+            preview_error: None,
         };
 
+        if let Some(presets) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, PRESETS_KEY))
+        {
+            app.settings.snarl_viewer.presets = presets;
+        }
+
         app.update_texture_for_selected();
 
         app
@@ -80,45 +135,39 @@ impl App {
     fn update_texture_for(&mut self, node_id: egui_snarl::NodeId) {
         log::info!("updating texture");
 
-        let graph = &self.settings.snarl;
+        let graph = self.settings.snarl_viewer.graph(&self.settings.snarl);
 
         let out_pin = OutPinId {
-            node: node_id,
+            node: node_id.into(),
             output: 0,
         };
 
-        let Some(noise) = nodes_snarl::node_to_noise(graph, out_pin) else {
-            return;
+        let program = match graph::ir::Program::new(&graph, out_pin) {
+            Ok(program) => program,
+            Err(error) => {
+                self.preview_error = Some(error.to_string());
+                return;
+            }
         };
 
-        let mut image = Vec::<egui::Color32>::new();
-
-        image.resize(
-            self.settings.preview_texture_size * self.settings.preview_texture_size,
-            egui::Color32::PLACEHOLDER,
-        );
-
-        let value_min = self.settings.preview_value_min;
-        let value_max = self.settings.preview_value_max;
-        let value_delta = value_max - value_min;
-        let inv_value_delta = 1.0 / value_delta;
-        let value_offset = value_min * inv_value_delta;
-
-        let inv_size = 1.0 / self.settings.preview_texture_size as f32;
-        let scalar = inv_size * 2.0;
-
-        for y in 0..self.settings.preview_texture_size {
-            for x in 0..self.settings.preview_texture_size {
-                let i = y * self.settings.preview_texture_size + x;
-                let x = (x as f32 * scalar - 1.0) * self.settings.preview_texture_scale;
-                let y = (y as f32 * scalar - 1.0) * self.settings.preview_texture_scale;
-                let value = noise.sample_with_seed([x, y], 0);
-                let value_01 = value * inv_value_delta - value_offset;
-                let value_255 = (value_01 * 255.0) as u8;
-                let color = egui::Color32::from_gray(value_255);
-                image[i] = color;
-            }
-        }
+        self.preview_error = None;
+
+        let size = self.settings.preview_texture_size;
+        let region = render::Region::centered(self.settings.preview_texture_scale);
+        let range = [
+            self.settings.preview_value_min,
+            self.settings.preview_value_max,
+        ];
+
+        let image = self
+            .grid_cache
+            .sample_grid(&program, [size, size], region, 0)
+            .into_iter()
+            .map(|value| {
+                let value_255 = (render::normalize(value, range) * 255.0) as u8;
+                egui::Color32::from_gray(value_255)
+            })
+            .collect();
 
         self.preview_texture.set(
             egui::ColorImage {
@@ -129,6 +178,317 @@ impl App {
         );
     }
 
+    /// Sets the range of a Normalize node to the range of its input over the preview region.
+    fn measure(&mut self, node: egui_snarl::NodeId) {
+        let graph = self.settings.snarl_viewer.graph(&self.settings.snarl);
+
+        let input = graph::InPinId {
+            node: node.into(),
+            input: 0,
+        };
+
+        let Some(pin) = graph.input(input) else {
+            self.message_box
+                .open("Error", "Connect a noise to the input to measure it.");
+            return;
+        };
+
+        let program = match graph::ir::Program::new(&graph, pin) {
+            Ok(program) => program,
+            Err(error) => {
+                self.message_box
+                    .open("Error", format!("Can't measure the input: {error}."));
+                return;
+            }
+        };
+
+        let size = self.settings.preview_texture_size;
+        let region = render::Region::centered(self.settings.preview_texture_scale);
+
+        // the grid cache only keeps the grids of its last call, which are the preview's
+        let sampler = graph::Sampler::from_program(&program);
+        let range = render::sample_grid(&sampler, [size, size], region, 0)
+            .into_iter()
+            .filter(|value| value.is_finite())
+            .fold(None::<[f32; 2]>, |range, value| match range {
+                None => Some([value, value]),
+                Some([min, max]) => Some([f32::min(min, value), f32::max(max, value)]),
+            });
+
+        let Some([measured_min, measured_max]) = range else {
+            self.message_box.open(
+                "Error",
+                "The input has no finite values in the preview region.",
+            );
+            return;
+        };
+
+        // a constant input would be divided by a range of zero
+        if measured_min == measured_max {
+            self.message_box.open(
+                "Error",
+                format!("The input is {measured_min} everywhere in the preview region, so it has no range."),
+            );
+            return;
+        }
+
+        if let Some(graph::Node::Normalize { min, max }) = self.settings.snarl.get_node_mut(node) {
+            *min = measured_min;
+            *max = measured_max;
+        }
+    }
+
+    fn export(&mut self, node: egui_snarl::NodeId, language: ExportLanguage) {
+        let graph = self.settings.snarl_viewer.graph(&self.settings.snarl);
+        let pin = OutPinId {
+            node: node.into(),
+            output: 0,
+        };
+        let unevaluable = || "The node's output can't be evaluated.".to_string();
+
+        // the program says why the output can't be evaluated, also for the Rust code
+        let code = graph::ir::Program::new(&graph, pin)
+            .map_err(|error| error.to_string())
+            .and_then(|program| match language {
+                ExportLanguage::Rust => {
+                    codegen::rust::generate(&graph, pin).ok_or_else(unevaluable)
+                }
+                ExportLanguage::Shader(language) => {
+                    shader::generate(&program, language).map_err(|error| error.to_string())
+                }
+            });
+
+        match code {
+            Ok(code) => self.exported_code = Some((language, code)),
+            Err(error) => self.message_box.open("Error", error),
+        }
+    }
+
+    fn show_exported_code(&mut self, ctx: &egui::Context) {
+        let Some((language, code)) = &self.exported_code else {
+            return;
+        };
+
+        let mut open = true;
+
+        egui::Window::new(format!("{} Code", language.name()))
+            .id(egui::Id::new("exported code"))
+            .open(&mut open)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy").clicked() {
+                        ui.ctx().copy_text(code.clone());
+                    }
+
+                    ui.label(language.usage());
+                });
+
+                egui::ScrollArea::both().show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut code.as_str())
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+            });
+
+        if !open {
+            self.exported_code = None;
+        }
+    }
+
+    fn show_presets_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Presets");
+
+        ui.add(egui::TextEdit::singleline(&mut self.new_preset_name).hint_text("Name"));
+        ui.add(egui::TextEdit::singleline(&mut self.new_preset_category).hint_text("Category"));
+
+        if ui
+            .add_enabled(
+                !self.new_preset_name.is_empty(),
+                egui::Button::new("Save Selection"),
+            )
+            .on_hover_text("Saves the selected nodes as a preset.")
+            .clicked()
+        {
+            let fragment = self.settings.snarl_viewer.selection(&self.settings.snarl);
+
+            if fragment.is_empty() {
+                self.message_box.open(
+                    "No selection",
+                    "Select some nodes to save them as a preset.",
+                );
+            } else {
+                let category = match self.new_preset_category.trim() {
+                    "" => "Presets",
+                    category => category,
+                };
+
+                self.settings.snarl_viewer.presets.insert(Preset {
+                    name: self.new_preset_name.trim().into(),
+                    category: category.into(),
+                    fragment,
+                });
+            }
+        }
+
+        ui.separator();
+
+        let mut insert = None;
+        let mut remove = None;
+
+        egui::ScrollArea::vertical()
+            .max_height((ui.available_height() - 40.0).max(0.0))
+            .show(ui, |ui| {
+                let presets = &self.settings.snarl_viewer.presets;
+
+                for category in presets.categories() {
+                    egui::CollapsingHeader::new(category)
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for (i, preset) in presets.presets.iter().enumerate() {
+                                if preset.category != category {
+                                    continue;
+                                }
+
+                                ui.horizontal(|ui| {
+                                    if ui.button("🗑").on_hover_text("Delete").clicked() {
+                                        remove = Some(i);
+                                    }
+
+                                    if ui.button(&preset.name).on_hover_text("Insert").clicked() {
+                                        insert = Some(i);
+                                    }
+                                });
+                            }
+                        });
+                }
+            });
+
+        if let Some(i) = insert {
+            let fragment = self.settings.snarl_viewer.presets.presets[i]
+                .fragment
+                .clone();
+
+            self.settings
+                .snarl_viewer
+                .insert_fragment(&mut self.settings.snarl, &fragment);
+        }
+
+        if let Some(i) = remove {
+            self.settings.snarl_viewer.presets.presets.remove(i);
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("Import…").clicked() {
+                self.import_presets(ui.ctx());
+            }
+
+            if ui.button("Export…").clicked() {
+                self.export_presets(ui.ctx());
+            }
+        });
+    }
+
+    fn open_graph(&mut self, ctx: &egui::Context) {
+        let channel = self.channel_sender.clone();
+        let ctx = ctx.clone();
+
+        execute(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("Graph", &["ron"])
+                .pick_file()
+                .await
+            else {
+                return;
+            };
+
+            let text = String::from_utf8_lossy(&file.read().await).into_owned();
+
+            _ = channel.send(match Graph::from_text(&text) {
+                Ok(graph) => Message::OpenGraph(graph),
+                Err(err) => Message::Error(format!("Can't read graph: {err}")),
+            });
+
+            ctx.request_repaint();
+        });
+    }
+
+    fn save_graph(&mut self, ctx: &egui::Context) {
+        let channel = self.channel_sender.clone();
+        let ctx = ctx.clone();
+        let text = self
+            .settings
+            .snarl_viewer
+            .graph(&self.settings.snarl)
+            .to_text();
+
+        execute(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("Graph", &["ron"])
+                .set_file_name("graph.ron")
+                .save_file()
+                .await
+            else {
+                return;
+            };
+
+            if let Err(err) = file.write(text.as_bytes()).await {
+                _ = channel.send(Message::Error(format!("Can't write graph: {err}")));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn import_presets(&mut self, ctx: &egui::Context) {
+        let channel = self.channel_sender.clone();
+        let ctx = ctx.clone();
+
+        execute(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("Presets", &["ron"])
+                .pick_file()
+                .await
+            else {
+                return;
+            };
+
+            let text = String::from_utf8_lossy(&file.read().await).into_owned();
+
+            _ = channel.send(match PresetLibrary::from_text(&text) {
+                Ok(presets) => Message::ImportPresets(presets),
+                Err(err) => Message::Error(format!("Can't read presets: {err}")),
+            });
+
+            ctx.request_repaint();
+        });
+    }
+
+    fn export_presets(&mut self, ctx: &egui::Context) {
+        let channel = self.channel_sender.clone();
+        let ctx = ctx.clone();
+        let text = self.settings.snarl_viewer.presets.to_text();
+
+        execute(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("Presets", &["ron"])
+                .set_file_name("presets.ron")
+                .save_file()
+                .await
+            else {
+                return;
+            };
+
+            if let Err(err) = file.write(text.as_bytes()).await {
+                _ = channel.send(Message::Error(format!("Can't write presets: {err}")));
+                ctx.request_repaint();
+            }
+        });
+    }
+
     #[cfg(target_arch = "wasm32")]
     fn clear_cache_and_reload_window(&mut self) {
         use wasm_bindgen_futures::{spawn_local, JsFuture};
@@ -174,16 +534,23 @@ impl App {
 
 impl eframe::App for App {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.settings.snarl_viewer.save_groups(&self.settings.snarl);
         eframe::set_value(storage, eframe::APP_KEY, &self.settings);
+        eframe::set_value(storage, PRESETS_KEY, &self.settings.snarl_viewer.presets);
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         if let Ok(message) = self.channel.try_recv() {
             match message {
-                #[cfg(target_arch = "wasm32")]
                 Message::Error(error) => {
                     self.message_box.open("Error", error);
                 }
+                Message::ImportPresets(presets) => {
+                    self.settings.snarl_viewer.presets.merge(presets);
+                }
+                Message::OpenGraph(graph) => {
+                    self.settings.snarl = self.settings.snarl_viewer.open(&graph);
+                }
             }
         }
 
@@ -204,6 +571,46 @@ impl eframe::App for App {
                     );
                 });
 
+                if ui.button("Open…").clicked() {
+                    self.open_graph(ui.ctx());
+                }
+
+                if ui
+                    .button("Save…")
+                    .on_hover_text(
+                        "Saves the graph to a file that can be rendered from the command line.",
+                    )
+                    .clicked()
+                {
+                    self.save_graph(ui.ctx());
+                }
+
+                ui.toggle_value(&mut self.settings.show_presets, "Presets");
+
+                let active_node = self.settings.snarl_viewer.active_node;
+                let mut export = None;
+
+                ui.add_enabled_ui(active_node.is_some(), |ui| {
+                    ui.menu_button("Export", |ui| {
+                        for language in ExportLanguage::ALL {
+                            if ui
+                                .button(format!("Export as {}", language.name()))
+                                .clicked()
+                            {
+                                export = Some(language);
+                                ui.close_menu();
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Generates code for the previewed node.")
+                    .on_disabled_hover_text("Select a node to preview first.");
+                });
+
+                if let (Some(node), Some(language)) = (active_node, export) {
+                    self.export(node, language);
+                }
+
                 #[cfg(target_arch = "wasm32")]
                 if ui
                     .button("Update")
@@ -215,12 +622,24 @@ impl eframe::App for App {
             });
         });
 
+        self.show_exported_code(ctx);
+
+        if self.settings.show_presets {
+            egui::SidePanel::left("presets").show(ctx, |ui| {
+                self.show_presets_panel(ui);
+            });
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             self.settings
                 .snarl_viewer
                 .show(&mut self.settings.snarl, ui);
 
-            if let Some(node) = self.settings.snarl_viewer.changed() {
+            for node in self.settings.snarl_viewer.take_measure_requests() {
+                self.measure(node);
+            }
+
+            if let Some(node) = self.settings.snarl_viewer.changed(&self.settings.snarl) {
                 self.update_texture_for(node)
             }
 
@@ -234,6 +653,10 @@ impl eframe::App for App {
                     egui::load::SizedTexture::new(&self.preview_texture, egui::Vec2::splat(512.0));
                 ui.image(texture);
 
+                if let Some(error) = &self.preview_error {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {error}"));
+                }
+
                 ui.horizontal(|ui| {
                     let mut changed = false;
 
@@ -289,11 +712,27 @@ impl eframe::App for App {
                 ui.label("ℹ Right click nodes and links to delete them.");
                 ui.label("ℹ Hold left mouse button to pan.");
                 ui.label("ℹ Tick a node's checkbox to preview.");
+                ui.label("ℹ Press Space or Tab to search for nodes.");
+                ui.label("ℹ Shift click or shift drag to select nodes.");
+                ui.label("ℹ Ctrl+C, Ctrl+V and Ctrl+D copy, paste and duplicate them.");
+                ui.label("ℹ Ctrl+G groups them, right click a group to enter it.");
             });
         });
     }
 }
 
+/// Runs the future on a separate thread.
+#[cfg(not(target_arch = "wasm32"))]
+fn execute<F: std::future::Future<Output = ()> + Send + 'static>(f: F) {
+    std::thread::spawn(move || pollster::block_on(f));
+}
+
+/// Runs the future on the browser's event loop.
+#[cfg(target_arch = "wasm32")]
+fn execute<F: std::future::Future<Output = ()> + 'static>(f: F) {
+    wasm_bindgen_futures::spawn_local(f);
+}
+
 fn attribution(ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.spacing_mut().item_spacing.x = 0.0;
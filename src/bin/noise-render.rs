@@ -0,0 +1,9 @@
+//! Renders graph files from the command line, see `noise-render --help`.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    noise_functions_playground::cli::main()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
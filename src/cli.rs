@@ -0,0 +1,328 @@
+//! The `noise-render` command that renders graph files without opening the GUI.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use crate::{
+    graph::{self, Graph, NodeId, OutPinId},
+    render::{self, Region},
+};
+
+const USAGE: &str = "\
+Renders a node of a graph file saved from the playground.
+
+Usage: noise-render <GRAPH> --node <NAME|ID> --output <FILE> [OPTIONS]
+       noise-render <GRAPH> --list
+
+Options:
+  -n, --node <NAME|ID>        The node to render, by its name or id
+      --pin <INDEX>           The output pin of the node [default: 0]
+  -o, --output <FILE>         The file to write, .png, .exr or .raw
+      --format <FORMAT>       png, exr or raw [default: from the file extension]
+  -s, --size <WIDTH[xHEIGHT]> The resolution [default: 256]
+      --region <X0,Y0,X1,Y1>  The area of the noise to render [default: -3,-3,3,3]
+      --seed <SEED>           [default: 0]
+      --range <MIN,MAX>       The values that become 0 and 1 [default: -1,1]
+  -l, --list                  Lists the ids and names of the graph's nodes
+  -h, --help                  Prints this message
+
+PNG files are 16-bit grayscale and clamped to the range.
+EXR files are 32-bit float RGB, raw files are 32-bit little endian floats in row-major order.
+";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Png,
+    Exr,
+    Raw,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(Format::Png),
+            "exr" => Some(Format::Exr),
+            "raw" | "r32" => Some(Format::Raw),
+            _ => None,
+        }
+    }
+}
+
+struct Options {
+    graph: PathBuf,
+    node: Option<String>,
+    pin: usize,
+    output: Option<PathBuf>,
+    format: Option<Format>,
+    size: [usize; 2],
+    region: Region,
+    seed: i32,
+    range: [f32; 2],
+    list: bool,
+}
+
+pub fn main() -> ExitCode {
+    match run(std::env::args().skip(1)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let Some(options) = parse(args)? else {
+        print!("{USAGE}");
+        return Ok(());
+    };
+
+    let text = std::fs::read_to_string(&options.graph)
+        .map_err(|err| format!("can't read {}: {err}", options.graph.display()))?;
+
+    let graph = Graph::from_text(&text)
+        .map_err(|err| format!("can't load {}: {err}", options.graph.display()))?;
+
+    if options.list {
+        for (id, node) in graph.nodes() {
+            println!("{}\t{}", id.0, node.name());
+        }
+
+        return Ok(());
+    }
+
+    let node = find_node(&graph, options.node.as_deref().ok_or("missing --node")?)?;
+    let output = options.output.ok_or("missing --output")?;
+
+    let format = match options.format {
+        Some(format) => format,
+        None => output
+            .extension()
+            .and_then(|extension| Format::from_name(&extension.to_string_lossy()))
+            .ok_or("can't tell the format from the file extension, use --format")?,
+    };
+
+    let pin = OutPinId {
+        node,
+        output: options.pin,
+    };
+
+    let sampler = graph::Sampler::new(&graph, pin).map_err(|err| err.to_string())?;
+
+    let values: Vec<f32> =
+        render::sample_grid(&sampler, options.size, options.region, options.seed)
+            .into_iter()
+            .map(|value| render::normalize(value, options.range))
+            .collect();
+
+    write(&output, format, options.size, &values)
+        .map_err(|err| format!("can't write {}: {err}", output.display()))
+}
+
+/// Returns `None` if the usage should be printed.
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut graph = None;
+    let mut options = Options {
+        graph: PathBuf::new(),
+        node: None,
+        pin: 0,
+        output: None,
+        format: None,
+        size: [256; 2],
+        region: Region::centered(3.0),
+        seed: 0,
+        range: [-1.0, 1.0],
+        list: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-l" | "--list" => options.list = true,
+            "-n" | "--node" => options.node = Some(value()?),
+            "--pin" => options.pin = parse_number(&value()?)?,
+            "-o" | "--output" => options.output = Some(value()?.into()),
+            "--format" => {
+                let name = value()?;
+                let format =
+                    Format::from_name(&name).ok_or_else(|| format!("unknown format {name:?}"))?;
+                options.format = Some(format);
+            }
+            "-s" | "--size" => {
+                let value = value()?;
+
+                let [width, height] = match value.split_once('x') {
+                    Some((width, height)) => [width, height],
+                    None => [value.as_str(); 2],
+                };
+
+                options.size = [parse_size(width)?, parse_size(height)?];
+            }
+            "--region" => {
+                let [x0, y0, x1, y1] = parse_list(&value()?)?;
+
+                options.region = Region {
+                    min: [x0, y0],
+                    max: [x1, y1],
+                };
+            }
+            "--seed" => options.seed = parse_number(&value()?)?,
+            "--range" => options.range = parse_list(&value()?)?,
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ if graph.is_none() => graph = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {arg:?}")),
+        }
+    }
+
+    let Some(graph) = graph else {
+        return Ok(None);
+    };
+
+    options.graph = graph;
+    Ok(Some(options))
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("invalid number {text:?}"))
+}
+
+/// Parses a width or height, which the image files store as `u32`.
+fn parse_size(text: &str) -> Result<usize, String> {
+    let size: usize = parse_number(text)?;
+    u32::try_from(size).map_err(|_| format!("size {size} is too large"))?;
+    Ok(size)
+}
+
+/// Parses comma separated numbers.
+fn parse_list<const N: usize>(text: &str) -> Result<[f32; N], String> {
+    let numbers = text
+        .split(',')
+        .map(parse_number)
+        .collect::<Result<Vec<f32>, String>>()?;
+
+    numbers
+        .try_into()
+        .map_err(|_| format!("expected {N} comma separated numbers, got {text:?}"))
+}
+
+/// Finds a node of the root graph by its id or its name.
+fn find_node(graph: &Graph, query: &str) -> Result<NodeId, String> {
+    if let Ok(id) = query.parse() {
+        if graph.node(NodeId(id)).is_some() {
+            return Ok(NodeId(id));
+        }
+    }
+
+    let ids: Vec<NodeId> = graph
+        .nodes()
+        .filter(|(_, node)| node.name().eq_ignore_ascii_case(query))
+        .map(|(id, _)| id)
+        .collect();
+
+    match ids.as_slice() {
+        [id] => Ok(*id),
+        [] => Err(format!(
+            "there is no node {query:?}, use --list to see the nodes"
+        )),
+        ids => Err(format!(
+            "there are {} nodes named {query:?}, use one of their ids: {}",
+            ids.len(),
+            ids.iter()
+                .map(|id| id.0.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+fn write(
+    path: &std::path::Path,
+    format: Format,
+    [width, height]: [usize; 2],
+    values: &[f32],
+) -> Result<(), String> {
+    // `parse` checked that they fit
+    let (width, height) = (width as u32, height as u32);
+
+    match format {
+        Format::Png => {
+            let pixels = values
+                .iter()
+                .map(|value| (value.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16)
+                .collect();
+
+            image::ImageBuffer::<image::Luma<u16>, Vec<u16>>::from_raw(width, height, pixels)
+                .expect("buffer has the image's size")
+                .save_with_format(path, image::ImageFormat::Png)
+                .map_err(|err| err.to_string())
+        }
+        Format::Exr => {
+            let pixels = values.iter().flat_map(|&value| [value; 3]).collect();
+
+            image::Rgb32FImage::from_raw(width, height, pixels)
+                .expect("buffer has the image's size")
+                .save_with_format(path, image::ImageFormat::OpenExr)
+                .map_err(|err| err.to_string())
+        }
+        Format::Raw => {
+            let bytes: Vec<u8> = values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+            std::fs::write(path, bytes).map_err(|err| err.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_node, parse, Options};
+    use crate::graph::{Graph, Node};
+
+    fn parse_args(args: &[&str]) -> Result<Options, String> {
+        let args = ["graph.ron"].iter().chain(args).map(|arg| arg.to_string());
+        parse(args).map(|options| options.expect("a graph is given"))
+    }
+
+    #[test]
+    fn sizes_are_parsed() {
+        assert_eq!(parse_args(&[]).unwrap().size, [256, 256]);
+        assert_eq!(parse_args(&["-s", "64"]).unwrap().size, [64, 64]);
+        assert_eq!(parse_args(&["--size", "640x480"]).unwrap().size, [640, 480]);
+
+        for size in ["64x", "x64", "-1", "64x48x2", "5000000000"] {
+            assert!(parse_args(&["--size", size]).is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn regions_are_parsed() {
+        let region = parse_args(&["--region", "-1, 0,2.5,4"]).unwrap().region;
+        assert_eq!([region.min, region.max], [[-1.0, 0.0], [2.5, 4.0]]);
+
+        for region in ["0,0,1", "0,0,1,1,1", "0,0,1,x", ""] {
+            assert!(parse_args(&["--region", region]).is_err(), "{region}");
+        }
+    }
+
+    #[test]
+    fn ambiguous_names_need_an_id() {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+        let perlin = graph.insert_node(pos, Node::Perlin);
+        let [a, b] = [(); 2].map(|_| graph.insert_node(pos, Node::Abs));
+
+        assert_eq!(find_node(&graph, "perlin"), Ok(perlin));
+        assert_eq!(find_node(&graph, &b.0.to_string()), Ok(b));
+        assert!(find_node(&graph, "Simplex").is_err());
+
+        let error = find_node(&graph, "Abs").unwrap_err();
+        assert!(error.ends_with(&format!("{}, {}", a.0, b.0)), "{error}");
+    }
+}
@@ -0,0 +1,4 @@
+//! Turns a graph into source code.
+
+pub mod rust;
+pub mod shader;
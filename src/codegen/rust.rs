@@ -0,0 +1,1650 @@
+//! Generates a Rust function that builds the same noise as [`Sampler`](crate::graph::Sampler)
+//! out of `noise_functions` combinators.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::graph::{
+    curve::Curve,
+    expression::{self, Expr, Variable},
+    ir::{self, BinaryOp, CellularOutput, TernaryOp, UnaryOp},
+    CellMetric, FalloffShape, Graph, InPinId, Node, NodeId, OutPinId, Scope,
+};
+
+/// Returns the source code of a function `noise() -> impl Sample<2>` that computes the output pin,
+/// or `None` if the pin can't be evaluated.
+pub fn generate(graph: &Graph, pin: OutPinId) -> Option<String> {
+    // the generator follows the wires, which only ends if they don't form a cycle
+    graph.check().ok()?;
+
+    let mut generator = Generator::default();
+    let mut result = generator.node(graph, pin, None)?;
+
+    // return the last binding's expression directly instead of `let nK = ...; nK`
+    if generator
+        .statements
+        .last()
+        .is_some_and(|(name, _)| *name == result)
+    {
+        result = generator.statements.pop().unwrap().1;
+    }
+
+    let mut code = String::new();
+    code.push_str("pub fn noise() -> impl noise_functions::Sample<2> {\n");
+
+    let imports: Vec<&str> = generator.imports.into_iter().collect();
+
+    match imports.as_slice() {
+        [] => (),
+        [import] => code.push_str(&format!("    use noise_functions::{import};\n\n")),
+        imports => code.push_str(&format!(
+            "    use noise_functions::{{{}}};\n\n",
+            imports.join(", ")
+        )),
+    }
+
+    for (name, expr) in &generator.statements {
+        code.push_str(&indent(&format!("let {name} = {expr};")));
+        code.push('\n');
+    }
+
+    if !generator.statements.is_empty() {
+        code.push('\n');
+    }
+
+    code.push_str(&indent(&result));
+    code.push_str("\n}\n");
+
+    Some(code)
+}
+
+#[derive(Default)]
+struct Generator {
+    /// `let` bindings as pairs of name and expression.
+    statements: Vec<(String, String)>,
+    imports: BTreeSet<&'static str>,
+    next_binding: usize,
+    /// The expression of every output pin bound so far, by the group nodes around it and the pin.
+    bound: HashMap<(Vec<NodeId>, OutPinId), String>,
+}
+
+impl Generator {
+    /// Binds the expression to a new variable and returns its name.
+    fn bind(&mut self, expr: String) -> String {
+        let name = format!("n{}", self.next_binding);
+        self.next_binding += 1;
+        self.statements.push((name.clone(), expr));
+        name
+    }
+
+    fn bound_node(
+        &mut self,
+        graph: &Graph,
+        pin: OutPinId,
+        scope: Option<&Scope>,
+    ) -> Option<String> {
+        let key = (Scope::path(scope), pin);
+
+        // the noises are `Copy`, so a binding can be used by every node that uses the pin
+        if let Some(name) = self.bound.get(&key) {
+            return Some(name.clone());
+        }
+
+        let expr = self.node(graph, pin, scope)?;
+        let name = if is_identifier(&expr) {
+            expr
+        } else {
+            self.bind(expr)
+        };
+
+        self.bound.insert(key, name.clone());
+        Some(name)
+    }
+
+    fn constant(&mut self, value: f32) -> String {
+        self.imports.insert("Constant");
+        format!("Constant({})", float(value))
+    }
+
+    fn in_pin_or(
+        &mut self,
+        graph: &Graph,
+        pin: InPinId,
+        default: f32,
+        scope: Option<&Scope>,
+    ) -> Option<String> {
+        if let Some(pin) = graph.input(pin) {
+            self.bound_node(graph, pin, scope)
+        } else {
+            Some(self.constant(default))
+        }
+    }
+
+    fn cell_noise(
+        &mut self,
+        graph: &Graph,
+        pin: OutPinId,
+        name: &str,
+        jitter: f32,
+        scope: Option<&Scope>,
+    ) -> Option<String> {
+        let pin = InPinId {
+            node: pin.node,
+            input: 0,
+        };
+
+        let Some(remote) = graph.input(pin) else {
+            return Some(format!(
+                "noise_functions::{name} {{ jitter: {} }}",
+                float(jitter)
+            ));
+        };
+
+        let jitter = self.bound_node(graph, remote, scope)?;
+        self.imports.insert("NoiseFn");
+        self.imports.insert("Sample");
+
+        Some(format!(
+            "NoiseFn(move |point: [f32; 2], seed: i32| {{\n    \
+                let jitter = {jitter}.sample_with_seed(point, seed);\n    \
+                noise_functions::{name} {{ jitter }}.sample_with_seed(point, seed)\n\
+            }})"
+        ))
+    }
+
+    /// A closure that returns `body`, with the inputs bound to variables of their names.
+    ///
+    /// `uses` says whether the body itself uses the point and the seed.
+    #[allow(clippy::too_many_arguments)]
+    fn closure(
+        &mut self,
+        graph: &Graph,
+        pin: OutPinId,
+        inputs: &[(usize, &str, f32)],
+        body: &str,
+        uses: [bool; 2],
+        scope: Option<&Scope>,
+    ) -> Option<String> {
+        let [mut uses_point, mut uses_seed] = uses;
+        let mut code = String::new();
+
+        for &(input, name, default) in inputs {
+            let pin = InPinId {
+                node: pin.node,
+                input,
+            };
+
+            let value = match graph.input(pin) {
+                Some(remote) => {
+                    let noise = self.bound_node(graph, remote, scope)?;
+                    self.imports.insert("Sample");
+                    uses_point = true;
+                    uses_seed = true;
+                    format!("{noise}.sample_with_seed(point, seed)")
+                }
+                None => float(default),
+            };
+
+            code.push_str(&indent(&format!("let {name}: f32 = {value};")));
+            code.push('\n');
+        }
+
+        code.push_str(&indent(body));
+
+        let point = if uses_point { "point" } else { "_point" };
+        let seed = if uses_seed { "seed" } else { "_seed" };
+        self.imports.insert("NoiseFn");
+
+        Some(format!(
+            "NoiseFn(move |{point}: [f32; 2], {seed}: i32| -> f32 {{\n{code}\n}})"
+        ))
+    }
+
+    fn ternary(
+        &mut self,
+        graph: &Graph,
+        pin: OutPinId,
+        op: TernaryOp,
+        defaults: [f32; 3],
+        scope: Option<&Scope>,
+    ) -> Option<String> {
+        let (names, body) = ternary_formula(op);
+        let inputs: Vec<(usize, &str, f32)> = names
+            .into_iter()
+            .zip(defaults)
+            .enumerate()
+            .map(|(input, (name, default))| (input, name, default))
+            .collect();
+
+        self.closure(graph, pin, &inputs, body, [false; 2], scope)
+    }
+
+    /// For the unary ops that aren't methods of `Noise`.
+    fn unary(
+        &mut self,
+        graph: &Graph,
+        pin: OutPinId,
+        op: UnaryOp,
+        scope: Option<&Scope>,
+    ) -> Option<String> {
+        let body = unary_formula(op);
+        self.closure(graph, pin, &[(0, "value", 0.0)], body, [false; 2], scope)
+    }
+
+    /// For nodes like [`Node::Sum`], which combine their connected inputs like the sampler.
+    fn variadic(&mut self, graph: &Graph, pin: OutPinId, scope: Option<&Scope>) -> Option<String> {
+        let node = &graph[pin.node];
+        let count = node.variadic_inputs()?;
+
+        let connected: Vec<usize> = (0..count)
+            .filter(|&input| {
+                let pin = InPinId {
+                    node: pin.node,
+                    input,
+                };
+                graph.input(pin).is_some()
+            })
+            .collect();
+
+        let names: Vec<String> = connected
+            .iter()
+            .map(|input| format!("value{input}"))
+            .collect();
+
+        if names.is_empty() {
+            let empty = match node {
+                Node::Product { .. } => 1.0,
+                _ => 0.0,
+            };
+            return Some(self.constant(empty));
+        }
+
+        let body = match node {
+            Node::Sum { weights } => {
+                let terms: Vec<String> = connected
+                    .iter()
+                    .zip(&names)
+                    .map(|(&input, name)| {
+                        if weights[input] == 1.0 {
+                            name.clone()
+                        } else {
+                            format!("{name} * {}", float(weights[input]))
+                        }
+                    })
+                    .collect();
+                terms.join(" + ")
+            }
+            Node::Product { .. } => names.join(" * "),
+            Node::Minimum { .. } | Node::Maximum { .. } => {
+                let method = match node {
+                    Node::Minimum { .. } => "min",
+                    _ => "max",
+                };
+                names[1..].iter().fold(names[0].clone(), |code, name| {
+                    format!("{code}.{method}({name})")
+                })
+            }
+            Node::Average { .. } if names.len() > 1 => {
+                format!("({}) / {}", names.join(" + "), float(names.len() as f32))
+            }
+            _ => names[0].clone(),
+        };
+
+        let inputs: Vec<(usize, &str, f32)> = connected
+            .iter()
+            .zip(&names)
+            .map(|(&input, name)| (input, name.as_str(), 0.0))
+            .collect();
+
+        self.closure(graph, pin, &inputs, &body, [false; 2], scope)
+    }
+
+    fn expression(
+        &mut self,
+        graph: &Graph,
+        pin: OutPinId,
+        expression: &str,
+        defaults: [f32; 3],
+        scope: Option<&Scope>,
+    ) -> Option<String> {
+        let expression = expression::parse(expression).ok()?;
+
+        let inputs: Vec<(usize, &str, f32)> = [Variable::A, Variable::B, Variable::C]
+            .into_iter()
+            .zip(defaults)
+            .enumerate()
+            .filter(|(_, (variable, _))| expression.uses(*variable))
+            .map(|(input, (variable, default))| (input, variable.name(), default))
+            .collect();
+
+        let uses = [
+            expression.uses(Variable::X) || expression.uses(Variable::Y),
+            expression.uses(Variable::Seed),
+        ];
+
+        let body = rust_expression(&expression, false);
+        self.closure(graph, pin, &inputs, &body, uses, scope)
+    }
+
+    /// Returns an expression for the noise of the output pin.
+    fn node(&mut self, graph: &Graph, pin: OutPinId, scope: Option<&Scope>) -> Option<String> {
+        let input_or = |this: &mut Self, i: usize, default: f32| {
+            let pin = InPinId {
+                node: pin.node,
+                input: i,
+            };
+
+            this.in_pin_or(graph, pin, default, scope)
+        };
+
+        macro_rules! method {
+            ($method:literal $(, $arg:expr)*) => {{
+                let receiver = input_or(self, 0, 0.0)?;
+                let args: &[String] = &[$($arg),*];
+                self.imports.insert("Noise");
+                format!("{receiver}.{}({})", $method, args.join(", "))
+            }};
+        }
+
+        macro_rules! binary {
+            ($method:literal, $lhs:expr, $rhs:expr) => {{
+                let lhs = input_or(self, 0, $lhs)?;
+                let rhs = input_or(self, 1, $rhs)?;
+                self.imports.insert("Noise");
+                format!("{lhs}.{}({rhs})", $method)
+            }};
+        }
+
+        let expr = match graph[pin.node] {
+            Node::Value => "noise_functions::Value".into(),
+            Node::ValueCubic => "noise_functions::ValueCubic".into(),
+            Node::Perlin => "noise_functions::Perlin".into(),
+            Node::Simplex => "noise_functions::Simplex".into(),
+            Node::OpenSimplex2 => "noise_functions::OpenSimplex2".into(),
+            Node::OpenSimplex2s => "noise_functions::OpenSimplex2s".into(),
+            Node::CellValue { jitter } => {
+                self.cell_noise(graph, pin, "CellValue", jitter, scope)?
+            }
+            Node::CellDistance { jitter } => {
+                self.cell_noise(graph, pin, "CellDistance", jitter, scope)?
+            }
+            Node::CellDistanceSq { jitter } => {
+                self.cell_noise(graph, pin, "CellDistanceSq", jitter, scope)?
+            }
+            Node::Cellular {
+                metric,
+                jitter,
+                exponent,
+            } => {
+                let output = *CellularOutput::ALL.get(pin.output)?;
+                let mut inputs = vec![(0, "jitter", jitter)];
+
+                if metric == CellMetric::Minkowski {
+                    inputs.push((1, "exponent", exponent));
+                }
+
+                let body = cellular_formula(metric, output);
+                self.closure(graph, pin, &inputs, &body, [true, true], scope)?
+            }
+            Node::Checkerboard { .. }
+            | Node::Stripes { .. }
+            | Node::Rings { .. }
+            | Node::RadialGradient { .. }
+            | Node::LinearGradient { .. }
+            | Node::CircleSdf { .. }
+            | Node::BoxSdf { .. }
+            | Node::GridLines { .. }
+            | Node::HexGrid { .. }
+            | Node::Falloff { .. } => {
+                let (inputs, body) = pattern_formula(&graph[pin.node]);
+                self.closure(graph, pin, &inputs, &body, [true, false], scope)?
+            }
+            Node::Fractal {
+                octaves,
+                gain,
+                lacunarity,
+                weighted_strength,
+            } => {
+                let receiver = input_or(self, 0, 0.0)?;
+                self.imports.insert("Noise");
+                format!(
+                    "{receiver}\n    .fbm({octaves}, {}, {})\n    .weighted({})",
+                    float(gain),
+                    float(lacunarity),
+                    float(weighted_strength)
+                )
+            }
+            Node::Frequency { frequency } => binary!("frequency", 0.0, frequency),
+            Node::TriangleWave { frequency } => binary!("triangle_wave", 0.0, frequency),
+            Node::TranslateXy { x, y } => {
+                let receiver = input_or(self, 0, 0.0)?;
+                let x = input_or(self, 1, x)?;
+                let y = input_or(self, 2, y)?;
+                self.imports.insert("Noise");
+                format!("{receiver}.translate_xy({x}, {y})")
+            }
+            Node::Abs => method!("abs"),
+            Node::Neg => method!("neg"),
+            Node::Sqrt => method!("sqrt"),
+            Node::Floor => method!("floor"),
+            Node::Ceil => method!("ceil"),
+            Node::Round => method!("round"),
+            Node::Sin => method!("sin"),
+            Node::Cos => method!("cos"),
+            Node::Tan => method!("tan"),
+            Node::Exp => self.unary(graph, pin, UnaryOp::Exp, scope)?,
+            Node::Ln => self.unary(graph, pin, UnaryOp::Ln, scope)?,
+            Node::Log2 => self.unary(graph, pin, UnaryOp::Log2, scope)?,
+            Node::Sign => self.unary(graph, pin, UnaryOp::Sign, scope)?,
+            Node::Fract => self.unary(graph, pin, UnaryOp::Fract, scope)?,
+            Node::Reciprocal => self.unary(graph, pin, UnaryOp::Reciprocal, scope)?,
+            Node::OneMinus => self.unary(graph, pin, UnaryOp::OneMinus, scope)?,
+            Node::Saturate => self.unary(graph, pin, UnaryOp::Saturate, scope)?,
+            Node::Add { lhs, rhs } => binary!("add", lhs, rhs),
+            Node::Sub { lhs, rhs } => binary!("sub", lhs, rhs),
+            Node::Mul { lhs, rhs } => binary!("mul", lhs, rhs),
+            Node::Div { lhs, rhs } => binary!("div", lhs, rhs),
+            Node::Rem { lhs, rhs } => binary!("rem", lhs, rhs),
+            Node::Pow { lhs, rhs } => binary!("pow", lhs, rhs),
+            Node::Min { lhs, rhs } => binary!("min", lhs, rhs),
+            Node::Max { lhs, rhs } => binary!("max", lhs, rhs),
+            Node::Step { edge, value } => self.closure(
+                graph,
+                pin,
+                &[(0, "edge", edge), (1, "value", value)],
+                "if value < edge { 0.0 } else { 1.0 }",
+                [false; 2],
+                scope,
+            )?,
+            Node::Atan2 { y, x } => self.closure(
+                graph,
+                pin,
+                &[(0, "y", y), (1, "x", x)],
+                "y.atan2(x)",
+                [false; 2],
+                scope,
+            )?,
+            Node::Hypot { x, y } => self.closure(
+                graph,
+                pin,
+                &[(0, "x", x), (1, "y", y)],
+                "x.hypot(y)",
+                [false; 2],
+                scope,
+            )?,
+            Node::Lerp { a, b, t } => {
+                self.ternary(graph, pin, TernaryOp::Lerp, [a, b, t], scope)?
+            }
+            Node::InverseLerp { a, b, value } => {
+                self.ternary(graph, pin, TernaryOp::InverseLerp, [a, b, value], scope)?
+            }
+            Node::Smoothstep {
+                edge0,
+                edge1,
+                value,
+            } => self.ternary(
+                graph,
+                pin,
+                TernaryOp::Smoothstep,
+                [edge0, edge1, value],
+                scope,
+            )?,
+            Node::Smootherstep {
+                edge0,
+                edge1,
+                value,
+            } => self.ternary(
+                graph,
+                pin,
+                TernaryOp::Smootherstep,
+                [edge0, edge1, value],
+                scope,
+            )?,
+            Node::SmoothMin { lhs, rhs, k } => {
+                self.ternary(graph, pin, TernaryOp::SmoothMin, [lhs, rhs, k], scope)?
+            }
+            Node::SmoothMax { lhs, rhs, k } => {
+                self.ternary(graph, pin, TernaryOp::SmoothMax, [lhs, rhs, k], scope)?
+            }
+            Node::Clamp { value, min, max } => {
+                let value = input_or(self, 0, value)?;
+                let min = input_or(self, 1, min)?;
+                let max = input_or(self, 2, max)?;
+                self.imports.insert("Noise");
+                format!("{value}.clamp({min}, {max})")
+            }
+            Node::Remap {
+                value,
+                from_min,
+                from_max,
+                to_min,
+                to_max,
+                clamp,
+            } => self.closure(
+                graph,
+                pin,
+                &[
+                    (0, "value", value),
+                    (1, "from_min", from_min),
+                    (2, "from_max", from_max),
+                    (3, "to_min", to_min),
+                    (4, "to_max", to_max),
+                ],
+                &remap_formula(clamp),
+                [false; 2],
+                scope,
+            )?,
+            Node::Normalize { min, max } => {
+                let range = format!(
+                    "let [from_min, from_max, to_min, to_max]: [f32; 4] = [{}, {}, -1.0, 1.0];\n",
+                    float(min),
+                    float(max)
+                );
+                let body = range + &remap_formula(false);
+                self.closure(graph, pin, &[(0, "value", 0.0)], &body, [false; 2], scope)?
+            }
+            Node::Expression {
+                ref expression,
+                a,
+                b,
+                c,
+            } => self.expression(graph, pin, expression, [a, b, c], scope)?,
+            Node::Curve(ref curve) => self.closure(
+                graph,
+                pin,
+                &[(0, "value", 0.0)],
+                &curve_formula(curve),
+                [false; 2],
+                scope,
+            )?,
+            Node::Terrace {
+                steps,
+                ref heights,
+                smoothness,
+                invert,
+            } => {
+                let heights = ir::terrace_heights(steps, heights);
+
+                if heights.len() < 2 {
+                    self.constant(heights.first().copied().unwrap_or(0.0))
+                } else {
+                    let body = terrace_formula(&heights, smoothness, invert);
+                    self.closure(graph, pin, &[(0, "value", 0.0)], &body, [false; 2], scope)?
+                }
+            }
+            Node::Quantize { levels, min, max } => {
+                let levels = levels.max(2) as f32;
+
+                let body = format!(
+                    "let t = ((value - min) / (max - min)).clamp(0.0, 1.0);\n\
+                     let level = (t * {}).floor().min({});\n\
+                     min + level / {} * (max - min)",
+                    float(levels),
+                    float(levels - 1.0),
+                    float(levels - 1.0)
+                );
+
+                self.closure(
+                    graph,
+                    pin,
+                    &[(0, "value", 0.0), (1, "min", min), (2, "max", max)],
+                    &body,
+                    [false; 2],
+                    scope,
+                )?
+            }
+            Node::Select {
+                a,
+                b,
+                control,
+                threshold,
+                falloff,
+            } => {
+                let falloff_pin = InPinId {
+                    node: pin.node,
+                    input: 4,
+                };
+
+                let mut inputs = vec![
+                    (0, "a", a),
+                    (1, "b", b),
+                    (2, "control", control),
+                    (3, "threshold", threshold),
+                ];
+
+                // like the sampler, which can't smoothstep between equal edges
+                let weight = if graph.input(falloff_pin).is_none() && falloff <= 0.0 {
+                    "let weight = if control < threshold { 0.0 } else { 1.0 };"
+                } else {
+                    inputs.push((4, "falloff", falloff));
+                    "let half = falloff * 0.5;\n\
+                     let [edge0, edge1] = [threshold - half, threshold + half];\n\
+                     let t = ((control - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);\n\
+                     let weight = t * t * (3.0 - 2.0 * t);"
+                };
+
+                self.closure(
+                    graph,
+                    pin,
+                    &inputs,
+                    &format!("{weight}\na + weight * (b - a)"),
+                    [false; 2],
+                    scope,
+                )?
+            }
+            Node::Blend { a, b, weight } => self.closure(
+                graph,
+                pin,
+                &[(0, "a", a), (1, "b", b), (2, "weight", weight)],
+                "let weight = weight.clamp(0.0, 1.0);\na + weight * (b - a)",
+                [false; 2],
+                scope,
+            )?,
+            Node::Sum { .. }
+            | Node::Product { .. }
+            | Node::Minimum { .. }
+            | Node::Maximum { .. }
+            | Node::Average { .. } => self.variadic(graph, pin, scope)?,
+            Node::Seed { seed } => method!("seed", seed.to_string()),
+            Node::AddSeed { add } => method!("add_seed", add.to_string()),
+            Node::MulSeed { mul } => method!("mul_seed", mul.to_string()),
+            Node::Position => {
+                if pin.output > 1 {
+                    return None;
+                }
+
+                self.imports.insert("NoiseFn");
+                format!("NoiseFn(|point: [f32; 2]| point[{}])", pin.output)
+            }
+            Node::Number { value } => input_or(self, 0, value)?,
+            Node::Group { group: id, .. } => {
+                let group = Scope::root(graph, scope).groups().get(id)?;
+                let output = group.outputs().nth(pin.output)?;
+
+                let scope = Scope {
+                    graph,
+                    node: pin.node,
+                    parent: scope,
+                };
+
+                let pin = InPinId {
+                    node: output,
+                    input: 0,
+                };
+
+                self.in_pin_or(&group.graph, pin, 0.0, Some(&scope))?
+            }
+            Node::GroupInput { value, .. } => {
+                let Some(scope) = scope else {
+                    return Some(self.constant(value));
+                };
+
+                let input = scope.group()?.inputs().position(|id| id == pin.node)?;
+
+                let pin = InPinId {
+                    node: scope.node,
+                    input,
+                };
+
+                let value = scope.input_value(input, value);
+                self.in_pin_or(scope.graph, pin, value, scope.parent)?
+            }
+            Node::GroupOutput { .. } => return None,
+        };
+
+        Some(expr)
+    }
+}
+
+/// Formats the value as a Rust `f32` expression.
+fn float(value: f32) -> String {
+    if value.is_nan() {
+        "f32::NAN".into()
+    } else if value == f32::INFINITY {
+        "f32::INFINITY".into()
+    } else if value == f32::NEG_INFINITY {
+        "f32::NEG_INFINITY".into()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// Formats the expression as Rust code, in parentheses if it is `nested` and could bind differently.
+fn rust_expression(expr: &Expr, nested: bool) -> String {
+    let wrap = |code: String| if nested { format!("({code})") } else { code };
+
+    let call = |function: &str, args: &[&Expr]| {
+        let args: Vec<String> = args.iter().map(|arg| rust_expression(arg, false)).collect();
+        format!("f32::{function}({})", args.join(", "))
+    };
+
+    let code = match expr {
+        Expr::Number(value) => return float(*value),
+        Expr::Variable(Variable::X) => return "point[0]".into(),
+        Expr::Variable(Variable::Y) => return "point[1]".into(),
+        Expr::Variable(Variable::Seed) => "seed as f32".into(),
+        Expr::Variable(variable) => return variable.name().into(),
+        Expr::Unary(UnaryOp::Neg, x) => format!("-{}", rust_expression(x, true)),
+        Expr::Unary(op, x) => {
+            let function = match op {
+                UnaryOp::Abs => "abs",
+                UnaryOp::Neg => unreachable!(),
+                UnaryOp::Sqrt => "sqrt",
+                UnaryOp::Floor => "floor",
+                UnaryOp::Ceil => "ceil",
+                UnaryOp::Round => "round",
+                UnaryOp::Sin => "sin",
+                UnaryOp::Cos => "cos",
+                UnaryOp::Tan => "tan",
+                UnaryOp::Exp => "exp",
+                UnaryOp::Ln => "ln",
+                UnaryOp::Log2 => "log2",
+                _ => {
+                    return format!(
+                        "{{ let value: f32 = {}; {} }}",
+                        rust_expression(x, false),
+                        unary_formula(*op)
+                    )
+                }
+            };
+
+            return call(function, &[x]);
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let operator = match op {
+                BinaryOp::Add => "+",
+                BinaryOp::Sub => "-",
+                BinaryOp::Mul => "*",
+                BinaryOp::Div => "/",
+                BinaryOp::Rem => "%",
+                BinaryOp::Pow => return call("powf", &[lhs, rhs]),
+                BinaryOp::Min => return call("min", &[lhs, rhs]),
+                BinaryOp::Max => return call("max", &[lhs, rhs]),
+                BinaryOp::Atan2 => return call("atan2", &[lhs, rhs]),
+                BinaryOp::Hypot => return call("hypot", &[lhs, rhs]),
+                BinaryOp::Step => {
+                    let (edge, value) = (rust_expression(lhs, true), rust_expression(rhs, true));
+                    return wrap(format!("if {value} < {edge} {{ 0.0 }} else {{ 1.0 }}"));
+                }
+            };
+
+            format!(
+                "{} {operator} {}",
+                rust_expression(lhs, true),
+                rust_expression(rhs, true)
+            )
+        }
+        Expr::Ternary(op, a, b, c) => {
+            let (names, body) = ternary_formula(*op);
+            let args = [a, b, c].map(|arg| rust_expression(arg, false));
+
+            format!(
+                "{{ let [{}]: [f32; 3] = [{}]; {} }}",
+                names.join(", "),
+                args.join(", "),
+                body.replace('\n', " ")
+            )
+        }
+    };
+
+    wrap(code)
+}
+
+/// The Rust code computing the op from the operand `value`.
+fn unary_formula(op: UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Abs => "value.abs()",
+        UnaryOp::Neg => "-value",
+        UnaryOp::Sqrt => "value.sqrt()",
+        UnaryOp::Floor => "value.floor()",
+        UnaryOp::Ceil => "value.ceil()",
+        UnaryOp::Round => "value.round()",
+        UnaryOp::Sin => "value.sin()",
+        UnaryOp::Cos => "value.cos()",
+        UnaryOp::Tan => "value.tan()",
+        UnaryOp::Exp => "value.exp()",
+        UnaryOp::Ln => "value.ln()",
+        UnaryOp::Log2 => "value.log2()",
+        // `f32::signum` is 1 for 0
+        UnaryOp::Sign => "if value > 0.0 { 1.0 } else if value < 0.0 { -1.0 } else { value }",
+        UnaryOp::Fract => "value - value.floor()",
+        UnaryOp::Reciprocal => "1.0 / value",
+        UnaryOp::OneMinus => "1.0 - value",
+        UnaryOp::Saturate => "value.clamp(0.0, 1.0)",
+    }
+}
+
+/// The names of the operands and the Rust code computing the op from them.
+fn ternary_formula(op: TernaryOp) -> ([&'static str; 3], &'static str) {
+    match op {
+        // `f32::clamp` would panic if min is greater than max
+        TernaryOp::Clamp => (["value", "min", "max"], "value.max(min).min(max)"),
+        TernaryOp::Lerp => (["a", "b", "t"], "a + t * (b - a)"),
+        TernaryOp::InverseLerp => (["a", "b", "value"], "(value - a) / (b - a)"),
+        TernaryOp::Smoothstep => (
+            ["edge0", "edge1", "value"],
+            "let t = ((value - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);\n\
+             t * t * (3.0 - 2.0 * t)",
+        ),
+        TernaryOp::Smootherstep => (
+            ["edge0", "edge1", "value"],
+            "let t = ((value - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);\n\
+             t * t * t * (t * (t * 6.0 - 15.0) + 10.0)",
+        ),
+        TernaryOp::SmoothMin => (
+            ["a", "b", "k"],
+            "if k <= 0.0 {\n    \
+                 a.min(b)\n\
+             } else {\n    \
+                 let h = (k - (a - b).abs()).max(0.0) / k;\n    \
+                 a.min(b) - h * h * k * 0.25\n\
+             }",
+        ),
+        TernaryOp::SmoothMax => (
+            ["a", "b", "k"],
+            "if k <= 0.0 {\n    \
+                 a.max(b)\n\
+             } else {\n    \
+                 let h = (k - (a - b).abs()).max(0.0) / k;\n    \
+                 a.max(b) + h * h * k * 0.25\n\
+             }",
+        ),
+    }
+}
+
+/// The inputs of a pattern node like [`Node::Checkerboard`] and the Rust code computing it
+/// from them and the point, with the same steps as the sampler.
+fn pattern_formula(node: &Node) -> (Vec<(usize, &'static str, f32)>, String) {
+    const ALONG: &str = "let angle = angle.to_radians();\n\
+                         let t = point[0] * angle.cos() + point[1] * angle.sin();\n";
+    const ALTERNATE: &str = "let t = t.floor();\n\
+                             1.0 - 2.0 * (t - 2.0 * (t * 0.5).floor())";
+
+    match *node {
+        Node::Checkerboard { size } => (
+            vec![(0, "size", size)],
+            format!("let t = (point[0] / size).floor() + (point[1] / size).floor();\n{ALTERNATE}"),
+        ),
+        Node::Stripes { width, angle } => (
+            vec![(0, "width", width), (1, "angle", angle)],
+            format!("{ALONG}let t = t / width;\n{ALTERNATE}"),
+        ),
+        Node::Rings { width } => (
+            vec![(0, "width", width)],
+            format!("let t = point[0].hypot(point[1]) / width;\n{ALTERNATE}"),
+        ),
+        Node::RadialGradient { x, y, radius } => (
+            vec![(0, "x", x), (1, "y", y), (2, "radius", radius)],
+            "let distance = (point[0] - x).hypot(point[1] - y);\n\
+             1.0 - 2.0 * (distance / radius).clamp(0.0, 1.0)"
+                .into(),
+        ),
+        Node::LinearGradient { angle, length } => (
+            vec![(0, "angle", angle), (1, "length", length)],
+            format!("{ALONG}(t * 2.0 / length).clamp(-1.0, 1.0)"),
+        ),
+        Node::CircleSdf { x, y, radius } => (
+            vec![(0, "x", x), (1, "y", y), (2, "radius", radius)],
+            "(point[0] - x).hypot(point[1] - y) - radius".into(),
+        ),
+        Node::BoxSdf {
+            x,
+            y,
+            width,
+            height,
+        } => (
+            vec![
+                (0, "x", x),
+                (1, "y", y),
+                (2, "width", width),
+                (3, "height", height),
+            ],
+            "// the distances outside the sides, negative inside\n\
+             let dx = (point[0] - x).abs() - width * 0.5;\n\
+             let dy = (point[1] - y).abs() - height * 0.5;\n\
+             dx.max(0.0).hypot(dy.max(0.0)) + dx.max(dy).min(0.0)"
+                .into(),
+        ),
+        Node::GridLines { spacing, thickness } => (
+            vec![(0, "spacing", spacing), (1, "thickness", thickness)],
+            "// the distances to the nearest vertical and horizontal line\n\
+             let [dx, dy] = point.map(|p| (p - spacing * (p / spacing).round()).abs());\n\
+             if dx.min(dy) < thickness * 0.5 { 1.0 } else { -1.0 }"
+                .into(),
+        ),
+        Node::HexGrid { size, thickness } => {
+            let [rect, half_rect] = [ir::SQRT_3, ir::SQRT_3 * 0.5].map(float);
+
+            (
+                vec![(0, "size", size), (1, "thickness", thickness)],
+                format!(
+                    "let [x, y] = [point[0] / size, point[1] / size];\n\
+                     let modulo = |value: f32, modulus: f32| value - modulus * (value / modulus).floor();\n\
+                     // the centers lie on two rectangular grids, the second one offset by half a rectangle\n\
+                     let a = [modulo(x, 1.0) - 0.5, modulo(y, {rect}) - {half_rect}];\n\
+                     let b = [\n    \
+                         modulo(x - 0.5, 1.0) - 0.5,\n    \
+                         modulo(y - {half_rect}, {rect}) - {half_rect},\n\
+                     ];\n\
+                     let [x, y] = if a[0] * a[0] + a[1] * a[1] < b[0] * b[0] + b[1] * b[1] {{ a }} else {{ b }};\n\
+                     // the distance to the center in the hexagon's metric, 0.5 at the sides\n\
+                     let distance = x.abs().max(x.abs() * 0.5 + y.abs() * {half_rect});\n\
+                     if (0.5 - distance) * size < thickness * 0.5 {{ 1.0 }} else {{ -1.0 }}"
+                ),
+            )
+        }
+        Node::Falloff {
+            shape,
+            x,
+            y,
+            radius,
+            exponent,
+            invert,
+        } => {
+            let distance = match shape {
+                FalloffShape::Circle => "let distance = x.hypot(y);",
+                FalloffShape::Square => "let distance = x.max(y);",
+                FalloffShape::RoundedBox => {
+                    "let [x, y] = [x - 0.5, y - 0.5];\n\
+                     let distance = x.max(0.0).hypot(y.max(0.0)) + x.max(y).min(0.0) + 0.5;"
+                }
+            };
+            let value = if invert {
+                "distance.clamp(0.0, 1.0).powf(exponent)"
+            } else {
+                "1.0 - distance.clamp(0.0, 1.0).powf(exponent)"
+            };
+
+            (
+                vec![
+                    (0, "x", x),
+                    (1, "y", y),
+                    (2, "radius", radius),
+                    (3, "exponent", exponent),
+                ],
+                format!(
+                    "let x = ((point[0] - x) / radius).abs();\n\
+                     let y = ((point[1] - y) / radius).abs();\n\
+                     // the distance from the center in the shape's metric, 1 at its edge\n\
+                     {distance}\n\
+                     {value}"
+                ),
+            )
+        }
+        _ => unreachable!("{} isn't a pattern", node.name()),
+    }
+}
+
+/// The Rust code of [`Node::Cellular`] from the operands `jitter` and `exponent`, which searches
+/// the cells like [`ir::cells`].
+fn cellular_formula(metric: CellMetric, output: CellularOutput) -> String {
+    let distance = match metric {
+        CellMetric::Euclidean => "x.hypot(y)",
+        CellMetric::Manhattan => "x.abs() + y.abs()",
+        CellMetric::Chebyshev => "x.abs().max(y.abs())",
+        CellMetric::Minkowski => {
+            "(x.abs().powf(exponent) + y.abs().powf(exponent)).powf(1.0 / exponent)"
+        }
+    };
+
+    let value = match output {
+        CellularOutput::F1 => "f1 - 1.0",
+        CellularOutput::F2 => "f2 - 1.0",
+        CellularOutput::Difference => "f2 - f1 - 1.0",
+        CellularOutput::Product => "f1 * f2 * 0.5 - 1.0",
+        CellularOutput::Value => "nearest_hash as f32 * (1.0 / 2147483648.0)",
+    };
+
+    let clamp_exponent = if metric == CellMetric::Minkowski {
+        format!(
+            "let exponent = exponent.max({:?});\n",
+            CellMetric::MIN_EXPONENT
+        )
+    } else {
+        String::new()
+    };
+
+    // only the value needs the hash of the nearest cell
+    let (declare_hash, store_hash) = if output == CellularOutput::Value {
+        (
+            "let mut nearest_hash = 0;\n",
+            "\n            nearest_hash = hash;",
+        )
+    } else {
+        ("", "")
+    };
+
+    format!(
+        "let round = |x: f32| (x + if x >= 0.0 {{ 0.5 }} else {{ -0.5 }}) as i32;\n\
+         let [xr, yr] = point.map(round);\n\
+         let cell_jitter = 0.437_015_95 * jitter;\n\
+         {clamp_exponent}\
+         let mut f1 = 1e10_f32;\n\
+         let mut f2 = 1e10_f32;\n\
+         {declare_hash}\n\
+         for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {{\n    \
+             for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {{\n        \
+                 let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))\n            \
+                     .wrapping_mul(668265261);\n        \
+                 let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);\n        \
+                 let x = xi as f32 - point[0] + angle.cos() * cell_jitter;\n        \
+                 let y = yi as f32 - point[1] + angle.sin() * cell_jitter;\n        \
+                 let distance = {distance};\n\
+                 \n        \
+                 if distance < f1 {{\n            \
+                     f2 = f1;\n            \
+                     f1 = distance;{store_hash}\n        \
+                 }} else if distance < f2 {{\n            \
+                     f2 = distance;\n        \
+                 }}\n    \
+             }}\n\
+         }}\n\
+         \n\
+         {value}"
+    )
+}
+
+/// The Rust code of [`Node::Remap`] from the operands `value`, `from_min`, `from_max`, `to_min` and `to_max`.
+fn remap_formula(clamp: bool) -> String {
+    let clamp = if clamp {
+        "let t = t.clamp(0.0, 1.0);\n"
+    } else {
+        ""
+    };
+
+    format!("let t = (value - from_min) / (from_max - from_min);\n{clamp}to_min + t * (to_max - to_min)")
+}
+
+/// The Rust code of [`Node::Curve`] from the operand `value`, which evaluates the segments like
+/// [`Segment::sample`](crate::graph::curve::Segment::sample).
+fn curve_formula(curve: &Curve) -> String {
+    let segments: Vec<String> = curve
+        .segments()
+        .iter()
+        .map(|segment| {
+            let [x0, y0] = segment.from;
+            let [x1, y1] = segment.to;
+            let [m0, m1] = segment.slopes;
+            let values = [x0, y0, x1, y1, m0, m1].map(float);
+            format!("    [{}],", values.join(", "))
+        })
+        .collect();
+
+    format!(
+        "// the start and end points and the slopes there\n\
+         let segments: [[f32; 6]; {}] = [\n{}\n];\n\
+         segments.iter().fold({}, |before, &[x0, y0, x1, y1, m0, m1]| {{\n    \
+             if value < x0 {{\n        \
+                 return before;\n    \
+             }}\n    \
+             let dx = x1 - x0;\n    \
+             let t = ((value - x0) / dx).min(1.0);\n    \
+             let t2 = t * t;\n    \
+             let t3 = t2 * t;\n    \
+             (2.0 * t3 - 3.0 * t2 + 1.0) * y0\n        \
+                 + (t3 - 2.0 * t2 + t) * dx * m0\n        \
+                 + (3.0 * t2 - 2.0 * t3) * y1\n        \
+                 + (t3 - t2) * dx * m1\n\
+         }})",
+        segments.len(),
+        segments.join("\n"),
+        float(curve.start())
+    )
+}
+
+/// The Rust code of [`Node::Terrace`] from the operand `value`, which adds up the terraces
+/// like the sampler.
+fn terrace_formula(heights: &[f32], smoothness: f32, invert: bool) -> String {
+    let heights: Vec<String> = heights.iter().map(|&height| float(height)).collect();
+
+    let mut code = format!(
+        "let heights: [f32; {}] = [{}];\n\
+         heights.windows(2).fold(heights[0], |sum, pair| {{\n    \
+             let t = ((value - pair[0]) / (pair[1] - pair[0])).clamp(0.0, 1.0);\n",
+        heights.len(),
+        heights.join(", ")
+    );
+
+    if invert {
+        code.push_str("    let t = 1.0 - t;\n");
+    }
+
+    if smoothness <= 0.0 {
+        code.push_str("    let shaped = if t < 1.0 { 0.0 } else { 1.0 };\n");
+    } else {
+        code.push_str(&format!(
+            "    let shaped = t.powf({});\n",
+            float(1.0 / smoothness)
+        ));
+    }
+
+    if invert {
+        code.push_str("    let shaped = 1.0 - shaped;\n");
+    }
+
+    code.push_str("    sum + shaped * (pair[1] - pair[0])\n})");
+    code
+}
+
+fn is_identifier(expr: &str) -> bool {
+    expr.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("    {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use noise_functions::Sample;
+
+    use crate::graph::{
+        curve::{Curve, Interpolation},
+        CellMetric, FalloffShape, Graph, Group, InPinId, Node, NodeId, OutPinId, Sampler,
+    };
+
+    /// The code generated for [`example_graph`], compiled as part of the tests.
+    mod example {
+        include!("rust_example.rs");
+    }
+
+    const EXAMPLE_PATH: &str = "src/codegen/rust_example.rs";
+
+    fn connect(graph: &mut Graph, from: NodeId, output: usize, to: NodeId, input: usize) {
+        graph.connect(OutPinId { node: from, output }, InPinId { node: to, input });
+    }
+
+    /// Inserts the node with the outputs connected to its first inputs.
+    fn insert(graph: &mut Graph, node: Node, inputs: &[(NodeId, usize)]) -> NodeId {
+        let node = graph.insert_node([0.0; 2], node);
+
+        for (input, &(from, output)) in inputs.iter().enumerate() {
+            connect(graph, from, output, node, input);
+        }
+
+        node
+    }
+
+    /// A graph with every kind of node, whose outputs are added up by a [`Node::Sum`].
+    fn example_graph() -> (Graph, OutPinId) {
+        let pos = [0.0; 2];
+
+        let mut inner = Graph::new();
+        let input = inner.insert_node(
+            pos,
+            Node::GroupInput {
+                name: "Input".into(),
+                value: 0.0,
+            },
+        );
+        let abs = inner.insert_node(pos, Node::Abs);
+        let output = inner.insert_node(
+            pos,
+            Node::GroupOutput {
+                name: "Output".into(),
+            },
+        );
+        connect(&mut inner, input, 0, abs, 0);
+        connect(&mut inner, abs, 0, output, 0);
+
+        let mut graph = Graph::new();
+        let absolute_group = graph
+            .groups_mut()
+            .insert(Group::new("Absolute".into(), inner));
+
+        let perlin = graph.insert_node(pos, Node::Perlin);
+        let fractal = graph.insert_node(
+            pos,
+            Node::Fractal {
+                octaves: 4,
+                gain: 0.5,
+                lacunarity: 2.0,
+                weighted_strength: 0.25,
+            },
+        );
+        let frequency_value = graph.insert_node(pos, Node::Number { value: 0.8 });
+        let frequency = graph.insert_node(pos, Node::Frequency { frequency: 1.0 });
+        let jitter = graph.insert_node(pos, Node::Number { value: 0.6 });
+        let cell = graph.insert_node(pos, Node::CellDistance { jitter: 1.0 });
+        let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+        let position = graph.insert_node(pos, Node::Position);
+        let translate = graph.insert_node(pos, Node::TranslateXy { x: 0.5, y: -1.5 });
+        let add_seed = graph.insert_node(pos, Node::AddSeed { add: 3 });
+        let clamp = graph.insert_node(
+            pos,
+            Node::Clamp {
+                value: 0.0,
+                min: -0.5,
+                max: 0.75,
+            },
+        );
+        let group = graph.insert_node(
+            pos,
+            Node::Group {
+                group: absolute_group,
+                values: Vec::new(),
+            },
+        );
+
+        connect(&mut graph, perlin, 0, fractal, 0);
+        connect(&mut graph, fractal, 0, frequency, 0);
+        connect(&mut graph, frequency_value, 0, frequency, 1);
+        connect(&mut graph, jitter, 0, cell, 0);
+        connect(&mut graph, frequency, 0, add, 0);
+        connect(&mut graph, cell, 0, add, 1);
+        connect(&mut graph, add, 0, translate, 0);
+        connect(&mut graph, position, 1, translate, 1);
+        connect(&mut graph, translate, 0, add_seed, 0);
+        connect(&mut graph, add_seed, 0, clamp, 0);
+        connect(&mut graph, clamp, 0, group, 0);
+
+        // the inputs of the other nodes, nodes with steps get the position
+        // so that the interpreter and the generated code step at the same points
+        let g = &mut graph;
+        let base = (group, 0);
+        let x = (position, 0);
+        let y = (position, 1);
+        let simplex = (insert(g, Node::Simplex, &[]), 0);
+        let absolute = (insert(g, Node::Abs, &[simplex]), 0);
+        let shifted = (insert(g, Node::Add { lhs: 0.0, rhs: 2.0 }, &[simplex]), 0);
+        let quarter_x = (
+            insert(
+                g,
+                Node::Mul {
+                    lhs: 0.0,
+                    rhs: 0.25,
+                },
+                &[x],
+            ),
+            0,
+        );
+        let [minus_one, minus_half, half, one] =
+            [-1.0, -0.5, 0.5, 1.0].map(|value| (insert(g, Node::Number { value }, &[]), 0));
+
+        let cellular = |metric, jitter| Node::Cellular {
+            metric,
+            jitter,
+            exponent: 1.5,
+        };
+        let falloff = |shape, invert| Node::Falloff {
+            shape,
+            x: 0.5,
+            y: 0.5,
+            radius: 4.0,
+            exponent: 1.5,
+            invert,
+        };
+        let curve = |interpolation| {
+            Node::Curve(Curve {
+                points: vec![[-1.0, -1.0], [-0.2, 0.4], [0.3, 0.1], [1.0, 0.5]],
+                interpolation,
+            })
+        };
+
+        let nodes = [
+            // noise
+            (Node::Value, vec![]),
+            (Node::ValueCubic, vec![]),
+            (Node::OpenSimplex2, vec![]),
+            (Node::OpenSimplex2s, vec![]),
+            (Node::CellValue { jitter: 0.8 }, vec![]),
+            (Node::CellDistanceSq { jitter: 1.0 }, vec![]),
+            (cellular(CellMetric::Manhattan, 0.5), vec![]),
+            (cellular(CellMetric::Chebyshev, 1.0), vec![]),
+            (cellular(CellMetric::Minkowski, 0.8), vec![]),
+            (Node::TriangleWave { frequency: 2.0 }, vec![simplex]),
+            (Node::Seed { seed: 5 }, vec![simplex]),
+            (Node::MulSeed { mul: 3 }, vec![simplex]),
+            // patterns
+            (Node::Checkerboard { size: 1.3 }, vec![]),
+            (
+                Node::Stripes {
+                    width: 0.9,
+                    angle: 30.0,
+                },
+                vec![],
+            ),
+            (Node::Rings { width: 1.1 }, vec![]),
+            (
+                Node::RadialGradient {
+                    x: 0.5,
+                    y: -0.5,
+                    radius: 3.0,
+                },
+                vec![],
+            ),
+            (
+                Node::LinearGradient {
+                    angle: 60.0,
+                    length: 4.0,
+                },
+                vec![],
+            ),
+            (
+                Node::CircleSdf {
+                    x: 1.0,
+                    y: 0.0,
+                    radius: 2.0,
+                },
+                vec![],
+            ),
+            (
+                Node::BoxSdf {
+                    x: 0.0,
+                    y: 1.0,
+                    width: 2.0,
+                    height: 1.5,
+                },
+                vec![],
+            ),
+            (
+                Node::GridLines {
+                    spacing: 1.7,
+                    thickness: 0.2,
+                },
+                vec![],
+            ),
+            (
+                Node::HexGrid {
+                    size: 1.2,
+                    thickness: 0.15,
+                },
+                vec![],
+            ),
+            (falloff(FalloffShape::Circle, false), vec![]),
+            (falloff(FalloffShape::Square, true), vec![]),
+            (falloff(FalloffShape::RoundedBox, false), vec![]),
+            // unary
+            (Node::Neg, vec![simplex]),
+            (Node::Sqrt, vec![absolute]),
+            (Node::Floor, vec![x]),
+            (Node::Ceil, vec![y]),
+            (Node::Round, vec![x]),
+            (Node::Sin, vec![simplex]),
+            (Node::Cos, vec![simplex]),
+            (Node::Tan, vec![simplex]),
+            (Node::Exp, vec![simplex]),
+            (Node::Ln, vec![shifted]),
+            (Node::Log2, vec![shifted]),
+            (Node::Sign, vec![x]),
+            (Node::Fract, vec![y]),
+            (Node::Reciprocal, vec![shifted]),
+            (Node::OneMinus, vec![simplex]),
+            (Node::Saturate, vec![shifted]),
+            // binary
+            (Node::Sub { lhs: 0.0, rhs: 0.0 }, vec![simplex, base]),
+            (Node::Div { lhs: 0.0, rhs: 4.0 }, vec![simplex]),
+            (Node::Rem { lhs: 0.0, rhs: 0.7 }, vec![x]),
+            (Node::Pow { lhs: 0.0, rhs: 1.5 }, vec![absolute]),
+            (Node::Min { lhs: 0.0, rhs: 0.0 }, vec![simplex, base]),
+            (Node::Max { lhs: 0.0, rhs: 0.2 }, vec![simplex]),
+            (
+                Node::Step {
+                    edge: 0.0,
+                    value: 0.0,
+                },
+                vec![half, quarter_x],
+            ),
+            (Node::Atan2 { y: 0.0, x: 0.0 }, vec![simplex, shifted]),
+            (Node::Hypot { x: 0.0, y: 0.5 }, vec![simplex]),
+            // ternary
+            (
+                Node::Lerp {
+                    a: 0.0,
+                    b: 0.0,
+                    t: 0.3,
+                },
+                vec![simplex, base],
+            ),
+            (
+                Node::InverseLerp {
+                    a: 0.0,
+                    b: 0.0,
+                    value: 0.0,
+                },
+                vec![minus_one, one, simplex],
+            ),
+            (
+                Node::Smoothstep {
+                    edge0: 0.0,
+                    edge1: 0.0,
+                    value: 0.0,
+                },
+                vec![minus_half, half, simplex],
+            ),
+            (
+                Node::Smootherstep {
+                    edge0: 0.0,
+                    edge1: 0.0,
+                    value: 0.0,
+                },
+                vec![minus_half, half, simplex],
+            ),
+            (
+                Node::SmoothMin {
+                    lhs: 0.0,
+                    rhs: 0.0,
+                    k: 0.3,
+                },
+                vec![simplex, base],
+            ),
+            (
+                Node::SmoothMax {
+                    lhs: 0.0,
+                    rhs: 0.1,
+                    k: 0.5,
+                },
+                vec![simplex],
+            ),
+            (
+                Node::Remap {
+                    value: 0.0,
+                    from_min: -1.0,
+                    from_max: 1.0,
+                    to_min: 0.0,
+                    to_max: 2.0,
+                    clamp: true,
+                },
+                vec![simplex],
+            ),
+            (
+                Node::Normalize {
+                    min: -0.5,
+                    max: 0.5,
+                },
+                vec![simplex],
+            ),
+            // shaping
+            (
+                Node::Expression {
+                    expression: "a * sin(x * 2) + max(b, y) * c - seed * 0.01".into(),
+                    a: 0.0,
+                    b: 0.2,
+                    c: 0.5,
+                },
+                vec![simplex],
+            ),
+            (curve(Interpolation::Linear), vec![simplex]),
+            (curve(Interpolation::Cubic), vec![simplex]),
+            (curve(Interpolation::MonotoneCubic), vec![simplex]),
+            (
+                Node::Terrace {
+                    steps: 4,
+                    heights: Vec::new(),
+                    smoothness: 0.5,
+                    invert: false,
+                },
+                vec![simplex],
+            ),
+            (
+                Node::Terrace {
+                    steps: 0,
+                    heights: vec![-0.5, 0.0, 0.7],
+                    smoothness: 0.0,
+                    invert: true,
+                },
+                vec![quarter_x],
+            ),
+            (
+                Node::Quantize {
+                    levels: 5,
+                    min: -1.0,
+                    max: 1.0,
+                },
+                vec![quarter_x],
+            ),
+            // combine
+            (
+                Node::Select {
+                    a: 0.0,
+                    b: 0.0,
+                    control: 0.0,
+                    threshold: 0.0,
+                    falloff: 0.0,
+                },
+                vec![simplex, base, quarter_x],
+            ),
+            (
+                Node::Select {
+                    a: 0.0,
+                    b: 0.0,
+                    control: 0.0,
+                    threshold: 0.2,
+                    falloff: 0.5,
+                },
+                vec![simplex, base, quarter_x],
+            ),
+            (
+                Node::Blend {
+                    a: 0.0,
+                    b: 0.0,
+                    weight: 0.3,
+                },
+                vec![simplex, base],
+            ),
+            (Node::Product { inputs: 2 }, vec![simplex, base]),
+            (Node::Minimum { inputs: 3 }, vec![simplex, base, y]),
+            (Node::Average { inputs: 3 }, vec![simplex, base, y]),
+            // group
+            (
+                Node::Group {
+                    group: absolute_group,
+                    values: vec![-0.3],
+                },
+                vec![],
+            ),
+        ];
+
+        let mut terms: Vec<(NodeId, usize)> = nodes
+            .into_iter()
+            .map(|(node, inputs)| (insert(g, node, &inputs), 0))
+            .collect();
+
+        // every output of a cellular node, and a variadic node with an unconnected input
+        let euclidean = insert(g, cellular(CellMetric::Euclidean, 1.0), &[]);
+        terms.extend((0..5).map(|output| (euclidean, output)));
+
+        let maximum = insert(g, Node::Maximum { inputs: 3 }, &[simplex]);
+        connect(g, base.0, base.1, maximum, 2);
+        terms.push((maximum, 0));
+
+        terms.push(base);
+
+        let weights = (0..terms.len())
+            .map(|i| if i % 3 == 0 { 0.5 } else { 1.0 })
+            .collect();
+        let sum = insert(g, Node::Sum { weights }, &terms);
+
+        (
+            graph,
+            OutPinId {
+                node: sum,
+                output: 0,
+            },
+        )
+    }
+
+    #[test]
+    fn example_is_up_to_date() {
+        let (graph, pin) = example_graph();
+        let code = super::generate(&graph, pin).unwrap();
+
+        if std::env::var_os("UPDATE_CODEGEN_EXAMPLE").is_some() {
+            std::fs::write(EXAMPLE_PATH, &code).unwrap();
+        }
+
+        assert_eq!(
+            code,
+            include_str!("rust_example.rs"),
+            "run the tests with `UPDATE_CODEGEN_EXAMPLE=1` to update {EXAMPLE_PATH}"
+        );
+    }
+
+    #[test]
+    fn example_matches_graph() {
+        let (graph, pin) = example_graph();
+        let sampler = Sampler::new(&graph, pin).unwrap();
+        let generated = example::noise();
+
+        for seed in [0, 1, 42] {
+            for y in -16..16 {
+                for x in -16..16 {
+                    let point = [x as f32 * 0.37, y as f32 * 0.29];
+                    let expected = sampler.sample_with_seed(point, seed);
+                    let actual = generated.sample_with_seed(point, seed);
+
+                    // relative, the sum is in the tens
+                    assert!(
+                        (expected - actual).abs() <= 1e-5 * expected.abs().max(1.0),
+                        "{expected} != {actual} at {point:?} with seed {seed}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cycles_are_not_generated() {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        let abs = graph.insert_node(pos, Node::Abs);
+        let neg = graph.insert_node(pos, Node::Neg);
+        connect(&mut graph, neg, 0, abs, 0);
+        connect(&mut graph, abs, 0, neg, 0);
+
+        let pin = OutPinId {
+            node: abs,
+            output: 0,
+        };
+
+        assert!(super::generate(&graph, pin).is_none());
+    }
+
+    #[test]
+    fn shared_nodes_are_bound_once() {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        // every node adds the previous one to itself, which has 2^64 paths to the position
+        let mut node = graph.insert_node(pos, Node::Position);
+
+        for _ in 0..64 {
+            let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+            connect(&mut graph, node, 0, add, 0);
+            connect(&mut graph, node, 0, add, 1);
+            node = add;
+        }
+
+        let code = super::generate(&graph, OutPinId { node, output: 0 }).unwrap();
+        assert_eq!(code.matches("let n").count(), 64, "{code}");
+    }
+}
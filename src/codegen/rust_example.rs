@@ -0,0 +1,733 @@
+pub fn noise() -> impl noise_functions::Sample<2> {
+    use noise_functions::{Constant, Noise, NoiseFn, Sample};
+
+    let n0 = noise_functions::Value;
+    let n1 = noise_functions::ValueCubic;
+    let n2 = noise_functions::OpenSimplex2;
+    let n3 = noise_functions::OpenSimplex2s;
+    let n4 = noise_functions::CellValue { jitter: 0.8 };
+    let n5 = noise_functions::CellDistanceSq { jitter: 1.0 };
+    let n6 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 0.5;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = x.abs() + y.abs();
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        f1 - 1.0
+    });
+    let n7 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 1.0;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = x.abs().max(y.abs());
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        f1 - 1.0
+    });
+    let n8 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 0.8;
+        let exponent: f32 = 1.5;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let exponent = exponent.max(0.1);
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = (x.abs().powf(exponent) + y.abs().powf(exponent)).powf(1.0 / exponent);
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        f1 - 1.0
+    });
+    let n9 = noise_functions::Simplex;
+    let n10 = n9.triangle_wave(Constant(2.0));
+    let n11 = n9.seed(5);
+    let n12 = n9.mul_seed(3);
+    let n13 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let size: f32 = 1.3;
+        let t = (point[0] / size).floor() + (point[1] / size).floor();
+        let t = t.floor();
+        1.0 - 2.0 * (t - 2.0 * (t * 0.5).floor())
+    });
+    let n14 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let width: f32 = 0.9;
+        let angle: f32 = 30.0;
+        let angle = angle.to_radians();
+        let t = point[0] * angle.cos() + point[1] * angle.sin();
+        let t = t / width;
+        let t = t.floor();
+        1.0 - 2.0 * (t - 2.0 * (t * 0.5).floor())
+    });
+    let n15 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let width: f32 = 1.1;
+        let t = point[0].hypot(point[1]) / width;
+        let t = t.floor();
+        1.0 - 2.0 * (t - 2.0 * (t * 0.5).floor())
+    });
+    let n16 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let x: f32 = 0.5;
+        let y: f32 = -0.5;
+        let radius: f32 = 3.0;
+        let distance = (point[0] - x).hypot(point[1] - y);
+        1.0 - 2.0 * (distance / radius).clamp(0.0, 1.0)
+    });
+    let n17 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let angle: f32 = 60.0;
+        let length: f32 = 4.0;
+        let angle = angle.to_radians();
+        let t = point[0] * angle.cos() + point[1] * angle.sin();
+        (t * 2.0 / length).clamp(-1.0, 1.0)
+    });
+    let n18 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let x: f32 = 1.0;
+        let y: f32 = 0.0;
+        let radius: f32 = 2.0;
+        (point[0] - x).hypot(point[1] - y) - radius
+    });
+    let n19 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let x: f32 = 0.0;
+        let y: f32 = 1.0;
+        let width: f32 = 2.0;
+        let height: f32 = 1.5;
+        // the distances outside the sides, negative inside
+        let dx = (point[0] - x).abs() - width * 0.5;
+        let dy = (point[1] - y).abs() - height * 0.5;
+        dx.max(0.0).hypot(dy.max(0.0)) + dx.max(dy).min(0.0)
+    });
+    let n20 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let spacing: f32 = 1.7;
+        let thickness: f32 = 0.2;
+        // the distances to the nearest vertical and horizontal line
+        let [dx, dy] = point.map(|p| (p - spacing * (p / spacing).round()).abs());
+        if dx.min(dy) < thickness * 0.5 { 1.0 } else { -1.0 }
+    });
+    let n21 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let size: f32 = 1.2;
+        let thickness: f32 = 0.15;
+        let [x, y] = [point[0] / size, point[1] / size];
+        let modulo = |value: f32, modulus: f32| value - modulus * (value / modulus).floor();
+        // the centers lie on two rectangular grids, the second one offset by half a rectangle
+        let a = [modulo(x, 1.0) - 0.5, modulo(y, 1.7320508) - 0.8660254];
+        let b = [
+            modulo(x - 0.5, 1.0) - 0.5,
+            modulo(y - 0.8660254, 1.7320508) - 0.8660254,
+        ];
+        let [x, y] = if a[0] * a[0] + a[1] * a[1] < b[0] * b[0] + b[1] * b[1] { a } else { b };
+        // the distance to the center in the hexagon's metric, 0.5 at the sides
+        let distance = x.abs().max(x.abs() * 0.5 + y.abs() * 0.8660254);
+        if (0.5 - distance) * size < thickness * 0.5 { 1.0 } else { -1.0 }
+    });
+    let n22 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let x: f32 = 0.5;
+        let y: f32 = 0.5;
+        let radius: f32 = 4.0;
+        let exponent: f32 = 1.5;
+        let x = ((point[0] - x) / radius).abs();
+        let y = ((point[1] - y) / radius).abs();
+        // the distance from the center in the shape's metric, 1 at its edge
+        let distance = x.hypot(y);
+        1.0 - distance.clamp(0.0, 1.0).powf(exponent)
+    });
+    let n23 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let x: f32 = 0.5;
+        let y: f32 = 0.5;
+        let radius: f32 = 4.0;
+        let exponent: f32 = 1.5;
+        let x = ((point[0] - x) / radius).abs();
+        let y = ((point[1] - y) / radius).abs();
+        // the distance from the center in the shape's metric, 1 at its edge
+        let distance = x.max(y);
+        distance.clamp(0.0, 1.0).powf(exponent)
+    });
+    let n24 = NoiseFn(move |point: [f32; 2], _seed: i32| -> f32 {
+        let x: f32 = 0.5;
+        let y: f32 = 0.5;
+        let radius: f32 = 4.0;
+        let exponent: f32 = 1.5;
+        let x = ((point[0] - x) / radius).abs();
+        let y = ((point[1] - y) / radius).abs();
+        // the distance from the center in the shape's metric, 1 at its edge
+        let [x, y] = [x - 0.5, y - 0.5];
+        let distance = x.max(0.0).hypot(y.max(0.0)) + x.max(y).min(0.0) + 0.5;
+        1.0 - distance.clamp(0.0, 1.0).powf(exponent)
+    });
+    let n25 = n9.neg();
+    let n26 = n9.abs();
+    let n27 = n26.sqrt();
+    let n28 = NoiseFn(|point: [f32; 2]| point[0]);
+    let n29 = n28.floor();
+    let n30 = NoiseFn(|point: [f32; 2]| point[1]);
+    let n31 = n30.ceil();
+    let n32 = n28.round();
+    let n33 = n9.sin();
+    let n34 = n9.cos();
+    let n35 = n9.tan();
+    let n36 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        value.exp()
+    });
+    let n37 = n9.add(Constant(2.0));
+    let n38 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n37.sample_with_seed(point, seed);
+        value.ln()
+    });
+    let n39 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n37.sample_with_seed(point, seed);
+        value.log2()
+    });
+    let n40 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n28.sample_with_seed(point, seed);
+        if value > 0.0 { 1.0 } else if value < 0.0 { -1.0 } else { value }
+    });
+    let n41 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n30.sample_with_seed(point, seed);
+        value - value.floor()
+    });
+    let n42 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n37.sample_with_seed(point, seed);
+        1.0 / value
+    });
+    let n43 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        1.0 - value
+    });
+    let n44 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n37.sample_with_seed(point, seed);
+        value.clamp(0.0, 1.0)
+    });
+    let n45 = noise_functions::Perlin;
+    let n46 = n45
+        .fbm(4, 0.5, 2.0)
+        .weighted(0.25);
+    let n47 = Constant(0.8);
+    let n48 = n46.frequency(n47);
+    let n49 = Constant(0.6);
+    let n50 = NoiseFn(move |point: [f32; 2], seed: i32| {
+        let jitter = n49.sample_with_seed(point, seed);
+        noise_functions::CellDistance { jitter }.sample_with_seed(point, seed)
+    });
+    let n51 = n48.add(n50);
+    let n52 = n51.translate_xy(n30, Constant(-1.5));
+    let n53 = n52.add_seed(3);
+    let n54 = n53.clamp(Constant(-0.5), Constant(0.75));
+    let n55 = n54.abs();
+    let n56 = n9.sub(n55);
+    let n57 = n9.div(Constant(4.0));
+    let n58 = n28.rem(Constant(0.7));
+    let n59 = n26.pow(Constant(1.5));
+    let n60 = n9.min(n55);
+    let n61 = n9.max(Constant(0.2));
+    let n62 = Constant(0.5);
+    let n63 = n28.mul(Constant(0.25));
+    let n64 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let edge: f32 = n62.sample_with_seed(point, seed);
+        let value: f32 = n63.sample_with_seed(point, seed);
+        if value < edge { 0.0 } else { 1.0 }
+    });
+    let n65 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let y: f32 = n9.sample_with_seed(point, seed);
+        let x: f32 = n37.sample_with_seed(point, seed);
+        y.atan2(x)
+    });
+    let n66 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let x: f32 = n9.sample_with_seed(point, seed);
+        let y: f32 = 0.5;
+        x.hypot(y)
+    });
+    let n67 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n9.sample_with_seed(point, seed);
+        let b: f32 = n55.sample_with_seed(point, seed);
+        let t: f32 = 0.3;
+        a + t * (b - a)
+    });
+    let n68 = Constant(-1.0);
+    let n69 = Constant(1.0);
+    let n70 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n68.sample_with_seed(point, seed);
+        let b: f32 = n69.sample_with_seed(point, seed);
+        let value: f32 = n9.sample_with_seed(point, seed);
+        (value - a) / (b - a)
+    });
+    let n71 = Constant(-0.5);
+    let n72 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let edge0: f32 = n71.sample_with_seed(point, seed);
+        let edge1: f32 = n62.sample_with_seed(point, seed);
+        let value: f32 = n9.sample_with_seed(point, seed);
+        let t = ((value - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    });
+    let n73 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let edge0: f32 = n71.sample_with_seed(point, seed);
+        let edge1: f32 = n62.sample_with_seed(point, seed);
+        let value: f32 = n9.sample_with_seed(point, seed);
+        let t = ((value - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+    });
+    let n74 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n9.sample_with_seed(point, seed);
+        let b: f32 = n55.sample_with_seed(point, seed);
+        let k: f32 = 0.3;
+        if k <= 0.0 {
+            a.min(b)
+        } else {
+            let h = (k - (a - b).abs()).max(0.0) / k;
+            a.min(b) - h * h * k * 0.25
+        }
+    });
+    let n75 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n9.sample_with_seed(point, seed);
+        let b: f32 = 0.1;
+        let k: f32 = 0.5;
+        if k <= 0.0 {
+            a.max(b)
+        } else {
+            let h = (k - (a - b).abs()).max(0.0) / k;
+            a.max(b) + h * h * k * 0.25
+        }
+    });
+    let n76 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        let from_min: f32 = -1.0;
+        let from_max: f32 = 1.0;
+        let to_min: f32 = 0.0;
+        let to_max: f32 = 2.0;
+        let t = (value - from_min) / (from_max - from_min);
+        let t = t.clamp(0.0, 1.0);
+        to_min + t * (to_max - to_min)
+    });
+    let n77 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        let [from_min, from_max, to_min, to_max]: [f32; 4] = [-0.5, 0.5, -1.0, 1.0];
+        let t = (value - from_min) / (from_max - from_min);
+        to_min + t * (to_max - to_min)
+    });
+    let n78 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n9.sample_with_seed(point, seed);
+        let b: f32 = 0.2;
+        let c: f32 = 0.5;
+        ((a * f32::sin(point[0] * 2.0)) + (f32::max(b, point[1]) * c)) - ((seed as f32) * 0.01)
+    });
+    let n79 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        // the start and end points and the slopes there
+        let segments: [[f32; 6]; 3] = [
+            [-1.0, -1.0, -0.2, 0.4, 1.75, 1.75],
+            [-0.2, 0.4, 0.3, 0.1, -0.6, -0.6],
+            [0.3, 0.1, 1.0, 0.5, 0.5714286, 0.5714286],
+        ];
+        segments.iter().fold(-1.0, |before, &[x0, y0, x1, y1, m0, m1]| {
+            if value < x0 {
+                return before;
+            }
+            let dx = x1 - x0;
+            let t = ((value - x0) / dx).min(1.0);
+            let t2 = t * t;
+            let t3 = t2 * t;
+            (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                + (t3 - 2.0 * t2 + t) * dx * m0
+                + (3.0 * t2 - 2.0 * t3) * y1
+                + (t3 - t2) * dx * m1
+        })
+    });
+    let n80 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        // the start and end points and the slopes there
+        let segments: [[f32; 6]; 3] = [
+            [-1.0, -1.0, -0.2, 0.4, 1.75, 0.8461539],
+            [-0.2, 0.4, 0.3, 0.1, 0.8461539, 0.08333333],
+            [0.3, 0.1, 1.0, 0.5, 0.08333333, 0.5714286],
+        ];
+        segments.iter().fold(-1.0, |before, &[x0, y0, x1, y1, m0, m1]| {
+            if value < x0 {
+                return before;
+            }
+            let dx = x1 - x0;
+            let t = ((value - x0) / dx).min(1.0);
+            let t2 = t * t;
+            let t3 = t2 * t;
+            (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                + (t3 - 2.0 * t2 + t) * dx * m0
+                + (3.0 * t2 - 2.0 * t3) * y1
+                + (t3 - t2) * dx * m1
+        })
+    });
+    let n81 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        // the start and end points and the slopes there
+        let segments: [[f32; 6]; 3] = [
+            [-1.0, -1.0, -0.2, 0.4, 1.75, 0.0],
+            [-0.2, 0.4, 0.3, 0.1, 0.0, 0.0],
+            [0.3, 0.1, 1.0, 0.5, 0.0, 0.5714286],
+        ];
+        segments.iter().fold(-1.0, |before, &[x0, y0, x1, y1, m0, m1]| {
+            if value < x0 {
+                return before;
+            }
+            let dx = x1 - x0;
+            let t = ((value - x0) / dx).min(1.0);
+            let t2 = t * t;
+            let t3 = t2 * t;
+            (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                + (t3 - 2.0 * t2 + t) * dx * m0
+                + (3.0 * t2 - 2.0 * t3) * y1
+                + (t3 - t2) * dx * m1
+        })
+    });
+    let n82 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n9.sample_with_seed(point, seed);
+        let heights: [f32; 5] = [-1.0, -0.5, 0.0, 0.5, 1.0];
+        heights.windows(2).fold(heights[0], |sum, pair| {
+            let t = ((value - pair[0]) / (pair[1] - pair[0])).clamp(0.0, 1.0);
+            let shaped = t.powf(2.0);
+            sum + shaped * (pair[1] - pair[0])
+        })
+    });
+    let n83 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n63.sample_with_seed(point, seed);
+        let heights: [f32; 3] = [-0.5, 0.0, 0.7];
+        heights.windows(2).fold(heights[0], |sum, pair| {
+            let t = ((value - pair[0]) / (pair[1] - pair[0])).clamp(0.0, 1.0);
+            let t = 1.0 - t;
+            let shaped = if t < 1.0 { 0.0 } else { 1.0 };
+            let shaped = 1.0 - shaped;
+            sum + shaped * (pair[1] - pair[0])
+        })
+    });
+    let n84 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value: f32 = n63.sample_with_seed(point, seed);
+        let min: f32 = -1.0;
+        let max: f32 = 1.0;
+        let t = ((value - min) / (max - min)).clamp(0.0, 1.0);
+        let level = (t * 5.0).floor().min(4.0);
+        min + level / 4.0 * (max - min)
+    });
+    let n85 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n9.sample_with_seed(point, seed);
+        let b: f32 = n55.sample_with_seed(point, seed);
+        let control: f32 = n63.sample_with_seed(point, seed);
+        let threshold: f32 = 0.0;
+        let weight = if control < threshold { 0.0 } else { 1.0 };
+        a + weight * (b - a)
+    });
+    let n86 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n9.sample_with_seed(point, seed);
+        let b: f32 = n55.sample_with_seed(point, seed);
+        let control: f32 = n63.sample_with_seed(point, seed);
+        let threshold: f32 = 0.2;
+        let falloff: f32 = 0.5;
+        let half = falloff * 0.5;
+        let [edge0, edge1] = [threshold - half, threshold + half];
+        let t = ((control - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        let weight = t * t * (3.0 - 2.0 * t);
+        a + weight * (b - a)
+    });
+    let n87 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let a: f32 = n9.sample_with_seed(point, seed);
+        let b: f32 = n55.sample_with_seed(point, seed);
+        let weight: f32 = 0.3;
+        let weight = weight.clamp(0.0, 1.0);
+        a + weight * (b - a)
+    });
+    let n88 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value0: f32 = n9.sample_with_seed(point, seed);
+        let value1: f32 = n55.sample_with_seed(point, seed);
+        value0 * value1
+    });
+    let n89 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value0: f32 = n9.sample_with_seed(point, seed);
+        let value1: f32 = n55.sample_with_seed(point, seed);
+        let value2: f32 = n30.sample_with_seed(point, seed);
+        value0.min(value1).min(value2)
+    });
+    let n90 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value0: f32 = n9.sample_with_seed(point, seed);
+        let value1: f32 = n55.sample_with_seed(point, seed);
+        let value2: f32 = n30.sample_with_seed(point, seed);
+        (value0 + value1 + value2) / 3.0
+    });
+    let n91 = Constant(-0.3);
+    let n92 = n91.abs();
+    let n93 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 1.0;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = x.hypot(y);
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        f1 - 1.0
+    });
+    let n94 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 1.0;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = x.hypot(y);
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        f2 - 1.0
+    });
+    let n95 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 1.0;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = x.hypot(y);
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        f2 - f1 - 1.0
+    });
+    let n96 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 1.0;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = x.hypot(y);
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        f1 * f2 * 0.5 - 1.0
+    });
+    let n97 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let jitter: f32 = 1.0;
+        let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+        let [xr, yr] = point.map(round);
+        let cell_jitter = 0.437_015_95 * jitter;
+        let mut f1 = 1e10_f32;
+        let mut f2 = 1e10_f32;
+        let mut nearest_hash = 0;
+
+        for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+            for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+                let hash = (seed ^ xi.wrapping_mul(501125321) ^ yi.wrapping_mul(1136930381))
+                    .wrapping_mul(668265261);
+                let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+                let x = xi as f32 - point[0] + angle.cos() * cell_jitter;
+                let y = yi as f32 - point[1] + angle.sin() * cell_jitter;
+                let distance = x.hypot(y);
+
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                    nearest_hash = hash;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+
+        nearest_hash as f32 * (1.0 / 2147483648.0)
+    });
+    let n98 = NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value0: f32 = n9.sample_with_seed(point, seed);
+        let value2: f32 = n55.sample_with_seed(point, seed);
+        value0.max(value2)
+    });
+
+    NoiseFn(move |point: [f32; 2], seed: i32| -> f32 {
+        let value0: f32 = n0.sample_with_seed(point, seed);
+        let value1: f32 = n1.sample_with_seed(point, seed);
+        let value2: f32 = n2.sample_with_seed(point, seed);
+        let value3: f32 = n3.sample_with_seed(point, seed);
+        let value4: f32 = n4.sample_with_seed(point, seed);
+        let value5: f32 = n5.sample_with_seed(point, seed);
+        let value6: f32 = n6.sample_with_seed(point, seed);
+        let value7: f32 = n7.sample_with_seed(point, seed);
+        let value8: f32 = n8.sample_with_seed(point, seed);
+        let value9: f32 = n10.sample_with_seed(point, seed);
+        let value10: f32 = n11.sample_with_seed(point, seed);
+        let value11: f32 = n12.sample_with_seed(point, seed);
+        let value12: f32 = n13.sample_with_seed(point, seed);
+        let value13: f32 = n14.sample_with_seed(point, seed);
+        let value14: f32 = n15.sample_with_seed(point, seed);
+        let value15: f32 = n16.sample_with_seed(point, seed);
+        let value16: f32 = n17.sample_with_seed(point, seed);
+        let value17: f32 = n18.sample_with_seed(point, seed);
+        let value18: f32 = n19.sample_with_seed(point, seed);
+        let value19: f32 = n20.sample_with_seed(point, seed);
+        let value20: f32 = n21.sample_with_seed(point, seed);
+        let value21: f32 = n22.sample_with_seed(point, seed);
+        let value22: f32 = n23.sample_with_seed(point, seed);
+        let value23: f32 = n24.sample_with_seed(point, seed);
+        let value24: f32 = n25.sample_with_seed(point, seed);
+        let value25: f32 = n27.sample_with_seed(point, seed);
+        let value26: f32 = n29.sample_with_seed(point, seed);
+        let value27: f32 = n31.sample_with_seed(point, seed);
+        let value28: f32 = n32.sample_with_seed(point, seed);
+        let value29: f32 = n33.sample_with_seed(point, seed);
+        let value30: f32 = n34.sample_with_seed(point, seed);
+        let value31: f32 = n35.sample_with_seed(point, seed);
+        let value32: f32 = n36.sample_with_seed(point, seed);
+        let value33: f32 = n38.sample_with_seed(point, seed);
+        let value34: f32 = n39.sample_with_seed(point, seed);
+        let value35: f32 = n40.sample_with_seed(point, seed);
+        let value36: f32 = n41.sample_with_seed(point, seed);
+        let value37: f32 = n42.sample_with_seed(point, seed);
+        let value38: f32 = n43.sample_with_seed(point, seed);
+        let value39: f32 = n44.sample_with_seed(point, seed);
+        let value40: f32 = n56.sample_with_seed(point, seed);
+        let value41: f32 = n57.sample_with_seed(point, seed);
+        let value42: f32 = n58.sample_with_seed(point, seed);
+        let value43: f32 = n59.sample_with_seed(point, seed);
+        let value44: f32 = n60.sample_with_seed(point, seed);
+        let value45: f32 = n61.sample_with_seed(point, seed);
+        let value46: f32 = n64.sample_with_seed(point, seed);
+        let value47: f32 = n65.sample_with_seed(point, seed);
+        let value48: f32 = n66.sample_with_seed(point, seed);
+        let value49: f32 = n67.sample_with_seed(point, seed);
+        let value50: f32 = n70.sample_with_seed(point, seed);
+        let value51: f32 = n72.sample_with_seed(point, seed);
+        let value52: f32 = n73.sample_with_seed(point, seed);
+        let value53: f32 = n74.sample_with_seed(point, seed);
+        let value54: f32 = n75.sample_with_seed(point, seed);
+        let value55: f32 = n76.sample_with_seed(point, seed);
+        let value56: f32 = n77.sample_with_seed(point, seed);
+        let value57: f32 = n78.sample_with_seed(point, seed);
+        let value58: f32 = n79.sample_with_seed(point, seed);
+        let value59: f32 = n80.sample_with_seed(point, seed);
+        let value60: f32 = n81.sample_with_seed(point, seed);
+        let value61: f32 = n82.sample_with_seed(point, seed);
+        let value62: f32 = n83.sample_with_seed(point, seed);
+        let value63: f32 = n84.sample_with_seed(point, seed);
+        let value64: f32 = n85.sample_with_seed(point, seed);
+        let value65: f32 = n86.sample_with_seed(point, seed);
+        let value66: f32 = n87.sample_with_seed(point, seed);
+        let value67: f32 = n88.sample_with_seed(point, seed);
+        let value68: f32 = n89.sample_with_seed(point, seed);
+        let value69: f32 = n90.sample_with_seed(point, seed);
+        let value70: f32 = n92.sample_with_seed(point, seed);
+        let value71: f32 = n93.sample_with_seed(point, seed);
+        let value72: f32 = n94.sample_with_seed(point, seed);
+        let value73: f32 = n95.sample_with_seed(point, seed);
+        let value74: f32 = n96.sample_with_seed(point, seed);
+        let value75: f32 = n97.sample_with_seed(point, seed);
+        let value76: f32 = n98.sample_with_seed(point, seed);
+        let value77: f32 = n55.sample_with_seed(point, seed);
+        value0 * 0.5 + value1 + value2 + value3 * 0.5 + value4 + value5 + value6 * 0.5 + value7 + value8 + value9 * 0.5 + value10 + value11 + value12 * 0.5 + value13 + value14 + value15 * 0.5 + value16 + value17 + value18 * 0.5 + value19 + value20 + value21 * 0.5 + value22 + value23 + value24 * 0.5 + value25 + value26 + value27 * 0.5 + value28 + value29 + value30 * 0.5 + value31 + value32 + value33 * 0.5 + value34 + value35 + value36 * 0.5 + value37 + value38 + value39 * 0.5 + value40 + value41 + value42 * 0.5 + value43 + value44 + value45 * 0.5 + value46 + value47 + value48 * 0.5 + value49 + value50 + value51 * 0.5 + value52 + value53 + value54 * 0.5 + value55 + value56 + value57 * 0.5 + value58 + value59 + value60 * 0.5 + value61 + value62 + value63 * 0.5 + value64 + value65 + value66 * 0.5 + value67 + value68 + value69 * 0.5 + value70 + value71 + value72 * 0.5 + value73 + value74 + value75 * 0.5 + value76 + value77
+    })
+}
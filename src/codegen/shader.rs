@@ -0,0 +1,705 @@
+//! Generates WGSL or GLSL functions from a [`Program`].
+//!
+//! Every op becomes a function `noise_op{index}(p, seed)`, the entry point is `noise(p, seed)`.
+//! The noise primitives are defined in the prelude that is part of the output.
+
+use std::fmt;
+
+use crate::graph::{
+    ir::{self, BinaryOp, CellKind, CellularOutput, Op, Primitive, Program, TernaryOp, UnaryOp},
+    CellMetric,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Language {
+    Wgsl,
+    Glsl,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The noise has no shader implementation.
+    Unsupported(&'static str),
+    /// Infinity and NaN can't be written as literals.
+    NonFinite(f32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Unsupported(name) => write!(f, "{name} noise is not supported in shaders"),
+            Error::NonFinite(value) => write!(f, "{value} can't be used in shaders"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Copy)]
+enum Type {
+    Float,
+    Int,
+    Vec2,
+    Vec3,
+}
+
+impl Language {
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Wgsl => "WGSL",
+            Language::Glsl => "GLSL",
+        }
+    }
+
+    fn prelude(self) -> &'static str {
+        match self {
+            Language::Wgsl => include_str!("shader/noise.wgsl"),
+            Language::Glsl => include_str!("shader/noise.glsl"),
+        }
+    }
+
+    fn function(self, name: &str, body: &[String]) -> String {
+        let mut code = match self {
+            Language::Wgsl => format!("fn {name}(p: vec2<f32>, seed: i32) -> f32 {{\n"),
+            Language::Glsl => format!("float {name}(vec2 p, int seed) {{\n"),
+        };
+
+        for line in body {
+            code.push_str(&format!("    {line}\n"));
+        }
+
+        code.push_str("}\n");
+        code
+    }
+
+    fn declare(self, mutable: bool, ty: Type, name: &str, expr: &str) -> String {
+        match self {
+            Language::Wgsl => {
+                let keyword = if mutable { "var" } else { "let" };
+                format!("{keyword} {name} = {expr};")
+            }
+            Language::Glsl => {
+                let ty = match ty {
+                    Type::Float => "float",
+                    Type::Int => "int",
+                    Type::Vec2 => "vec2",
+                    Type::Vec3 => "vec3",
+                };
+                format!("{ty} {name} = {expr};")
+            }
+        }
+    }
+
+    fn float(self, value: f32) -> Result<String, Error> {
+        if !value.is_finite() {
+            return Err(Error::NonFinite(value));
+        }
+
+        // `{:?}` always writes a decimal point or an exponent
+        if value < 0.0 {
+            Ok(format!("({value:?})"))
+        } else {
+            Ok(format!("{value:?}"))
+        }
+    }
+
+    fn int(self, value: i32) -> String {
+        match value {
+            // the positive literal would overflow before being negated
+            i32::MIN => "(-2147483647 - 1)".into(),
+            _ if value < 0 => format!("({value})"),
+            _ => value.to_string(),
+        }
+    }
+
+    fn rem(self, lhs: &str, rhs: &str) -> String {
+        match self {
+            Language::Wgsl => format!("{lhs} % {rhs}"),
+            Language::Glsl => format!("noise_rem({lhs}, {rhs})"),
+        }
+    }
+
+    fn atan2(self, y: &str, x: &str) -> String {
+        match self {
+            Language::Wgsl => format!("atan2({y}, {x})"),
+            Language::Glsl => format!("atan({y}, {x})"),
+        }
+    }
+}
+
+/// Returns the prelude, a function for every op and the `noise` entry point.
+pub fn generate(program: &Program, language: Language) -> Result<String, Error> {
+    let mut code = String::new();
+    code.push_str(language.prelude());
+
+    for (index, op) in program.ops.iter().enumerate() {
+        code.push('\n');
+        code.push_str(&language.function(&op_name(index), &body(*op, language)?));
+    }
+
+    code.push('\n');
+    code.push_str(&language.function(
+        "noise",
+        &[format!("return {}(p, seed);", op_name(program.output))],
+    ));
+
+    Ok(code)
+}
+
+fn op_name(index: usize) -> String {
+    format!("noise_op{index}")
+}
+
+/// Calls the op with the same point and seed.
+fn call(index: usize) -> String {
+    format!("{}(p, seed)", op_name(index))
+}
+
+fn body(op: Op, language: Language) -> Result<Vec<String>, Error> {
+    let float = |value: f32| language.float(value);
+
+    let expr = match op {
+        Op::Constant(value) => float(value)?,
+        Op::Position(axis) => ["p.x", "p.y"][axis].into(),
+        Op::SeedValue => match language {
+            Language::Wgsl => "f32(seed)".into(),
+            Language::Glsl => "float(seed)".into(),
+        },
+        Op::Noise(primitive) => match primitive {
+            Primitive::Value => "noise_value(p, seed)".into(),
+            Primitive::ValueCubic => "noise_value_cubic(p, seed)".into(),
+            Primitive::Perlin => "noise_perlin(p, seed)".into(),
+            Primitive::Simplex => return Err(Error::Unsupported("Simplex")),
+            Primitive::OpenSimplex2 => return Err(Error::Unsupported("OpenSimplex2")),
+            Primitive::OpenSimplex2s => return Err(Error::Unsupported("OpenSimplex2s")),
+        },
+        Op::Cell { kind, jitter } => {
+            let function = match kind {
+                CellKind::Value => "noise_cell_value",
+                CellKind::Distance => "noise_cell_distance",
+                CellKind::DistanceSq => "noise_cell_distance_sq",
+            };
+
+            format!("{function}(p, seed, {})", call(jitter))
+        }
+        Op::Cellular {
+            metric,
+            output,
+            jitter,
+            exponent,
+        } => {
+            // the numbering of `noise_cell_metric`
+            let metric = match metric {
+                CellMetric::Euclidean => 0,
+                CellMetric::Manhattan => 1,
+                CellMetric::Chebyshev => 2,
+                CellMetric::Minkowski => 3,
+            };
+
+            let value = match output {
+                CellularOutput::F1 => "cells.x - 1.0",
+                CellularOutput::F2 => "cells.y - 1.0",
+                CellularOutput::Difference => "cells.y - cells.x - 1.0",
+                CellularOutput::Product => "cells.x * cells.y * 0.5 - 1.0",
+                CellularOutput::Value => "cells.z",
+            };
+
+            return Ok(vec![
+                language.declare(
+                    false,
+                    Type::Vec3,
+                    "cells",
+                    &format!(
+                        "noise_cellular(p, seed, {}, {metric}, {})",
+                        call(jitter),
+                        call(exponent)
+                    ),
+                ),
+                format!("return {value};"),
+            ]);
+        }
+        Op::Fractal {
+            input,
+            octaves,
+            gain,
+            lacunarity,
+            weighted_strength,
+        } => {
+            let declare = |mutable, ty, name: &str, expr: &str| {
+                format!("    {}", language.declare(mutable, ty, name, expr))
+            };
+
+            let amplitude = float(ir::fractal_bounding(octaves, gain))?;
+
+            let header = match language {
+                Language::Wgsl => format!("for (var i = 0u; i < {octaves}u; i++) {{"),
+                Language::Glsl => format!("for (uint i = 0u; i < {octaves}u; i++) {{"),
+            };
+
+            return Ok(vec![
+                language.declare(true, Type::Float, "total", "0.0"),
+                language.declare(true, Type::Float, "amplitude", &amplitude),
+                language.declare(true, Type::Vec2, "point", "p"),
+                language.declare(true, Type::Int, "octave_seed", "seed"),
+                header,
+                declare(
+                    false,
+                    Type::Float,
+                    "value",
+                    &format!("{}(point, octave_seed)", op_name(input)),
+                ),
+                "    octave_seed += 1;".into(),
+                "    total += value * amplitude;".into(),
+                format!(
+                    "    amplitude *= mix(1.0, min(value + 1.0, 2.0) * 0.5, {});",
+                    float(weighted_strength)?
+                ),
+                format!("    point *= {};", float(lacunarity)?),
+                format!("    amplitude *= {};", float(gain)?),
+                "}".into(),
+                "return total;".into(),
+            ]);
+        }
+        Op::Frequency { input, frequency } => {
+            format!("{}(p * {}, seed)", op_name(input), call(frequency))
+        }
+        Op::TriangleWave { input, frequency } => {
+            return Ok(vec![
+                language.declare(
+                    false,
+                    Type::Float,
+                    "value",
+                    &format!("{} * {}", call(input), call(frequency)),
+                ),
+                "return abs(value - floor(value + 0.5)) * 4.0 - 1.0;".into(),
+            ]);
+        }
+        Op::Translate { input, x, y } => format!(
+            "{}(p + vec2({}, {}), seed)",
+            op_name(input),
+            call(x),
+            call(y)
+        ),
+        Op::Unary(op, input) => {
+            let x = call(input);
+
+            match op {
+                UnaryOp::Abs => format!("abs({x})"),
+                UnaryOp::Neg => format!("-{x}"),
+                UnaryOp::Sqrt => format!("sqrt({x})"),
+                UnaryOp::Floor => format!("floor({x})"),
+                UnaryOp::Ceil => format!("ceil({x})"),
+                UnaryOp::Round => format!("noise_round({x})"),
+                UnaryOp::Sin => format!("sin({x})"),
+                UnaryOp::Cos => format!("cos({x})"),
+                UnaryOp::Tan => format!("tan({x})"),
+                UnaryOp::Exp => format!("exp({x})"),
+                UnaryOp::Ln => format!("log({x})"),
+                UnaryOp::Log2 => format!("log2({x})"),
+                UnaryOp::Sign => format!("sign({x})"),
+                UnaryOp::Fract => format!("fract({x})"),
+                UnaryOp::Reciprocal => format!("1.0 / {x}"),
+                UnaryOp::OneMinus => format!("1.0 - {x}"),
+                UnaryOp::Saturate => format!("clamp({x}, 0.0, 1.0)"),
+            }
+        }
+        Op::Binary(op, lhs, rhs) => {
+            let (a, b) = (call(lhs), call(rhs));
+
+            match op {
+                BinaryOp::Add => format!("{a} + {b}"),
+                BinaryOp::Sub => format!("{a} - {b}"),
+                BinaryOp::Mul => format!("{a} * {b}"),
+                BinaryOp::Div => format!("{a} / {b}"),
+                BinaryOp::Rem => language.rem(&a, &b),
+                BinaryOp::Pow => format!("pow({a}, {b})"),
+                BinaryOp::Min => format!("min({a}, {b})"),
+                BinaryOp::Max => format!("max({a}, {b})"),
+                BinaryOp::Step => format!("step({a}, {b})"),
+                BinaryOp::Atan2 => language.atan2(&a, &b),
+                BinaryOp::Hypot => format!("length(vec2({a}, {b}))"),
+            }
+        }
+        Op::Ternary(op, a, b, c) => {
+            let (a, b, c) = (call(a), call(b), call(c));
+
+            match op {
+                TernaryOp::Clamp => format!("min(max({a}, {b}), {c})"),
+                TernaryOp::Lerp => format!("noise_lerp({a}, {b}, {c})"),
+                TernaryOp::InverseLerp => format!("noise_inverse_lerp({a}, {b}, {c})"),
+                TernaryOp::Smoothstep => format!("noise_smoothstep({a}, {b}, {c})"),
+                TernaryOp::Smootherstep => format!("noise_smootherstep({a}, {b}, {c})"),
+                TernaryOp::SmoothMin => format!("noise_smooth_min({a}, {b}, {c})"),
+                TernaryOp::SmoothMax => format!("noise_smooth_max({a}, {b}, {c})"),
+            }
+        }
+        Op::Spline {
+            input,
+            before,
+            segment,
+        } => {
+            let [x0, y0] = segment.from;
+            let [x1, y1] = segment.to;
+            let [m0, m1] = segment.slopes;
+            let args: Result<Vec<String>, Error> =
+                [x0, y0, x1, y1, m0, m1].into_iter().map(float).collect();
+
+            format!(
+                "noise_spline({}, {}, {})",
+                call(input),
+                call(before),
+                args?.join(", ")
+            )
+        }
+        Op::Seed { input, seed } => format!("{}(p, {})", op_name(input), language.int(seed)),
+        Op::AddSeed { input, add } => {
+            format!("{}(p, seed + {})", op_name(input), language.int(add))
+        }
+        Op::MulSeed { input, mul } => {
+            format!("{}(p, seed * {})", op_name(input), language.int(mul))
+        }
+    };
+
+    Ok(vec![format!("return {expr};")])
+}
+
+#[cfg(test)]
+mod tests {
+    use noise_functions::Sample;
+
+    use super::{generate, Language};
+    use crate::graph::{
+        curve::{Curve, Interpolation},
+        ir::Program,
+        CellMetric, Graph, Group, InPinId, Node, NodeId, OutPinId, Sampler,
+    };
+
+    fn connect(graph: &mut Graph, from: NodeId, output: usize, to: NodeId, input: usize) {
+        graph.connect(OutPinId { node: from, output }, InPinId { node: to, input });
+    }
+
+    /// A graph using every op the shaders support, and a shared input.
+    fn example_graph() -> (Graph, OutPinId) {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        let perlin = graph.insert_node(pos, Node::Perlin);
+        let value = graph.insert_node(pos, Node::Value);
+        let value_cubic = graph.insert_node(pos, Node::ValueCubic);
+        let fractal = graph.insert_node(
+            pos,
+            Node::Fractal {
+                octaves: 3,
+                gain: 0.5,
+                lacunarity: 2.0,
+                weighted_strength: 0.5,
+            },
+        );
+        let position = graph.insert_node(pos, Node::Position);
+        let frequency = graph.insert_node(pos, Node::Frequency { frequency: 1.0 });
+        let cell = graph.insert_node(pos, Node::CellValue { jitter: 0.8 });
+        let cell_distance = graph.insert_node(pos, Node::CellDistance { jitter: 1.0 });
+        let cell_distance_sq = graph.insert_node(pos, Node::CellDistanceSq { jitter: 1.0 });
+        let translate = graph.insert_node(pos, Node::TranslateXy { x: 0.25, y: -3.0 });
+        let triangle = graph.insert_node(pos, Node::TriangleWave { frequency: 2.0 });
+        let seed = graph.insert_node(pos, Node::Seed { seed: -7 });
+        let add_seed = graph.insert_node(pos, Node::AddSeed { add: 5 });
+        let mul_seed = graph.insert_node(pos, Node::MulSeed { mul: 3 });
+        let clamp = graph.insert_node(
+            pos,
+            Node::Clamp {
+                value: 0.0,
+                min: -0.75,
+                max: 0.5,
+            },
+        );
+
+        let mut last = fractal;
+        connect(&mut graph, perlin, 0, fractal, 0);
+        connect(&mut graph, value, 0, add_seed, 0);
+        connect(&mut graph, value_cubic, 0, mul_seed, 0);
+        connect(&mut graph, cell, 0, seed, 0);
+        connect(&mut graph, position, 0, frequency, 1);
+        connect(&mut graph, cell_distance, 0, frequency, 0);
+        connect(&mut graph, position, 1, translate, 1);
+        connect(&mut graph, cell_distance_sq, 0, translate, 0);
+        connect(&mut graph, add_seed, 0, triangle, 0);
+
+        let abs = graph.insert_node(pos, Node::Abs);
+        connect(&mut graph, fractal, 0, abs, 0);
+
+        let unary = [
+            Node::Neg,
+            Node::Sqrt,
+            Node::Floor,
+            Node::Ceil,
+            Node::Round,
+            Node::Sin,
+            Node::Cos,
+            Node::Tan,
+            Node::Exp,
+            Node::Ln,
+            Node::Log2,
+            Node::Sign,
+            Node::Fract,
+            Node::Reciprocal,
+            Node::OneMinus,
+            Node::Saturate,
+        ];
+
+        for node in unary {
+            let node = graph.insert_node(pos, node);
+            let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+            // roots and logarithms of the absolute value so they don't turn everything into NaN
+            connect(&mut graph, abs, 0, node, 0);
+            connect(&mut graph, last, 0, add, 0);
+            connect(&mut graph, node, 0, add, 1);
+            last = add;
+        }
+
+        let binary = [
+            Node::Sub { lhs: 0.0, rhs: 0.0 },
+            Node::Mul { lhs: 0.0, rhs: 0.0 },
+            Node::Div { lhs: 0.0, rhs: 2.0 },
+            Node::Rem { lhs: 0.0, rhs: 0.0 },
+            Node::Min { lhs: 0.0, rhs: 0.0 },
+            Node::Max { lhs: 0.0, rhs: 0.0 },
+        ];
+
+        let others = [seed, mul_seed, frequency, translate, value, cell];
+
+        for (node, other) in binary.into_iter().zip(others) {
+            let node = graph.insert_node(pos, node);
+            connect(&mut graph, last, 0, node, 0);
+            connect(&mut graph, other, 0, node, 1);
+            last = node;
+        }
+
+        let pow = graph.insert_node(pos, Node::Pow { lhs: 0.0, rhs: 0.0 });
+        let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+        connect(&mut graph, abs, 0, pow, 0);
+        connect(&mut graph, triangle, 0, pow, 1);
+        connect(&mut graph, last, 0, add, 0);
+        connect(&mut graph, pow, 0, add, 1);
+        last = add;
+
+        let ternary = [
+            Node::Lerp {
+                a: 0.0,
+                b: 0.5,
+                t: 0.0,
+            },
+            Node::InverseLerp {
+                a: 0.0,
+                b: 2.0,
+                value: 0.0,
+            },
+            Node::Smoothstep {
+                edge0: 0.0,
+                edge1: 1.0,
+                value: 0.0,
+            },
+            Node::Smootherstep {
+                edge0: 0.0,
+                edge1: 1.0,
+                value: 0.0,
+            },
+            Node::SmoothMin {
+                lhs: 0.0,
+                rhs: 0.0,
+                k: 0.5,
+            },
+            Node::SmoothMax {
+                lhs: 0.0,
+                rhs: 0.0,
+                k: 0.5,
+            },
+        ];
+
+        for node in ternary {
+            let node = graph.insert_node(pos, node);
+            connect(&mut graph, triangle, 0, node, 0);
+            connect(&mut graph, last, 0, node, 2);
+            last = node;
+        }
+
+        let step = graph.insert_node(
+            pos,
+            Node::Step {
+                edge: 0.0,
+                value: 0.0,
+            },
+        );
+        let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+        connect(&mut graph, value, 0, step, 1);
+        connect(&mut graph, last, 0, add, 0);
+        connect(&mut graph, step, 0, add, 1);
+        last = add;
+
+        for node in [
+            Node::Atan2 { y: 0.0, x: 0.0 },
+            Node::Hypot { x: 0.0, y: 0.0 },
+        ] {
+            let node = graph.insert_node(pos, node);
+            let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+            connect(&mut graph, last, 0, node, 0);
+            connect(&mut graph, cell, 0, node, 1);
+            connect(&mut graph, last, 0, add, 0);
+            connect(&mut graph, node, 0, add, 1);
+            last = add;
+        }
+
+        for metric in CellMetric::ALL {
+            let cellular = graph.insert_node(
+                pos,
+                Node::Cellular {
+                    metric,
+                    jitter: 0.9,
+                    exponent: 1.5,
+                },
+            );
+
+            for output in 0..5 {
+                let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+                connect(&mut graph, last, 0, add, 0);
+                connect(&mut graph, cellular, output, add, 1);
+                last = add;
+            }
+        }
+
+        let curve = graph.insert_node(
+            pos,
+            Node::Curve(Curve {
+                points: vec![[-1.0, 0.5], [0.0, -0.5], [1.0, 1.0]],
+                interpolation: Interpolation::Cubic,
+            }),
+        );
+        connect(&mut graph, last, 0, curve, 0);
+        last = curve;
+
+        connect(&mut graph, last, 0, clamp, 0);
+
+        let mut inner = Graph::new();
+        let input = inner.insert_node(
+            pos,
+            Node::GroupInput {
+                name: "Input".into(),
+                value: 0.0,
+            },
+        );
+        let output = inner.insert_node(
+            pos,
+            Node::GroupOutput {
+                name: "Output".into(),
+            },
+        );
+        connect(&mut inner, input, 0, output, 0);
+
+        let identity = graph.groups_mut().insert(Group::new("Group".into(), inner));
+        let group = graph.insert_node(
+            pos,
+            Node::Group {
+                group: identity,
+                values: Vec::new(),
+            },
+        );
+        connect(&mut graph, clamp, 0, group, 0);
+
+        (
+            graph,
+            OutPinId {
+                node: group,
+                output: 0,
+            },
+        )
+    }
+
+    #[test]
+    fn program_matches_graph() {
+        let (graph, pin) = example_graph();
+        let program = Program::new(&graph, pin).unwrap();
+        let noise = Sampler::new(&graph, pin).unwrap();
+
+        for seed in [0, 1, 42] {
+            for y in -16..16 {
+                for x in -16..16 {
+                    let point = [x as f32 * 0.37, y as f32 * 0.29];
+                    let expected = noise.sample_with_seed(point, seed);
+                    let actual = program.sample(point, seed);
+
+                    // divisions can make the values large
+                    let tolerance = 1e-5 * expected.abs().max(1.0);
+
+                    assert!(
+                        (expected - actual).abs() <= tolerance
+                            || expected.is_nan() && actual.is_nan(),
+                        "{expected} != {actual} at {point:?} with seed {seed}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shared_inputs_are_lowered_once() {
+        let (graph, pin) = example_graph();
+        let program = Program::new(&graph, pin).unwrap();
+
+        let fractals = program
+            .ops
+            .iter()
+            .filter(|op| matches!(op, super::Op::Fractal { .. }))
+            .count();
+
+        assert_eq!(fractals, 1);
+    }
+
+    fn validate(module: &naga::Module) {
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(module)
+        .unwrap();
+    }
+
+    #[test]
+    fn wgsl_is_valid() {
+        let (graph, pin) = example_graph();
+        let program = Program::new(&graph, pin).unwrap();
+        let code = generate(&program, Language::Wgsl).unwrap();
+
+        let module = naga::front::wgsl::parse_str(&code)
+            .unwrap_or_else(|error| panic!("{}", error.emit_to_string(&code)));
+
+        validate(&module);
+    }
+
+    #[test]
+    fn glsl_is_valid() {
+        let (graph, pin) = example_graph();
+        let program = Program::new(&graph, pin).unwrap();
+        let code = generate(&program, Language::Glsl).unwrap();
+
+        // the generated functions are meant to be included in a shader
+        let shader = format!("#version 450\n\n{code}\nvoid main() {{}}\n");
+
+        let module = naga::front::glsl::Frontend::default()
+            .parse(
+                &naga::front::glsl::Options::from(naga::ShaderStage::Fragment),
+                &shader,
+            )
+            .unwrap_or_else(|errors| panic!("{}", errors.emit_to_string(&shader)));
+
+        validate(&module);
+    }
+
+    #[test]
+    fn unsupported_noise_is_an_error() {
+        let mut graph = Graph::new();
+        let node = graph.insert_node([0.0; 2], Node::Simplex);
+        let program = Program::new(&graph, OutPinId { node, output: 0 }).unwrap();
+
+        assert!(generate(&program, Language::Wgsl).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+// Noise primitives used by the generated code.
+//
+// The hashing and interpolation follow FastNoiseLite like the CPU noise does.
+// Gradients and cell offsets are computed from the hash instead of looked up in tables,
+// so Perlin and cell noise have the same character but not the same pattern as on the CPU.
+
+const int NOISE_PRIME_X = 501125321;
+const int NOISE_PRIME_Y = 1136930381;
+
+int noise_hash(int seed, int x, int y) {
+    return (seed ^ x ^ y) * 668265261;
+}
+
+float noise_val_coord(int seed, int x, int y) {
+    int hash = noise_hash(seed, x, y);
+    hash *= hash;
+    hash ^= hash << 19;
+    return float(hash) * (1.0 / 2147483648.0);
+}
+
+float noise_grad_coord(int seed, int x, int y, float xd, float yd) {
+    int hash = noise_hash(seed, x, y);
+    hash ^= hash >> 15;
+    float angle = float(hash & 127) * (6.2831855 / 128.0);
+    return xd * cos(angle) + yd * sin(angle);
+}
+
+float noise_interp_hermite(float t) {
+    return t * t * (3.0 - 2.0 * t);
+}
+
+float noise_interp_quintic(float t) {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+float noise_cubic_lerp(float a, float b, float c, float d, float t) {
+    float p = (d - c) - (a - b);
+    return t * t * t * p + t * t * ((a - b) - p) + t * (c - a) + b;
+}
+
+int noise_fast_round(float x) {
+    return int(x + (x >= 0.0 ? 0.5 : -0.5));
+}
+
+float noise_round(float x) {
+    return trunc(x + (x >= 0.0 ? 0.5 : -0.5));
+}
+
+float noise_rem(float a, float b) {
+    return a - b * trunc(a / b);
+}
+
+float noise_lerp(float a, float b, float t) {
+    return a + t * (b - a);
+}
+
+float noise_inverse_lerp(float a, float b, float value) {
+    return (value - a) / (b - a);
+}
+
+float noise_smoothstep(float edge0, float edge1, float value) {
+    float t = clamp((value - edge0) / (edge1 - edge0), 0.0, 1.0);
+    return t * t * (3.0 - 2.0 * t);
+}
+
+float noise_smootherstep(float edge0, float edge1, float value) {
+    float t = clamp((value - edge0) / (edge1 - edge0), 0.0, 1.0);
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+float noise_smooth_min(float a, float b, float k) {
+    if (k <= 0.0) {
+        return min(a, b);
+    }
+    float h = max(k - abs(a - b), 0.0) / k;
+    return min(a, b) - h * h * k * 0.25;
+}
+
+float noise_smooth_max(float a, float b, float k) {
+    if (k <= 0.0) {
+        return max(a, b);
+    }
+    float h = max(k - abs(a - b), 0.0) / k;
+    return max(a, b) + h * h * k * 0.25;
+}
+
+float noise_spline(float x, float before, float x0, float y0, float x1, float y1, float m0, float m1) {
+    if (x < x0) {
+        return before;
+    }
+    float dx = x1 - x0;
+    float t = min((x - x0) / dx, 1.0);
+    float t2 = t * t;
+    float t3 = t2 * t;
+    return (2.0 * t3 - 3.0 * t2 + 1.0) * y0 + (t3 - 2.0 * t2 + t) * dx * m0
+        + (3.0 * t2 - 2.0 * t3) * y1 + (t3 - t2) * dx * m1;
+}
+
+float noise_value(vec2 p, int seed) {
+    vec2 f = floor(p);
+    float sx = noise_interp_hermite(p.x - f.x);
+    float sy = noise_interp_hermite(p.y - f.y);
+    int x0 = int(f.x) * NOISE_PRIME_X;
+    int y0 = int(f.y) * NOISE_PRIME_Y;
+    int x1 = x0 + NOISE_PRIME_X;
+    int y1 = y0 + NOISE_PRIME_Y;
+    float xf0 = mix(noise_val_coord(seed, x0, y0), noise_val_coord(seed, x1, y0), sx);
+    float xf1 = mix(noise_val_coord(seed, x0, y1), noise_val_coord(seed, x1, y1), sx);
+    return mix(xf0, xf1, sy);
+}
+
+float noise_value_cubic_row(int seed, int x0, int y, float t) {
+    int x1 = x0 + NOISE_PRIME_X;
+    int x2 = x1 + NOISE_PRIME_X;
+    int x3 = x2 + NOISE_PRIME_X;
+    return noise_cubic_lerp(
+        noise_val_coord(seed, x0, y),
+        noise_val_coord(seed, x1, y),
+        noise_val_coord(seed, x2, y),
+        noise_val_coord(seed, x3, y),
+        t
+    );
+}
+
+float noise_value_cubic(vec2 p, int seed) {
+    vec2 f = floor(p);
+    vec2 s = p - f;
+    int x0 = (int(f.x) - 1) * NOISE_PRIME_X;
+    int y0 = (int(f.y) - 1) * NOISE_PRIME_Y;
+    int y1 = y0 + NOISE_PRIME_Y;
+    int y2 = y1 + NOISE_PRIME_Y;
+    int y3 = y2 + NOISE_PRIME_Y;
+    return noise_cubic_lerp(
+        noise_value_cubic_row(seed, x0, y0, s.x),
+        noise_value_cubic_row(seed, x0, y1, s.x),
+        noise_value_cubic_row(seed, x0, y2, s.x),
+        noise_value_cubic_row(seed, x0, y3, s.x),
+        s.y
+    ) * (1.0 / (1.5 * 1.5));
+}
+
+float noise_perlin(vec2 p, int seed) {
+    vec2 f = floor(p);
+    vec2 d0 = p - f;
+    vec2 d1 = d0 - 1.0;
+    float sx = noise_interp_quintic(d0.x);
+    float sy = noise_interp_quintic(d0.y);
+    int x0 = int(f.x) * NOISE_PRIME_X;
+    int y0 = int(f.y) * NOISE_PRIME_Y;
+    int x1 = x0 + NOISE_PRIME_X;
+    int y1 = y0 + NOISE_PRIME_Y;
+    float xf0 = mix(noise_grad_coord(seed, x0, y0, d0.x, d0.y), noise_grad_coord(seed, x1, y0, d1.x, d0.y), sx);
+    float xf1 = mix(noise_grad_coord(seed, x0, y1, d0.x, d1.y), noise_grad_coord(seed, x1, y1, d1.x, d1.y), sx);
+    return mix(xf0, xf1, sy) * 1.4247692;
+}
+
+// Returns the squared distance to the nearest cell point and the value of its cell.
+vec2 noise_cell(vec2 p, int seed, float jitter) {
+    int xr = noise_fast_round(p.x);
+    int yr = noise_fast_round(p.y);
+    float cell_jitter = 0.43701595 * jitter;
+    float nearest = 1e10;
+    int nearest_hash = 0;
+    int x_primed = (xr - 1) * NOISE_PRIME_X;
+    int y_primed_start = (yr - 1) * NOISE_PRIME_Y;
+
+    for (int xi = xr - 1; xi <= xr + 1; xi++) {
+        int y_primed = y_primed_start;
+
+        for (int yi = yr - 1; yi <= yr + 1; yi++) {
+            int hash = noise_hash(seed, x_primed, y_primed);
+            float angle = float((hash >> 1) & 255) * (6.2831855 / 256.0);
+            vec2 v = vec2(float(xi), float(yi)) - p + vec2(cos(angle), sin(angle)) * cell_jitter;
+            float d = dot(v, v);
+
+            if (d < nearest) {
+                nearest = d;
+                nearest_hash = hash;
+            }
+
+            y_primed += NOISE_PRIME_Y;
+        }
+
+        x_primed += NOISE_PRIME_X;
+    }
+
+    return vec2(nearest, float(nearest_hash) * (1.0 / 2147483648.0));
+}
+
+float noise_cell_value(vec2 p, int seed, float jitter) {
+    return noise_cell(p, seed, jitter).y;
+}
+
+float noise_cell_distance(vec2 p, int seed, float jitter) {
+    return sqrt(noise_cell(p, seed, jitter).x) - 1.0;
+}
+
+float noise_cell_distance_sq(vec2 p, int seed, float jitter) {
+    return noise_cell(p, seed, jitter).x - 1.0;
+}
+
+// The length of the vector in the metric of the cellular node:
+// 0 Euclidean, 1 Manhattan, 2 Chebyshev or 3 Minkowski with the exponent.
+float noise_cell_metric(vec2 v, int metric, float exponent) {
+    vec2 a = abs(v);
+
+    if (metric == 1) {
+        return a.x + a.y;
+    }
+    if (metric == 2) {
+        return max(a.x, a.y);
+    }
+    if (metric == 3) {
+        // CellMetric::MIN_EXPONENT
+        float e = max(exponent, 0.1);
+        return pow(pow(a.x, e) + pow(a.y, e), 1.0 / e);
+    }
+    return length(v);
+}
+
+// Returns the distances to the nearest and second nearest cell point and the value of the nearest cell.
+vec3 noise_cellular(vec2 p, int seed, float jitter, int metric, float exponent) {
+    int xr = noise_fast_round(p.x);
+    int yr = noise_fast_round(p.y);
+    float cell_jitter = 0.43701595 * jitter;
+    float f1 = 1e10;
+    float f2 = 1e10;
+    int nearest_hash = 0;
+    int x_primed = (xr - 1) * NOISE_PRIME_X;
+    int y_primed_start = (yr - 1) * NOISE_PRIME_Y;
+
+    for (int xi = xr - 1; xi <= xr + 1; xi++) {
+        int y_primed = y_primed_start;
+
+        for (int yi = yr - 1; yi <= yr + 1; yi++) {
+            int hash = noise_hash(seed, x_primed, y_primed);
+            float angle = float((hash >> 1) & 255) * (6.2831855 / 256.0);
+            vec2 v = vec2(float(xi), float(yi)) - p + vec2(cos(angle), sin(angle)) * cell_jitter;
+            float d = noise_cell_metric(v, metric, exponent);
+
+            if (d < f1) {
+                f2 = f1;
+                f1 = d;
+                nearest_hash = hash;
+            } else if (d < f2) {
+                f2 = d;
+            }
+
+            y_primed += NOISE_PRIME_Y;
+        }
+
+        x_primed += NOISE_PRIME_X;
+    }
+
+    return vec3(f1, f2, float(nearest_hash) * (1.0 / 2147483648.0));
+}
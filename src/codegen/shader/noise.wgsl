@@ -0,0 +1,252 @@
+// Noise primitives used by the generated code.
+//
+// The hashing and interpolation follow FastNoiseLite like the CPU noise does.
+// Gradients and cell offsets are computed from the hash instead of looked up in tables,
+// so Perlin and cell noise have the same character but not the same pattern as on the CPU.
+
+const NOISE_PRIME_X: i32 = 501125321;
+const NOISE_PRIME_Y: i32 = 1136930381;
+
+fn noise_hash(seed: i32, x: i32, y: i32) -> i32 {
+    return (seed ^ x ^ y) * 668265261;
+}
+
+fn noise_val_coord(seed: i32, x: i32, y: i32) -> f32 {
+    var hash = noise_hash(seed, x, y);
+    hash *= hash;
+    hash ^= hash << 19u;
+    return f32(hash) * (1.0 / 2147483648.0);
+}
+
+fn noise_grad_coord(seed: i32, x: i32, y: i32, xd: f32, yd: f32) -> f32 {
+    var hash = noise_hash(seed, x, y);
+    hash ^= hash >> 15u;
+    let angle = f32(hash & 127) * (6.2831855 / 128.0);
+    return xd * cos(angle) + yd * sin(angle);
+}
+
+fn noise_interp_hermite(t: f32) -> f32 {
+    return t * t * (3.0 - 2.0 * t);
+}
+
+fn noise_interp_quintic(t: f32) -> f32 {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn noise_cubic_lerp(a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+    let p = (d - c) - (a - b);
+    return t * t * t * p + t * t * ((a - b) - p) + t * (c - a) + b;
+}
+
+fn noise_fast_round(x: f32) -> i32 {
+    return i32(x + select(-0.5, 0.5, x >= 0.0));
+}
+
+fn noise_round(x: f32) -> f32 {
+    return trunc(x + select(-0.5, 0.5, x >= 0.0));
+}
+
+fn noise_lerp(a: f32, b: f32, t: f32) -> f32 {
+    return a + t * (b - a);
+}
+
+fn noise_inverse_lerp(a: f32, b: f32, value: f32) -> f32 {
+    return (value - a) / (b - a);
+}
+
+fn noise_smoothstep(edge0: f32, edge1: f32, value: f32) -> f32 {
+    let t = clamp((value - edge0) / (edge1 - edge0), 0.0, 1.0);
+    return t * t * (3.0 - 2.0 * t);
+}
+
+fn noise_smootherstep(edge0: f32, edge1: f32, value: f32) -> f32 {
+    let t = clamp((value - edge0) / (edge1 - edge0), 0.0, 1.0);
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn noise_smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0.0 {
+        return min(a, b);
+    }
+    let h = max(k - abs(a - b), 0.0) / k;
+    return min(a, b) - h * h * k * 0.25;
+}
+
+fn noise_smooth_max(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0.0 {
+        return max(a, b);
+    }
+    let h = max(k - abs(a - b), 0.0) / k;
+    return max(a, b) + h * h * k * 0.25;
+}
+
+fn noise_spline(x: f32, before: f32, x0: f32, y0: f32, x1: f32, y1: f32, m0: f32, m1: f32) -> f32 {
+    if x < x0 {
+        return before;
+    }
+    let dx = x1 - x0;
+    let t = min((x - x0) / dx, 1.0);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    return (2.0 * t3 - 3.0 * t2 + 1.0) * y0 + (t3 - 2.0 * t2 + t) * dx * m0
+        + (3.0 * t2 - 2.0 * t3) * y1 + (t3 - t2) * dx * m1;
+}
+
+fn noise_value(p: vec2<f32>, seed: i32) -> f32 {
+    let f = floor(p);
+    let sx = noise_interp_hermite(p.x - f.x);
+    let sy = noise_interp_hermite(p.y - f.y);
+    let x0 = i32(f.x) * NOISE_PRIME_X;
+    let y0 = i32(f.y) * NOISE_PRIME_Y;
+    let x1 = x0 + NOISE_PRIME_X;
+    let y1 = y0 + NOISE_PRIME_Y;
+    let xf0 = mix(noise_val_coord(seed, x0, y0), noise_val_coord(seed, x1, y0), sx);
+    let xf1 = mix(noise_val_coord(seed, x0, y1), noise_val_coord(seed, x1, y1), sx);
+    return mix(xf0, xf1, sy);
+}
+
+fn noise_value_cubic_row(seed: i32, x0: i32, y: i32, t: f32) -> f32 {
+    let x1 = x0 + NOISE_PRIME_X;
+    let x2 = x1 + NOISE_PRIME_X;
+    let x3 = x2 + NOISE_PRIME_X;
+    return noise_cubic_lerp(
+        noise_val_coord(seed, x0, y),
+        noise_val_coord(seed, x1, y),
+        noise_val_coord(seed, x2, y),
+        noise_val_coord(seed, x3, y),
+        t,
+    );
+}
+
+fn noise_value_cubic(p: vec2<f32>, seed: i32) -> f32 {
+    let f = floor(p);
+    let s = p - f;
+    let x0 = (i32(f.x) - 1) * NOISE_PRIME_X;
+    let y0 = (i32(f.y) - 1) * NOISE_PRIME_Y;
+    let y1 = y0 + NOISE_PRIME_Y;
+    let y2 = y1 + NOISE_PRIME_Y;
+    let y3 = y2 + NOISE_PRIME_Y;
+    return noise_cubic_lerp(
+        noise_value_cubic_row(seed, x0, y0, s.x),
+        noise_value_cubic_row(seed, x0, y1, s.x),
+        noise_value_cubic_row(seed, x0, y2, s.x),
+        noise_value_cubic_row(seed, x0, y3, s.x),
+        s.y,
+    ) * (1.0 / (1.5 * 1.5));
+}
+
+fn noise_perlin(p: vec2<f32>, seed: i32) -> f32 {
+    let f = floor(p);
+    let d0 = p - f;
+    let d1 = d0 - 1.0;
+    let sx = noise_interp_quintic(d0.x);
+    let sy = noise_interp_quintic(d0.y);
+    let x0 = i32(f.x) * NOISE_PRIME_X;
+    let y0 = i32(f.y) * NOISE_PRIME_Y;
+    let x1 = x0 + NOISE_PRIME_X;
+    let y1 = y0 + NOISE_PRIME_Y;
+    let xf0 = mix(noise_grad_coord(seed, x0, y0, d0.x, d0.y), noise_grad_coord(seed, x1, y0, d1.x, d0.y), sx);
+    let xf1 = mix(noise_grad_coord(seed, x0, y1, d0.x, d1.y), noise_grad_coord(seed, x1, y1, d1.x, d1.y), sx);
+    return mix(xf0, xf1, sy) * 1.4247692;
+}
+
+// Returns the squared distance to the nearest cell point and the value of its cell.
+fn noise_cell(p: vec2<f32>, seed: i32, jitter: f32) -> vec2<f32> {
+    let xr = noise_fast_round(p.x);
+    let yr = noise_fast_round(p.y);
+    let cell_jitter = 0.43701595 * jitter;
+    var nearest = 1e10;
+    var nearest_hash = 0;
+    var x_primed = (xr - 1) * NOISE_PRIME_X;
+    let y_primed_start = (yr - 1) * NOISE_PRIME_Y;
+
+    for (var xi = xr - 1; xi <= xr + 1; xi++) {
+        var y_primed = y_primed_start;
+
+        for (var yi = yr - 1; yi <= yr + 1; yi++) {
+            let hash = noise_hash(seed, x_primed, y_primed);
+            let angle = f32((hash >> 1u) & 255) * (6.2831855 / 256.0);
+            let v = vec2(f32(xi), f32(yi)) - p + vec2(cos(angle), sin(angle)) * cell_jitter;
+            let d = dot(v, v);
+
+            if d < nearest {
+                nearest = d;
+                nearest_hash = hash;
+            }
+
+            y_primed += NOISE_PRIME_Y;
+        }
+
+        x_primed += NOISE_PRIME_X;
+    }
+
+    return vec2(nearest, f32(nearest_hash) * (1.0 / 2147483648.0));
+}
+
+fn noise_cell_value(p: vec2<f32>, seed: i32, jitter: f32) -> f32 {
+    return noise_cell(p, seed, jitter).y;
+}
+
+fn noise_cell_distance(p: vec2<f32>, seed: i32, jitter: f32) -> f32 {
+    return sqrt(noise_cell(p, seed, jitter).x) - 1.0;
+}
+
+fn noise_cell_distance_sq(p: vec2<f32>, seed: i32, jitter: f32) -> f32 {
+    return noise_cell(p, seed, jitter).x - 1.0;
+}
+
+// The length of the vector in the metric of the cellular node:
+// 0 Euclidean, 1 Manhattan, 2 Chebyshev or 3 Minkowski with the exponent.
+fn noise_cell_metric(v: vec2<f32>, metric: i32, exponent: f32) -> f32 {
+    let a = abs(v);
+
+    if metric == 1 {
+        return a.x + a.y;
+    }
+    if metric == 2 {
+        return max(a.x, a.y);
+    }
+    if metric == 3 {
+        // CellMetric::MIN_EXPONENT
+        let e = max(exponent, 0.1);
+        return pow(pow(a.x, e) + pow(a.y, e), 1.0 / e);
+    }
+    return length(v);
+}
+
+// Returns the distances to the nearest and second nearest cell point and the value of the nearest cell.
+fn noise_cellular(p: vec2<f32>, seed: i32, jitter: f32, metric: i32, exponent: f32) -> vec3<f32> {
+    let xr = noise_fast_round(p.x);
+    let yr = noise_fast_round(p.y);
+    let cell_jitter = 0.43701595 * jitter;
+    var f1 = 1e10;
+    var f2 = 1e10;
+    var nearest_hash = 0;
+    var x_primed = (xr - 1) * NOISE_PRIME_X;
+    let y_primed_start = (yr - 1) * NOISE_PRIME_Y;
+
+    for (var xi = xr - 1; xi <= xr + 1; xi++) {
+        var y_primed = y_primed_start;
+
+        for (var yi = yr - 1; yi <= yr + 1; yi++) {
+            let hash = noise_hash(seed, x_primed, y_primed);
+            let angle = f32((hash >> 1u) & 255) * (6.2831855 / 256.0);
+            let v = vec2(f32(xi), f32(yi)) - p + vec2(cos(angle), sin(angle)) * cell_jitter;
+            let d = noise_cell_metric(v, metric, exponent);
+
+            if d < f1 {
+                f2 = f1;
+                f1 = d;
+                nearest_hash = hash;
+            } else if d < f2 {
+                f2 = d;
+            }
+
+            y_primed += NOISE_PRIME_Y;
+        }
+
+        x_primed += NOISE_PRIME_X;
+    }
+
+    return vec3(f1, f2, f32(nearest_hash) * (1.0 / 2147483648.0));
+}
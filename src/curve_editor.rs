@@ -0,0 +1,133 @@
+//! The editor of the curve of [`Node::Curve`](crate::graph::Node::Curve).
+
+use crate::graph::curve::{Curve, Interpolation};
+
+const SIZE: egui::Vec2 = egui::vec2(160.0, 120.0);
+
+const POINT_RADIUS: f32 = 4.0;
+
+/// The number of lines the curve is drawn with.
+const RESOLUTION: usize = 100;
+
+/// Shows the interpolation and a plot of -1..1 where points can be dragged, added by clicking
+/// and removed by right-clicking. Returns whether the curve changed.
+pub fn show(ui: &mut egui::Ui, id: egui::Id, curve: &mut Curve) -> bool {
+    let mut changed = false;
+
+    egui::ComboBox::from_id_salt(id.with("interpolation"))
+        .selected_text(curve.interpolation.name())
+        .show_ui(ui, |ui| {
+            for interpolation in Interpolation::ALL {
+                changed |= ui
+                    .selectable_value(
+                        &mut curve.interpolation,
+                        interpolation,
+                        interpolation.name(),
+                    )
+                    .changed();
+            }
+        });
+
+    let (response, painter) = ui.allocate_painter(SIZE, egui::Sense::click());
+    let rect = response.rect;
+
+    // y goes up in the plot
+    let to_screen = egui::emath::RectTransform::from_to(
+        egui::Rect::from_min_max(egui::pos2(-1.0, 1.0), egui::pos2(1.0, -1.0)),
+        rect,
+    );
+    let from_screen = to_screen.inverse();
+    let screen_pos = |[x, y]: [f32; 2]| to_screen * egui::pos2(x, y);
+
+    let mut hovered = vec![false; curve.points.len()];
+    let mut removed = None;
+
+    for (i, hovered) in hovered.iter_mut().enumerate() {
+        let center = screen_pos(curve.points[i]);
+        let point_rect =
+            egui::Rect::from_center_size(center, egui::Vec2::splat(POINT_RADIUS * 3.0));
+        let point_response = ui.interact(point_rect, id.with(i), egui::Sense::click_and_drag());
+
+        if point_response.dragged() {
+            let pos = from_screen * (center + point_response.drag_delta());
+
+            // a point can't pass its neighbors, so the points stay in order
+            let min_x = if i == 0 { -1.0 } else { curve.points[i - 1][0] };
+            let max_x = curve.points.get(i + 1).map_or(1.0, |point| point[0]);
+            curve.points[i] = [pos.x.clamp(min_x, max_x), pos.y.clamp(-1.0, 1.0)];
+            changed = true;
+        }
+
+        // the curve needs two points to be a curve
+        if point_response.secondary_clicked() && curve.points.len() > 2 {
+            removed = Some(i);
+        }
+
+        *hovered = point_response.hovered() || point_response.dragged();
+    }
+
+    if let Some(i) = removed {
+        curve.points.remove(i);
+        hovered.remove(i);
+        changed = true;
+    }
+
+    if response.clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            let pos = from_screen * pos;
+            let i = curve.points.partition_point(|point| point[0] < pos.x);
+            curve.points.insert(i, [pos.x, pos.y]);
+            hovered.insert(i, true);
+            changed = true;
+        }
+    }
+
+    let visuals = ui.visuals();
+    let axis_stroke = visuals.widgets.noninteractive.bg_stroke;
+    let curve_stroke = egui::Stroke::new(2.0, visuals.selection.bg_fill);
+
+    painter.rect_filled(
+        rect,
+        visuals.widgets.noninteractive.rounding,
+        visuals.extreme_bg_color,
+    );
+    painter.line_segment(
+        [screen_pos([-1.0, 0.0]), screen_pos([1.0, 0.0])],
+        axis_stroke,
+    );
+    painter.line_segment(
+        [screen_pos([0.0, -1.0]), screen_pos([0.0, 1.0])],
+        axis_stroke,
+    );
+
+    let segments = curve.segments();
+    let line = (0..=RESOLUTION)
+        .map(|i| {
+            let x = -1.0 + 2.0 * i as f32 / RESOLUTION as f32;
+            let y = segments
+                .iter()
+                .fold(curve.start(), |before, segment| segment.sample(x, before));
+            screen_pos([x, y])
+        })
+        .collect();
+    painter.add(egui::Shape::line(line, curve_stroke));
+
+    for (&point, hovered) in curve.points.iter().zip(hovered) {
+        let radius = if hovered {
+            POINT_RADIUS * 1.5
+        } else {
+            POINT_RADIUS
+        };
+        painter.circle(
+            screen_pos(point),
+            radius,
+            visuals.widgets.inactive.fg_stroke.color,
+            curve_stroke,
+        );
+    }
+
+    response
+        .on_hover_text("Drag a point to move it, click to add one and right-click to remove one");
+
+    changed
+}
@@ -0,0 +1,184 @@
+use egui_snarl::{ui::SnarlViewer, InPinId, NodeId, OutPinId, Snarl};
+use serde::{Deserialize, Serialize};
+
+use crate::graph::{GroupLibrary, Node};
+
+/// A set of nodes and the wires between them, detached from any graph.
+///
+/// Node positions are relative to `origin`, wires refer to nodes by their index in `nodes`.
+/// It has the groups its group nodes use so it can be inserted into any graph.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Fragment {
+    origin: egui::Pos2,
+    nodes: Vec<(egui::Vec2, Node)>,
+    wires: Vec<Wire>,
+    #[serde(default, skip_serializing_if = "GroupLibrary::is_empty")]
+    groups: GroupLibrary,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Wire {
+    from: (usize, usize),
+    to: (usize, usize),
+}
+
+impl Fragment {
+    pub fn new(snarl: &Snarl<Node>, node_ids: &[NodeId], groups: &GroupLibrary) -> Self {
+        let origin = node_ids
+            .iter()
+            .filter_map(|&id| snarl.get_node_info(id))
+            .map(|info| info.pos)
+            .reduce(|a, b| a.min(b))
+            .unwrap_or(egui::Pos2::ZERO);
+
+        let nodes: Vec<(egui::Vec2, Node)> = node_ids
+            .iter()
+            .filter_map(|&id| snarl.get_node_info(id))
+            .map(|info| (info.pos - origin, info.value.clone()))
+            .collect();
+
+        let groups = groups.used_by(nodes.iter().map(|(_, node)| node));
+
+        let index_of = |node: NodeId| node_ids.iter().position(|&id| id == node);
+
+        let wires = snarl
+            .wires()
+            .filter_map(|(out_pin, in_pin)| {
+                Some(Wire {
+                    from: (index_of(out_pin.node)?, out_pin.output),
+                    to: (index_of(in_pin.node)?, in_pin.input),
+                })
+            })
+            .collect();
+
+        Self {
+            origin,
+            nodes,
+            wires,
+            groups,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn origin(&self) -> egui::Pos2 {
+        self.origin
+    }
+
+    /// Leaves out the wires to pins that the nodes don't have or that can't be wired, further
+    /// wires to an input that is already wired and wires that would close a cycle.
+    ///
+    /// Fragments from the clipboard can hold anything, so this is done before inserting them.
+    pub fn remove_invalid_wires(&mut self, viewer: &mut impl SnarlViewer<Node>) {
+        let mut wires: Vec<Wire> = Vec::new();
+
+        for &wire in &self.wires {
+            let (Some((_, from)), Some((_, to))) =
+                (self.nodes.get(wire.from.0), self.nodes.get(wire.to.0))
+            else {
+                continue;
+            };
+
+            let valid = wire.from.1 < self.pins(from, viewer).1
+                && wire.to.1 < self.pins(to, viewer).0
+                && to.is_input_connectable(wire.to.1)
+                && !wires.iter().any(|w| w.to == wire.to)
+                && !reaches(&wires, wire.to.0, wire.from.0);
+
+            if valid {
+                wires.push(wire);
+            }
+        }
+
+        self.wires = wires;
+    }
+
+    /// The number of inputs and outputs of a node of the fragment.
+    fn pins(&self, node: &Node, viewer: &mut impl SnarlViewer<Node>) -> (usize, usize) {
+        match node.group() {
+            // the fragment's groups, not the viewer's
+            Some(group) => self.groups.get(group).map_or((0, 0), |group| {
+                (group.inputs().count(), group.outputs().count())
+            }),
+            None => (viewer.inputs(node), viewer.outputs(node)),
+        }
+    }
+
+    /// Inserts the nodes and wires into `snarl` with the top left corner at `pos`,
+    /// and the groups they use that `groups` doesn't have yet into `groups`.
+    pub fn insert(
+        &self,
+        snarl: &mut Snarl<Node>,
+        groups: &mut GroupLibrary,
+        pos: egui::Pos2,
+    ) -> Vec<NodeId> {
+        let group_ids = groups.merge(&self.groups);
+
+        let node_ids: Vec<NodeId> = self
+            .nodes
+            .iter()
+            .map(|(offset, node)| {
+                let mut node = node.clone();
+                node.replace_group(&group_ids);
+                snarl.insert_node(pos + *offset, node)
+            })
+            .collect();
+
+        for wire in &self.wires {
+            let (Some(&from), Some(&to)) = (node_ids.get(wire.from.0), node_ids.get(wire.to.0))
+            else {
+                continue;
+            };
+
+            snarl.connect(
+                OutPinId {
+                    node: from,
+                    output: wire.from.1,
+                },
+                InPinId {
+                    node: to,
+                    input: wire.to.1,
+                },
+            );
+        }
+
+        node_ids
+    }
+
+    pub fn to_text(&self) -> String {
+        ron::to_string(self).expect("fragment is serializable")
+    }
+
+    /// Reads a fragment, which has to have the groups that its group nodes use.
+    pub fn from_text(text: &str) -> Option<Self> {
+        ron::from_str(text).ok().filter(Self::has_groups)
+    }
+
+    /// Whether the fragment has the groups that its group nodes use, see
+    /// [`GroupLibrary::is_complete_for`].
+    pub fn has_groups(&self) -> bool {
+        let nodes = self.nodes.iter().map(|(_, node)| node);
+        self.groups.is_complete_for(nodes)
+    }
+}
+
+/// Whether the wires lead from the outputs of node `from` to the inputs of node `to`.
+fn reaches(wires: &[Wire], from: usize, to: usize) -> bool {
+    let mut stack = vec![from];
+    let mut visited = vec![];
+
+    while let Some(node) = stack.pop() {
+        if node == to {
+            return true;
+        }
+
+        if !visited.contains(&node) {
+            visited.push(node);
+            stack.extend(wires.iter().filter(|w| w.from.0 == node).map(|w| w.to.0));
+        }
+    }
+
+    false
+}
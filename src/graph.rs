@@ -0,0 +1,1051 @@
+//! The graph model and its evaluation, independent of the editor.
+//!
+//! ```no_run
+//! use noise_functions::Sample;
+//! use noise_functions_playground::graph::{self, Graph};
+//!
+//! let text = std::fs::read_to_string("graph.ron").unwrap();
+//! let graph = Graph::from_text(&text).unwrap();
+//! let node = graph.find_node("Fractal").unwrap();
+//! let noise = graph::build_sampler(&graph, graph::OutPinId { node, output: 0 }).unwrap();
+//! let value = noise.sample_with_seed([1.0, 2.0], 0);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+pub mod curve;
+mod eval;
+pub mod expression;
+pub mod ir;
+
+pub use eval::{build_sampler, Error, Registers, Sampler, Slot};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NodeId(pub usize);
+
+/// The id of a group in a [`GroupLibrary`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GroupId(pub usize);
+
+/// An output of a node.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct OutPinId {
+    pub node: NodeId,
+    pub output: usize,
+}
+
+/// An input of a node.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct InPinId {
+    pub node: NodeId,
+    pub input: usize,
+}
+
+/// Nodes and the wires between them.
+///
+/// This is the format of graph files; the editor converts it from and to its own representation.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Graph {
+    nodes: BTreeMap<NodeId, GraphNode>,
+    wires: Vec<(OutPinId, InPinId)>,
+    /// The groups of the group nodes, also of those inside of groups.
+    /// Only the outermost graph has groups, the graphs of groups use its groups.
+    #[serde(default, skip_serializing_if = "GroupLibrary::is_empty")]
+    groups: GroupLibrary,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct GraphNode {
+    pos: [f32; 2],
+    node: Node,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a graph file, checking it like [`Graph::check`].
+    pub fn from_text(text: &str) -> Result<Self, Error> {
+        let graph: Self = ron::from_str(text).map_err(Error::Parse)?;
+        graph.check()?;
+        Ok(graph)
+    }
+
+    pub fn to_text(&self) -> String {
+        ron::ser::to_string_pretty(self, Default::default()).expect("graph is serializable")
+    }
+
+    /// Adds a node at the position in the editor and returns its id.
+    pub fn insert_node(&mut self, pos: [f32; 2], node: Node) -> NodeId {
+        let id = match self.nodes.last_key_value() {
+            Some((id, _)) => NodeId(id.0 + 1),
+            None => NodeId(0),
+        };
+
+        self.insert_node_with_id(id, pos, node);
+        id
+    }
+
+    /// Adds or replaces the node with this id.
+    pub fn insert_node_with_id(&mut self, id: NodeId, pos: [f32; 2], node: Node) {
+        self.nodes.insert(id, GraphNode { pos, node });
+    }
+
+    /// Connects the output to the input, replacing the wire the input had.
+    pub fn connect(&mut self, from: OutPinId, to: InPinId) {
+        self.wires.retain(|&(_, in_pin)| in_pin != to);
+        self.wires.push((from, to));
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(&id).map(|n| &n.node)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(&id).map(|n| &mut n.node)
+    }
+
+    /// The position of the node in the editor.
+    pub fn node_pos(&self, id: NodeId) -> Option<[f32; 2]> {
+        self.nodes.get(&id).map(|n| n.pos)
+    }
+
+    /// All nodes ordered by id.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> + '_ {
+        self.nodes.iter().map(|(&id, n)| (id, &n.node))
+    }
+
+    pub fn wires(&self) -> impl Iterator<Item = (OutPinId, InPinId)> + '_ {
+        self.wires.iter().copied()
+    }
+
+    pub fn disconnect(&mut self, from: OutPinId, to: InPinId) {
+        self.wires.retain(|&wire| wire != (from, to));
+    }
+
+    /// The groups that the group nodes of the graph and of its groups refer to.
+    pub fn groups(&self) -> &GroupLibrary {
+        &self.groups
+    }
+
+    pub fn groups_mut(&mut self) -> &mut GroupLibrary {
+        &mut self.groups
+    }
+
+    /// The output connected to the input.
+    pub fn input(&self, pin: InPinId) -> Option<OutPinId> {
+        self.wires
+            .iter()
+            .find(|&&(_, in_pin)| in_pin == pin)
+            .map(|&(out_pin, _)| out_pin)
+    }
+
+    /// Finds the only node with this name, ignoring case.
+    pub fn find_node(&self, name: &str) -> Option<NodeId> {
+        let mut ids = self
+            .nodes()
+            .filter(|(_, node)| node.name().eq_ignore_ascii_case(name))
+            .map(|(id, _)| id);
+
+        match (ids.next(), ids.next()) {
+            (Some(id), None) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Checks that the wires of the graph and of its groups connect existing nodes without cycles
+    /// and that its groups exist and don't contain themselves, which the graph can't be evaluated with.
+    pub fn check(&self) -> Result<(), Error> {
+        self.check_nodes(&self.groups)?;
+        self.groups.check()
+    }
+
+    /// Checks the wires of the graph and that its group nodes refer to groups of the library.
+    fn check_nodes(&self, groups: &GroupLibrary) -> Result<(), Error> {
+        let mut inputs: BTreeMap<NodeId, Vec<NodeId>> = BTreeMap::new();
+
+        for (from, to) in self.wires() {
+            if !self.nodes.contains_key(&from.node) || !self.nodes.contains_key(&to.node) {
+                return Err(Error::InvalidWire(from, to));
+            }
+
+            inputs.entry(to.node).or_default().push(from.node);
+        }
+
+        /// Visits the nodes connected to the node's inputs before marking it as done.
+        fn visit(
+            node: NodeId,
+            inputs: &BTreeMap<NodeId, Vec<NodeId>>,
+            visiting: &mut BTreeSet<NodeId>,
+            done: &mut BTreeSet<NodeId>,
+        ) -> Result<(), Error> {
+            if done.contains(&node) {
+                return Ok(());
+            }
+
+            if !visiting.insert(node) {
+                return Err(Error::Cycle(node));
+            }
+
+            for &input in inputs.get(&node).into_iter().flatten() {
+                visit(input, inputs, visiting, done)?;
+            }
+
+            visiting.remove(&node);
+            done.insert(node);
+            Ok(())
+        }
+
+        let mut visiting = BTreeSet::new();
+        let mut done = BTreeSet::new();
+
+        for (id, node) in self.nodes() {
+            if let Node::Group { group, .. } = *node {
+                if groups.get(group).is_none() {
+                    return Err(Error::GroupNotFound(group));
+                }
+            }
+
+            visit(id, &inputs, &mut visiting, &mut done)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::ops::Index<NodeId> for Graph {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        self.node(id).expect("node exists")
+    }
+}
+
+impl std::ops::IndexMut<NodeId> for Graph {
+    fn index_mut(&mut self, id: NodeId) -> &mut Node {
+        self.node_mut(id).expect("node exists")
+    }
+}
+
+/// The group node whose graph is being evaluated, used to evaluate its [`Node::GroupInput`]s.
+pub(crate) struct Scope<'a> {
+    pub graph: &'a Graph,
+    pub node: NodeId,
+    pub parent: Option<&'a Scope<'a>>,
+}
+
+impl<'a> Scope<'a> {
+    /// The outermost graph, whose groups the group nodes of `graph` refer to.
+    pub fn root(graph: &'a Graph, scope: Option<&Scope<'a>>) -> &'a Graph {
+        let mut root = graph;
+        let mut scope = scope;
+
+        while let Some(group) = scope {
+            root = group.graph;
+            scope = group.parent;
+        }
+
+        root
+    }
+
+    /// The group of the group node.
+    pub fn group(&self) -> Option<&'a Group> {
+        let id = self.graph.node(self.node)?.group()?;
+        Scope::root(self.graph, Some(self)).groups().get(id)
+    }
+
+    /// The value of the group node's input when it's unconnected,
+    /// `default` is the value of the group input if the node has none.
+    pub fn input_value(&self, input: usize, default: f32) -> f32 {
+        match self.graph.node(self.node) {
+            Some(Node::Group { values, .. }) => values.get(input).copied().unwrap_or(default),
+            _ => default,
+        }
+    }
+
+    /// The group nodes from the outermost one to the scope's node, which tell scopes apart.
+    pub fn path(scope: Option<&Scope>) -> Vec<NodeId> {
+        let mut path = Vec::new();
+        let mut scope = scope;
+
+        while let Some(group) = scope {
+            path.push(group.node);
+            scope = group.parent;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+/// A graph that is used as a single node.
+///
+/// Its inputs and outputs are the [`Node::GroupInput`] and [`Node::GroupOutput`] nodes inside of it.
+/// It's stored once in a [`GroupLibrary`] and used by the [`Node::Group`]s that refer to it.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub name: String,
+    pub graph: Graph,
+
+    /// The order of the group node's pins, see [`Group::update_pins`].
+    #[serde(default)]
+    input_order: Vec<NodeId>,
+    #[serde(default)]
+    output_order: Vec<NodeId>,
+}
+
+impl Group {
+    pub fn new(name: String, graph: Graph) -> Self {
+        let mut group = Self {
+            name,
+            graph,
+            input_order: Vec::new(),
+            output_order: Vec::new(),
+        };
+
+        group.update_pins();
+        group
+    }
+
+    /// The [`Node::GroupInput`] nodes in the order of the group node's input pins.
+    pub fn inputs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        pins(&self.graph, &self.input_order, |node| {
+            matches!(node, Node::GroupInput { .. })
+        })
+    }
+
+    /// The [`Node::GroupOutput`] nodes in the order of the group node's output pins.
+    pub fn outputs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        pins(&self.graph, &self.output_order, |node| {
+            matches!(node, Node::GroupOutput { .. })
+        })
+    }
+
+    /// Keeps the pins of the group input and output nodes in their order, drops the pins of
+    /// removed nodes and adds pins for new nodes at the end.
+    ///
+    /// This has to happen whenever the group's graph was edited, so that a new node that gets
+    /// the id of a removed one doesn't take over its pin.
+    pub fn update_pins(&mut self) {
+        self.input_order = self.inputs().collect();
+        self.output_order = self.outputs().collect();
+    }
+
+    /// Changes the ids of the pins, for when the group's nodes got new ids.
+    pub fn replace_pin_ids(&mut self, ids: &BTreeMap<NodeId, NodeId>) {
+        for id in self.input_order.iter_mut().chain(&mut self.output_order) {
+            if let Some(&new) = ids.get(id) {
+                *id = new;
+            }
+        }
+    }
+
+    /// The values of the group inputs, which group nodes use for inputs they have no value for.
+    pub fn input_values(&self) -> impl Iterator<Item = f32> + '_ {
+        self.inputs().map(|id| match self.graph[id] {
+            Node::GroupInput { value, .. } => value,
+            _ => 0.0,
+        })
+    }
+}
+
+/// The nodes of the group's graph that are pins, first those in `order` and then the others by id.
+fn pins<'a>(
+    graph: &'a Graph,
+    order: &'a [NodeId],
+    is_pin: fn(&Node) -> bool,
+) -> impl Iterator<Item = NodeId> + 'a {
+    let ordered = order
+        .iter()
+        .copied()
+        .filter(move |&id| graph.node(id).is_some_and(is_pin));
+
+    let new = graph
+        .nodes()
+        .filter(move |(id, node)| is_pin(node) && !order.contains(id))
+        .map(|(id, _)| id);
+
+    ordered.chain(new)
+}
+
+/// The groups of a graph by id.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GroupLibrary {
+    groups: BTreeMap<GroupId, Group>,
+}
+
+impl GroupLibrary {
+    /// Adds the group and returns its id.
+    pub fn insert(&mut self, group: Group) -> GroupId {
+        let id = match self.groups.last_key_value() {
+            Some((id, _)) => GroupId(id.0 + 1),
+            None => GroupId(0),
+        };
+
+        self.groups.insert(id, group);
+        id
+    }
+
+    /// Adds or replaces the group with this id.
+    pub fn insert_with_id(&mut self, id: GroupId, group: Group) {
+        self.groups.insert(id, group);
+    }
+
+    pub fn get(&self, id: GroupId) -> Option<&Group> {
+        self.groups.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: GroupId) -> Option<&mut Group> {
+        self.groups.get_mut(&id)
+    }
+
+    /// All groups ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = (GroupId, &Group)> + '_ {
+        self.groups.iter().map(|(&id, group)| (id, group))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (GroupId, &mut Group)> + '_ {
+        self.groups.iter_mut().map(|(&id, group)| (id, group))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The groups that the nodes use, also through the groups they contain.
+    pub fn used_by<'a>(&self, nodes: impl IntoIterator<Item = &'a Node>) -> GroupLibrary {
+        let mut used = GroupLibrary::default();
+        let mut stack: Vec<GroupId> = nodes.into_iter().filter_map(Node::group).collect();
+
+        while let Some(id) = stack.pop() {
+            let Some(group) = self.get(id) else {
+                continue;
+            };
+
+            if used.get(id).is_none() {
+                used.insert_with_id(id, group.clone());
+                stack.extend(group.graph.nodes().filter_map(|(_, node)| node.group()));
+            }
+        }
+
+        used
+    }
+
+    /// Adds the groups of `other` that this library doesn't have yet.
+    ///
+    /// A group whose id is taken by a different group gets a new id,
+    /// the returned map has the new ids and is used to update the nodes that refer to them.
+    pub fn merge(&mut self, other: &GroupLibrary) -> BTreeMap<GroupId, GroupId> {
+        let mut ids = BTreeMap::new();
+        let mut inserted = Vec::new();
+
+        for (id, group) in other.iter() {
+            match self.get(id) {
+                Some(existing) if existing == group => continue,
+                Some(_) => {
+                    ids.insert(id, self.insert(group.clone()));
+                }
+                None => self.insert_with_id(id, group.clone()),
+            }
+
+            inserted.push(ids.get(&id).copied().unwrap_or(id));
+        }
+
+        for id in inserted {
+            let group = self.get_mut(id).expect("group was inserted");
+            let node_ids: Vec<NodeId> = group.graph.nodes().map(|(id, _)| id).collect();
+
+            for node in node_ids {
+                group.graph[node].replace_group(&ids);
+            }
+        }
+
+        ids
+    }
+
+    /// Whether the library has the groups that the nodes use and its groups pass [`Graph::check`],
+    /// which isn't known for groups that were read from the clipboard or a preset file.
+    pub fn is_complete_for<'a>(&self, nodes: impl IntoIterator<Item = &'a Node>) -> bool {
+        let mut groups = nodes.into_iter().filter_map(Node::group);
+        groups.all(|id| self.get(id).is_some()) && self.check().is_ok()
+    }
+
+    /// Checks the graphs of the groups and that none of them contains itself.
+    fn check(&self) -> Result<(), Error> {
+        for (id, group) in self.iter() {
+            group.graph.check_nodes(self)?;
+            self.check_recursion(id, &mut Vec::new())?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the group doesn't contain itself, also through the groups it contains.
+    fn check_recursion(&self, id: GroupId, path: &mut Vec<GroupId>) -> Result<(), Error> {
+        if path.contains(&id) {
+            return Err(Error::RecursiveGroup(id));
+        }
+
+        let Some(group) = self.get(id) else {
+            return Ok(());
+        };
+
+        path.push(id);
+
+        for (_, node) in group.graph.nodes() {
+            if let Some(inner) = node.group() {
+                self.check_recursion(inner, path)?;
+            }
+        }
+
+        path.pop();
+        Ok(())
+    }
+}
+
+/// A node of a [`Graph`], with the values of its unconnected inputs.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Node {
+    Value,
+    ValueCubic,
+    Perlin,
+    Simplex,
+    OpenSimplex2,
+    OpenSimplex2s,
+    CellValue {
+        jitter: f32,
+    },
+    CellDistance {
+        jitter: f32,
+    },
+    CellDistanceSq {
+        jitter: f32,
+    },
+    /// Cell noise like FastNoiseLite's cellular noise, with the distances measured in the metric.
+    ///
+    /// Its outputs are the distance to the nearest cell point (F1), to the second nearest (F2),
+    /// F2 - F1, F1 · F2 and the value of the nearest cell. `exponent` is the p of the Minkowski metric.
+    Cellular {
+        metric: CellMetric,
+        jitter: f32,
+        exponent: f32,
+    },
+
+    // pattern
+    /// Squares of the size that are alternately 1 and -1.
+    Checkerboard {
+        size: f32,
+    },
+    /// Bands of the width that are alternately 1 and -1, across the angle in degrees.
+    Stripes {
+        width: f32,
+        angle: f32,
+    },
+    /// Rings of the width around the origin that are alternately 1 and -1.
+    Rings {
+        width: f32,
+    },
+    /// 1 at the center, falling to -1 at the radius.
+    RadialGradient {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    /// Rises from -1 to 1 along the angle in degrees, over the length centered on the origin.
+    LinearGradient {
+        angle: f32,
+        length: f32,
+    },
+    /// The distance to the edge of a circle, negative inside.
+    CircleSdf {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    /// The distance to the edge of a box, negative inside.
+    BoxSdf {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    /// 1 on vertical and horizontal lines of the thickness and -1 between them.
+    GridLines {
+        spacing: f32,
+        thickness: f32,
+    },
+    /// 1 on the edges of hexagons whose centers are `size` apart and -1 inside them.
+    HexGrid {
+        size: f32,
+        thickness: f32,
+    },
+    /// 1 at the center, falling to 0 at the radius like `1 - t^exponent` and 0 beyond it,
+    /// or the other way around when inverted. Multiplying a noise with it makes an island.
+    Falloff {
+        shape: FalloffShape,
+        x: f32,
+        y: f32,
+        radius: f32,
+        exponent: f32,
+        invert: bool,
+    },
+
+    // misc
+    Fractal {
+        octaves: u32,
+        gain: f32,
+        lacunarity: f32,
+        weighted_strength: f32,
+    },
+    Frequency {
+        frequency: f32,
+    },
+    TriangleWave {
+        frequency: f32,
+    },
+
+    // translate
+    TranslateXy {
+        x: f32,
+        y: f32,
+    },
+
+    // unary
+    Abs,
+    Neg,
+    Sqrt,
+    Floor,
+    Ceil,
+    Round,
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    /// The natural logarithm.
+    Ln,
+    Log2,
+    /// -1 for negative values, 1 for positive ones and 0 for 0.
+    Sign,
+    /// The part after the floor, in 0..1 also for negative values.
+    Fract,
+    Reciprocal,
+    OneMinus,
+    /// The value limited to 0..1.
+    Saturate,
+
+    // binary
+    Add {
+        lhs: f32,
+        rhs: f32,
+    },
+    Sub {
+        lhs: f32,
+        rhs: f32,
+    },
+    Mul {
+        lhs: f32,
+        rhs: f32,
+    },
+    Div {
+        lhs: f32,
+        rhs: f32,
+    },
+    Rem {
+        lhs: f32,
+        rhs: f32,
+    },
+    Pow {
+        lhs: f32,
+        rhs: f32,
+    },
+    Min {
+        lhs: f32,
+        rhs: f32,
+    },
+    Max {
+        lhs: f32,
+        rhs: f32,
+    },
+    /// 0 below the edge and 1 from it on.
+    Step {
+        edge: f32,
+        value: f32,
+    },
+    /// The angle of the point `(x, y)`, in -π..π.
+    Atan2 {
+        y: f32,
+        x: f32,
+    },
+    /// The length of the vector `(x, y)`.
+    Hypot {
+        x: f32,
+        y: f32,
+    },
+
+    // ternary
+    Lerp {
+        a: f32,
+        b: f32,
+        t: f32,
+    },
+    /// Where the value is between `a` and `b`, 0 at `a` and 1 at `b`.
+    InverseLerp {
+        a: f32,
+        b: f32,
+        value: f32,
+    },
+    /// Goes smoothly from 0 at `edge0` to 1 at `edge1`.
+    Smoothstep {
+        edge0: f32,
+        edge1: f32,
+        value: f32,
+    },
+    /// Like [`Node::Smoothstep`] with a flat slope and curvature at the edges.
+    Smootherstep {
+        edge0: f32,
+        edge1: f32,
+        value: f32,
+    },
+    Clamp {
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    /// Like [`Node::Min`] with the corner rounded off where the values are closer than `k`.
+    SmoothMin {
+        lhs: f32,
+        rhs: f32,
+        k: f32,
+    },
+    /// Like [`Node::Max`] with the corner rounded off where the values are closer than `k`.
+    SmoothMax {
+        lhs: f32,
+        rhs: f32,
+        k: f32,
+    },
+    /// Maps the value from one range to another, limited to the second range if `clamp` is set.
+    Remap {
+        value: f32,
+        from_min: f32,
+        from_max: f32,
+        to_min: f32,
+        to_max: f32,
+        clamp: bool,
+    },
+    /// Maps the input from the range `min..max` to -1..1.
+    ///
+    /// The editor can set the range to that of the input over the preview region, but it's stored
+    /// and doesn't follow changes of the input, so that the graph evaluates the same everywhere.
+    Normalize {
+        min: f32,
+        max: f32,
+    },
+    /// A formula in the [`expression`] language, with the inputs as `a`, `b` and `c`.
+    Expression {
+        expression: String,
+        a: f32,
+        b: f32,
+        c: f32,
+    },
+    /// Maps the input with the curve.
+    Curve(curve::Curve),
+    /// Flattens the input into terraces between heights, which are evenly spaced in -1..1
+    /// unless custom `heights` are given.
+    ///
+    /// From one height to the next the value rises like `t^(1 / smoothness)`, so 0 makes hard steps
+    /// and 1 no terraces. Inverted terraces are flat at their upper height instead of their lower one.
+    Terrace {
+        steps: u32,
+        heights: Vec<f32>,
+        smoothness: f32,
+        invert: bool,
+    },
+    /// Snaps the input to `levels` evenly spaced values from `min` to `max`.
+    Quantize {
+        levels: u32,
+        min: f32,
+        max: f32,
+    },
+
+    // combine
+    /// `a` where the control is below the threshold and `b` from it on,
+    /// with a smooth transition `falloff` wide around the threshold.
+    Select {
+        a: f32,
+        b: f32,
+        control: f32,
+        threshold: f32,
+        falloff: f32,
+    },
+    /// From `a` to `b` by the weight limited to 0..1, like a mask.
+    Blend {
+        a: f32,
+        b: f32,
+        weight: f32,
+    },
+    /// Adds up the connected inputs, each multiplied by its weight.
+    ///
+    /// There is an input per weight. Like in the other variadic nodes, unconnected inputs are ignored.
+    Sum {
+        weights: Vec<f32>,
+    },
+    /// Multiplies the connected inputs.
+    Product {
+        inputs: usize,
+    },
+    /// The smallest of the connected inputs.
+    Minimum {
+        inputs: usize,
+    },
+    /// The largest of the connected inputs.
+    Maximum {
+        inputs: usize,
+    },
+    /// The mean of the connected inputs.
+    Average {
+        inputs: usize,
+    },
+
+    // seed
+    Seed {
+        seed: i32,
+    },
+    AddSeed {
+        add: i32,
+    },
+    MulSeed {
+        mul: i32,
+    },
+
+    // input
+    Position,
+    Number {
+        value: f32,
+    },
+
+    // group
+    /// A use of the group of the [`GroupLibrary`] with this id.
+    ///
+    /// `values` are the values of its unconnected inputs,
+    /// those it has no value for get the value of the group input.
+    Group {
+        group: GroupId,
+        values: Vec<f32>,
+    },
+    GroupInput {
+        name: String,
+        value: f32,
+    },
+    GroupOutput {
+        name: String,
+    },
+}
+
+impl Node {
+    /// The group of a [`Node::Group`].
+    pub fn group(&self) -> Option<GroupId> {
+        match *self {
+            Node::Group { group, .. } => Some(group),
+            _ => None,
+        }
+    }
+
+    /// Makes a [`Node::Group`] refer to the new id of its group if the map has one.
+    pub fn replace_group(&mut self, ids: &BTreeMap<GroupId, GroupId>) {
+        if let Node::Group { group, .. } = self {
+            if let Some(&id) = ids.get(group) {
+                *group = id;
+            }
+        }
+    }
+
+    /// The name shown in the node's header.
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Value => "Value",
+            Node::ValueCubic => "Value Cubic",
+            Node::Perlin => "Perlin",
+            Node::Simplex => "Simplex",
+            Node::OpenSimplex2 => "OpenSimplex2",
+            Node::OpenSimplex2s => "OpenSimplex2s",
+            Node::CellValue { .. } => "Cell Value",
+            Node::CellDistance { .. } => "Cell Distance",
+            Node::CellDistanceSq { .. } => "Cell DistanceSq",
+            Node::Cellular { .. } => "Cellular",
+            Node::Checkerboard { .. } => "Checkerboard",
+            Node::Stripes { .. } => "Stripes",
+            Node::Rings { .. } => "Rings",
+            Node::RadialGradient { .. } => "Radial Gradient",
+            Node::LinearGradient { .. } => "Linear Gradient",
+            Node::CircleSdf { .. } => "Circle SDF",
+            Node::BoxSdf { .. } => "Box SDF",
+            Node::GridLines { .. } => "Grid Lines",
+            Node::HexGrid { .. } => "Hex Grid",
+            Node::Falloff { .. } => "Falloff",
+            Node::Fractal { .. } => "Fractal",
+            Node::Frequency { .. } => "Frequency",
+            Node::TriangleWave { .. } => "TriangleWave",
+            Node::TranslateXy { .. } => "Translate Xy",
+            Node::Abs => "Abs",
+            Node::Neg => "Neg",
+            Node::Sqrt => "Sqrt",
+            Node::Floor => "Floor",
+            Node::Ceil => "Ceil",
+            Node::Round => "Round",
+            Node::Sin => "Sin",
+            Node::Cos => "Cos",
+            Node::Tan => "Tan",
+            Node::Exp => "Exp",
+            Node::Ln => "Ln",
+            Node::Log2 => "Log2",
+            Node::Sign => "Sign",
+            Node::Fract => "Fract",
+            Node::Reciprocal => "Reciprocal",
+            Node::OneMinus => "One Minus",
+            Node::Saturate => "Saturate",
+            Node::Add { .. } => "Add",
+            Node::Sub { .. } => "Subtract",
+            Node::Mul { .. } => "Multiply",
+            Node::Div { .. } => "Divide",
+            Node::Rem { .. } => "Modulo",
+            Node::Pow { .. } => "Power",
+            Node::Min { .. } => "Min",
+            Node::Max { .. } => "Max",
+            Node::Step { .. } => "Step",
+            Node::Atan2 { .. } => "Atan2",
+            Node::Hypot { .. } => "Hypot",
+            Node::Lerp { .. } => "Lerp",
+            Node::InverseLerp { .. } => "Inverse Lerp",
+            Node::Smoothstep { .. } => "Smoothstep",
+            Node::Smootherstep { .. } => "Smootherstep",
+            Node::Clamp { .. } => "Clamp",
+            Node::SmoothMin { .. } => "Smooth Min",
+            Node::SmoothMax { .. } => "Smooth Max",
+            Node::Remap { .. } => "Remap",
+            Node::Normalize { .. } => "Normalize",
+            Node::Expression { .. } => "Expression",
+            Node::Curve(_) => "Curve",
+            Node::Terrace { .. } => "Terrace",
+            Node::Quantize { .. } => "Quantize",
+            Node::Select { .. } => "Select",
+            Node::Blend { .. } => "Blend",
+            Node::Sum { .. } => "Sum",
+            Node::Product { .. } => "Product",
+            Node::Minimum { .. } => "Minimum",
+            Node::Maximum { .. } => "Maximum",
+            Node::Average { .. } => "Average",
+            Node::Seed { .. } => "Seed",
+            Node::AddSeed { .. } => "Add Seed",
+            Node::MulSeed { .. } => "Multiply Seed",
+            Node::Position => "Position",
+            Node::Number { .. } => "Number",
+            Node::Group { .. } => "Group",
+            Node::GroupInput { .. } => "Group Input",
+            Node::GroupOutput { .. } => "Group Output",
+        }
+    }
+
+    /// Whether a wire connected to this input is used when evaluating the node.
+    ///
+    /// Inputs like the octave count of [`Node::Fractal`] are parameters that can only be edited.
+    pub fn is_input_connectable(&self, input: usize) -> bool {
+        match self {
+            Node::Fractal { .. }
+            | Node::Seed { .. }
+            | Node::AddSeed { .. }
+            | Node::MulSeed { .. } => input == 0,
+            Node::GroupInput { .. } => false,
+            _ => true,
+        }
+    }
+
+    /// The number of inputs of nodes like [`Node::Sum`], which get a new input
+    /// whenever their last one is connected.
+    pub fn variadic_inputs(&self) -> Option<usize> {
+        match *self {
+            Node::Sum { ref weights } => Some(weights.len()),
+            Node::Product { inputs }
+            | Node::Minimum { inputs }
+            | Node::Maximum { inputs }
+            | Node::Average { inputs } => Some(inputs),
+            _ => None,
+        }
+    }
+
+    /// Sets the number of inputs of a variadic node, but to at least two.
+    /// New inputs of [`Node::Sum`] have a weight of 1.
+    pub fn set_variadic_inputs(&mut self, count: usize) {
+        let count = count.max(2);
+
+        match self {
+            Node::Sum { weights } => weights.resize(count, 1.0),
+            Node::Product { inputs }
+            | Node::Minimum { inputs }
+            | Node::Maximum { inputs }
+            | Node::Average { inputs } => *inputs = count,
+            _ => (),
+        }
+    }
+}
+
+/// The shape of [`Node::Falloff`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum FalloffShape {
+    Circle,
+    Square,
+    /// A square whose corners are rounded with half its radius.
+    RoundedBox,
+}
+
+impl FalloffShape {
+    pub const ALL: [FalloffShape; 3] = [
+        FalloffShape::Circle,
+        FalloffShape::Square,
+        FalloffShape::RoundedBox,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FalloffShape::Circle => "Circle",
+            FalloffShape::Square => "Square",
+            FalloffShape::RoundedBox => "Rounded Box",
+        }
+    }
+}
+
+/// How [`Node::Cellular`] measures the distance to a cell point.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum CellMetric {
+    Euclidean,
+    /// The sum of the distances along the axes.
+    Manhattan,
+    /// The larger of the distances along the axes.
+    Chebyshev,
+    /// `(|x|^p + |y|^p)^(1/p)`, which is Manhattan for a p of 1 and Euclidean for 2.
+    Minkowski,
+}
+
+impl CellMetric {
+    pub const ALL: [CellMetric; 4] = [
+        CellMetric::Euclidean,
+        CellMetric::Manhattan,
+        CellMetric::Chebyshev,
+        CellMetric::Minkowski,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CellMetric::Euclidean => "Euclidean",
+            CellMetric::Manhattan => "Manhattan",
+            CellMetric::Chebyshev => "Chebyshev",
+            CellMetric::Minkowski => "Minkowski",
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! The curves of [`Node::Curve`](super::Node::Curve), which map the values of a noise to other values.
+
+use serde::{Deserialize, Serialize};
+
+/// A function drawn through points from left to right, flat before the first and after the last point.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Curve {
+    /// Points that aren't to the right of the point before them are ignored.
+    pub points: Vec<[f32; 2]>,
+    pub interpolation: Interpolation,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Straight lines between the points.
+    Linear,
+    /// Smooth, with the slope at a point being that between its neighbors (Catmull-Rom).
+    Cubic,
+    /// Smooth, but only rising or falling between two points like the points do, so it doesn't overshoot.
+    MonotoneCubic,
+}
+
+/// The part of a curve between two points, a cubic Hermite spline.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Segment {
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+    /// The slopes at `from` and `to`.
+    pub slopes: [f32; 2],
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Self {
+            points: vec![[-1.0, -1.0], [1.0, 1.0]],
+            interpolation: Interpolation::MonotoneCubic,
+        }
+    }
+}
+
+impl Interpolation {
+    pub const ALL: [Interpolation; 3] = [
+        Interpolation::Linear,
+        Interpolation::Cubic,
+        Interpolation::MonotoneCubic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Interpolation::Linear => "Linear",
+            Interpolation::Cubic => "Cubic",
+            Interpolation::MonotoneCubic => "Monotone Cubic",
+        }
+    }
+}
+
+impl Segment {
+    /// The value of the curve at `x`, given its value `before` this segment.
+    ///
+    /// Past the end of the segment, the value stays that of its last point.
+    pub fn sample(self, x: f32, before: f32) -> f32 {
+        if x < self.from[0] {
+            return before;
+        }
+
+        let dx = self.to[0] - self.from[0];
+        let t = ((x - self.from[0]) / dx).min(1.0);
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        (2.0 * t3 - 3.0 * t2 + 1.0) * self.from[1]
+            + (t3 - 2.0 * t2 + t) * dx * self.slopes[0]
+            + (3.0 * t2 - 2.0 * t3) * self.to[1]
+            + (t3 - t2) * dx * self.slopes[1]
+    }
+}
+
+impl Curve {
+    /// The value before the first segment, which is the value everywhere if there are less than two points.
+    pub fn start(&self) -> f32 {
+        self.points.first().map_or(0.0, |point| point[1])
+    }
+
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut points = self.points.clone();
+        points.dedup_by(|point, previous| point[0] <= previous[0]);
+
+        if points.len() < 2 {
+            return Vec::new();
+        }
+
+        let secants: Vec<f32> = points
+            .windows(2)
+            .map(|pair| (pair[1][1] - pair[0][1]) / (pair[1][0] - pair[0][0]))
+            .collect();
+
+        let last = points.len() - 1;
+
+        // the slopes at the points, the ends have the slope of their segment
+        let slopes: Vec<f32> = (0..points.len())
+            .map(|i| match (i, self.interpolation) {
+                (0, _) => secants[0],
+                (i, _) if i == last => secants[last - 1],
+                (_, Interpolation::Linear) => 0.0,
+                (i, Interpolation::Cubic) => {
+                    let [before, after] = [points[i - 1], points[i + 1]];
+                    (after[1] - before[1]) / (after[0] - before[0])
+                }
+                (i, Interpolation::MonotoneCubic) => {
+                    let [d0, d1] = [secants[i - 1], secants[i]];
+
+                    if d0 * d1 <= 0.0 {
+                        return 0.0;
+                    }
+
+                    // weighted harmonic mean of the secants (Fritsch-Butland)
+                    let h0 = points[i][0] - points[i - 1][0];
+                    let h1 = points[i + 1][0] - points[i][0];
+                    let w0 = 2.0 * h1 + h0;
+                    let w1 = h1 + 2.0 * h0;
+                    (w0 + w1) / (w0 / d0 + w1 / d1)
+                }
+            })
+            .collect();
+
+        points
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| Segment {
+                from: pair[0],
+                to: pair[1],
+                slopes: match self.interpolation {
+                    Interpolation::Linear => [secants[i]; 2],
+                    _ => [slopes[i], slopes[i + 1]],
+                },
+            })
+            .collect()
+    }
+
+    pub fn sample(&self, x: f32) -> f32 {
+        self.segments()
+            .iter()
+            .fold(self.start(), |before, segment| segment.sample(x, before))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(interpolation: Interpolation) -> Curve {
+        Curve {
+            points: vec![[-1.0, 0.0], [0.0, 1.0], [0.5, 1.0], [1.0, -1.0]],
+            interpolation,
+        }
+    }
+
+    #[test]
+    fn passes_through_points_and_is_flat_outside() {
+        for interpolation in Interpolation::ALL {
+            let curve = curve(interpolation);
+
+            for [x, y] in curve.points.clone() {
+                assert!(
+                    (curve.sample(x) - y).abs() < 1e-6,
+                    "{interpolation:?} at {x}"
+                );
+            }
+
+            assert_eq!(curve.sample(-2.0), 0.0);
+            assert_eq!(curve.sample(2.0), -1.0);
+        }
+    }
+
+    #[test]
+    fn linear_is_straight() {
+        let curve = curve(Interpolation::Linear);
+        assert!((curve.sample(-0.5) - 0.5).abs() < 1e-6);
+        assert!((curve.sample(0.75) - 0.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn only_monotone_cubic_stays_within_points() {
+        let overshoots = |curve: &Curve| (0..=100).any(|i| curve.sample(i as f32 * 0.01) > 1.0);
+
+        assert!(overshoots(&curve(Interpolation::Cubic)));
+        assert!(!overshoots(&curve(Interpolation::MonotoneCubic)));
+    }
+}
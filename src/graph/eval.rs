@@ -0,0 +1,1565 @@
+use std::{cell::RefCell, fmt};
+
+use noise_functions::Sample;
+
+use super::{
+    curve::Segment,
+    expression::ParseError,
+    ir::{self, BinaryOp, CellKind, CellularOutput, Op, Primitive, TernaryOp, UnaryOp},
+    CellMetric, Graph, GroupId, InPinId, NodeId, OutPinId,
+};
+
+/// Why a graph can't be read or a sampler can't be built.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The text isn't a graph.
+    Parse(ron::error::SpannedError),
+    /// The graph has no node with this id.
+    NodeNotFound(NodeId),
+    /// The node has no such output, or the output can't be evaluated like that of a [`Node::GroupOutput`](super::Node::GroupOutput).
+    InvalidOutput(OutPinId),
+    /// A wire connects a node the graph doesn't have.
+    InvalidWire(OutPinId, InPinId),
+    /// The node's output is connected back to its inputs.
+    Cycle(NodeId),
+    /// A group node refers to a group the graph doesn't have.
+    GroupNotFound(GroupId),
+    /// The group contains a group node of itself.
+    RecursiveGroup(GroupId),
+    /// The expression of a [`Node::Expression`](super::Node::Expression) can't be parsed.
+    Expression { node: NodeId, error: ParseError },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => error.fmt(f),
+            Error::NodeNotFound(id) => write!(f, "there is no node with id {}", id.0),
+            Error::InvalidOutput(pin) => {
+                write!(
+                    f,
+                    "output {} of node {} can't be evaluated",
+                    pin.output, pin.node.0
+                )
+            }
+            Error::InvalidWire(from, to) => write!(
+                f,
+                "a wire connects node {} to node {}, which don't both exist",
+                from.node.0, to.node.0
+            ),
+            Error::Cycle(id) => write!(f, "node {} is connected to its own input", id.0),
+            Error::GroupNotFound(id) => write!(f, "there is no group with id {}", id.0),
+            Error::RecursiveGroup(id) => write!(f, "group {} contains itself", id.0),
+            Error::Expression { node, error } => {
+                write!(f, "the expression of node {} has an error: {error}", node.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Builds the noise of a node's output.
+///
+/// Graphs are two-dimensional, so the sampler takes two-dimensional points.
+pub fn build_sampler(graph: &Graph, output: OutPinId) -> Result<Box<dyn Sample<2>>, Error> {
+    Ok(Box::new(Sampler::new(graph, output)?))
+}
+
+/// A node's output compiled to a flat program.
+///
+/// The instructions are sorted so that every one comes after its inputs and writes its value to its own register,
+/// so a node that several others use is evaluated once per sample.
+///
+/// Nodes like [`Frequency`](super::Node::Frequency) sample their input at another point or with another seed.
+/// Their input is compiled to a separate block of instructions that they run with that point and seed.
+pub struct Sampler {
+    /// The first block computes the output.
+    blocks: Vec<Block>,
+    registers: usize,
+}
+
+/// Instructions that run with the same point and seed.
+struct Block {
+    /// The register of the first instruction, the others write the registers after it.
+    base: usize,
+    instructions: Vec<Instruction>,
+    /// The [`ir::Op`] of each instruction.
+    ops: Vec<usize>,
+}
+
+/// The operands are registers, `block`s are indices into [`Sampler::blocks`].
+#[derive(Clone, Copy)]
+enum Instruction {
+    Constant(f32),
+    Position(usize),
+    SeedValue,
+    Noise(Primitive),
+    Cell {
+        kind: CellKind,
+        jitter: usize,
+    },
+    Cellular {
+        metric: CellMetric,
+        output: CellularOutput,
+        jitter: usize,
+        exponent: usize,
+    },
+    Fractal {
+        block: usize,
+        octaves: u32,
+        gain: f32,
+        lacunarity: f32,
+        weighted_strength: f32,
+    },
+    Frequency {
+        block: usize,
+        frequency: usize,
+    },
+    TriangleWave {
+        input: usize,
+        frequency: usize,
+    },
+    Translate {
+        block: usize,
+        x: usize,
+        y: usize,
+    },
+    Unary(UnaryOp, usize),
+    Binary(BinaryOp, usize, usize),
+    Ternary(TernaryOp, usize, usize, usize),
+    Spline {
+        input: usize,
+        before: usize,
+        segment: Segment,
+    },
+    Seed {
+        block: usize,
+        seed: i32,
+    },
+    AddSeed {
+        block: usize,
+        add: i32,
+    },
+    MulSeed {
+        block: usize,
+        mul: i32,
+    },
+}
+
+/// The number of points that [`Sampler::sample_batch`] evaluates an instruction for at once.
+const LANES: usize = 64;
+
+/// What [`Sampler::sample_batch_with`] does with an instruction of the output block.
+pub enum Slot<'a> {
+    Compute,
+    /// Computes the values and also writes them to the slice.
+    Record(&'a mut [f32]),
+    /// Takes the values from the slice instead of computing them.
+    Known(&'a [f32]),
+}
+
+/// Scratch memory for [`Sampler::sample_with_registers`] and [`Sampler::sample_batch`].
+#[derive(Default)]
+pub struct Registers(Vec<f32>);
+
+impl Sampler {
+    pub fn new(graph: &Graph, output: OutPinId) -> Result<Self, Error> {
+        let program = ir::Program::new(graph, output)?;
+        Ok(Self::from_program(&program))
+    }
+
+    pub fn from_program(program: &ir::Program) -> Self {
+        let mut compiler = Compiler {
+            program,
+            blocks: Vec::new(),
+            registers: 0,
+            block_of_op: vec![None; program.ops.len()],
+        };
+
+        compiler.block(program.output);
+
+        Self {
+            blocks: compiler.blocks,
+            registers: compiler.registers,
+        }
+    }
+
+    /// Like [`Sample::sample_with_seed`], reusing the registers of earlier calls.
+    pub fn sample_with_registers(
+        &self,
+        point: [f32; 2],
+        seed: i32,
+        registers: &mut Registers,
+    ) -> f32 {
+        registers.0.resize(self.registers, 0.0);
+        self.run(0, point, seed, &mut registers.0)
+    }
+
+    fn run(&self, block: usize, point: [f32; 2], seed: i32, registers: &mut [f32]) -> f32 {
+        let Block {
+            base, instructions, ..
+        } = &self.blocks[block];
+
+        for (i, instruction) in instructions.iter().enumerate() {
+            let value = match *instruction {
+                Instruction::Constant(value) => value,
+                Instruction::Position(axis) => point[axis],
+                Instruction::SeedValue => seed as f32,
+                Instruction::Noise(primitive) => primitive.sample(point, seed),
+                Instruction::Cell { kind, jitter } => kind.sample(registers[jitter], point, seed),
+                Instruction::Cellular {
+                    metric,
+                    output,
+                    jitter,
+                    exponent,
+                } => {
+                    let cells =
+                        ir::cells(point, seed, registers[jitter], metric, registers[exponent]);
+                    output.of(&cells)
+                }
+                Instruction::Fractal {
+                    block,
+                    octaves,
+                    gain,
+                    lacunarity,
+                    weighted_strength,
+                } => {
+                    let mut sum = 0.0;
+                    let mut amp = ir::fractal_bounding(octaves, gain);
+                    let mut point = point;
+                    let mut seed = seed;
+
+                    for _ in 0..octaves {
+                        let noise = self.run(block, point, seed, registers);
+                        seed = seed.wrapping_add(1);
+                        sum += noise * amp;
+                        amp *= ir::lerp(1.0, (noise + 1.0).min(2.0) * 0.5, weighted_strength);
+                        point = point.map(|x| x * lacunarity);
+                        amp *= gain;
+                    }
+
+                    sum
+                }
+                Instruction::Frequency { block, frequency } => {
+                    let frequency = registers[frequency];
+                    self.run(block, point.map(|x| x * frequency), seed, registers)
+                }
+                Instruction::TriangleWave { input, frequency } => {
+                    let value = registers[input] * registers[frequency];
+                    let fract = value - (value + 0.5).floor();
+                    fract.abs() * 4.0 - 1.0
+                }
+                Instruction::Translate { block, x, y } => {
+                    let point = [point[0] + registers[x], point[1] + registers[y]];
+                    self.run(block, point, seed, registers)
+                }
+                Instruction::Unary(op, input) => op.apply(registers[input]),
+                Instruction::Binary(op, lhs, rhs) => op.apply(registers[lhs], registers[rhs]),
+                Instruction::Ternary(op, a, b, c) => {
+                    op.apply(registers[a], registers[b], registers[c])
+                }
+                Instruction::Spline {
+                    input,
+                    before,
+                    segment,
+                } => segment.sample(registers[input], registers[before]),
+                Instruction::Seed { block, seed } => self.run(block, point, seed, registers),
+                Instruction::AddSeed { block, add } => {
+                    self.run(block, point, seed.wrapping_add(add), registers)
+                }
+                Instruction::MulSeed { block, mul } => {
+                    self.run(block, point, seed.wrapping_mul(mul), registers)
+                }
+            };
+
+            registers[base + i] = value;
+        }
+
+        registers[base + instructions.len() - 1]
+    }
+
+    /// Samples the points `(xs[i], ys[i])` into `out[i]`.
+    ///
+    /// Every instruction is evaluated for up to 64 points before the next one, which saves dispatching
+    /// it per point and lets the compiler vectorize the arithmetic. The noise primitives are sampled
+    /// with [`Primitive::sample_batch`], which still computes one point at a time:
+    /// `noise_functions` has no batched sampling and its SIMD support needs the nightly compiler.
+    ///
+    /// The values are bit-identical to those of [`Sample::sample_with_seed`],
+    /// each point goes through the same operations in the same order.
+    pub fn sample_batch(
+        &self,
+        xs: &[f32],
+        ys: &[f32],
+        seed: i32,
+        out: &mut [f32],
+        registers: &mut Registers,
+    ) {
+        self.sample_batch_with(xs, ys, seed, out, registers, &mut []);
+    }
+
+    /// The [`ir::Op`]s of the instructions that run at the sampled points, in the order they run.
+    pub fn output_ops(&self) -> &[usize] {
+        &self.blocks[0].ops
+    }
+
+    /// Like [`Sampler::sample_batch`], with `slots[i]` saying what to do with the instruction of [`Sampler::output_ops`]`[i]`.
+    ///
+    /// The slices of the slots have a value for every point. Instructions without a slot are computed.
+    pub fn sample_batch_with(
+        &self,
+        xs: &[f32],
+        ys: &[f32],
+        seed: i32,
+        out: &mut [f32],
+        registers: &mut Registers,
+        slots: &mut [Slot],
+    ) {
+        assert!(
+            xs.len() == out.len() && ys.len() == out.len(),
+            "as many coordinates as outputs"
+        );
+
+        registers.0.resize(self.registers * LANES, 0.0);
+
+        for (chunk, ((xs, ys), out)) in xs
+            .chunks(LANES)
+            .zip(ys.chunks(LANES))
+            .zip(out.chunks_mut(LANES))
+            .enumerate()
+        {
+            let points = chunk * LANES..chunk * LANES + out.len();
+            let output = self.run_batch(0, xs, ys, seed, &mut registers.0, slots, points);
+            out.copy_from_slice(&registers.0[lanes(output, out.len())]);
+        }
+    }
+
+    /// Runs a block that an instruction calls, for up to [`LANES`] points, and returns its output register.
+    fn run_nested_batch(
+        &self,
+        block: usize,
+        xs: &[f32],
+        ys: &[f32],
+        seed: i32,
+        registers: &mut [f32],
+    ) -> usize {
+        self.run_batch(block, xs, ys, seed, registers, &mut [], 0..xs.len())
+    }
+
+    /// Runs the block for up to [`LANES`] points and returns its output register.
+    ///
+    /// `points` are the indices of the points in the slices of the `slots`.
+    // indexing the lanes of several registers reads better than zipping them
+    #[allow(clippy::needless_range_loop, clippy::too_many_arguments)]
+    fn run_batch(
+        &self,
+        block: usize,
+        xs: &[f32],
+        ys: &[f32],
+        seed: i32,
+        registers: &mut [f32],
+        slots: &mut [Slot],
+        points: std::ops::Range<usize>,
+    ) -> usize {
+        let Block {
+            base, instructions, ..
+        } = &self.blocks[block];
+        let len = xs.len();
+
+        for (i, instruction) in instructions.iter().enumerate() {
+            let mut values = [0.0; LANES];
+            let values = &mut values[..len];
+
+            if let Some(Slot::Known(known)) = slots.get(i) {
+                registers[lanes(base + i, len)].copy_from_slice(&known[points.clone()]);
+                continue;
+            }
+
+            match *instruction {
+                Instruction::Constant(value) => values.fill(value),
+                Instruction::Position(axis) => values.copy_from_slice([xs, ys][axis]),
+                Instruction::SeedValue => values.fill(seed as f32),
+                Instruction::Noise(primitive) => primitive.sample_batch(xs, ys, seed, values),
+                Instruction::Cell { kind, jitter } => {
+                    let jitter = &registers[lanes(jitter, len)];
+
+                    for (((value, &x), &y), &jitter) in
+                        values.iter_mut().zip(xs).zip(ys).zip(jitter)
+                    {
+                        *value = kind.sample(jitter, [x, y], seed);
+                    }
+                }
+                Instruction::Cellular {
+                    metric,
+                    output,
+                    jitter,
+                    exponent,
+                } => {
+                    let jitter = &registers[lanes(jitter, len)];
+                    let exponent = &registers[lanes(exponent, len)];
+
+                    for ((((value, &x), &y), &jitter), &exponent) in
+                        values.iter_mut().zip(xs).zip(ys).zip(jitter).zip(exponent)
+                    {
+                        *value = output.of(&ir::cells([x, y], seed, jitter, metric, exponent));
+                    }
+                }
+                Instruction::Fractal {
+                    block,
+                    octaves,
+                    gain,
+                    lacunarity,
+                    weighted_strength,
+                } => {
+                    let mut amps = [ir::fractal_bounding(octaves, gain); LANES];
+                    let mut octave_xs = [0.0; LANES];
+                    let mut octave_ys = [0.0; LANES];
+                    octave_xs[..len].copy_from_slice(xs);
+                    octave_ys[..len].copy_from_slice(ys);
+                    let mut seed = seed;
+
+                    for _ in 0..octaves {
+                        let output = self.run_nested_batch(
+                            block,
+                            &octave_xs[..len],
+                            &octave_ys[..len],
+                            seed,
+                            registers,
+                        );
+                        let noise = &registers[lanes(output, len)];
+                        seed = seed.wrapping_add(1);
+
+                        for lane in 0..len {
+                            values[lane] += noise[lane] * amps[lane];
+                            amps[lane] *= ir::lerp(
+                                1.0,
+                                (noise[lane] + 1.0).min(2.0) * 0.5,
+                                weighted_strength,
+                            );
+                            octave_xs[lane] *= lacunarity;
+                            octave_ys[lane] *= lacunarity;
+                            amps[lane] *= gain;
+                        }
+                    }
+                }
+                Instruction::Frequency { block, frequency } => {
+                    let frequency = &registers[lanes(frequency, len)];
+                    let mut scaled_xs = [0.0; LANES];
+                    let mut scaled_ys = [0.0; LANES];
+
+                    for lane in 0..len {
+                        scaled_xs[lane] = xs[lane] * frequency[lane];
+                        scaled_ys[lane] = ys[lane] * frequency[lane];
+                    }
+
+                    let output = self.run_nested_batch(
+                        block,
+                        &scaled_xs[..len],
+                        &scaled_ys[..len],
+                        seed,
+                        registers,
+                    );
+                    values.copy_from_slice(&registers[lanes(output, len)]);
+                }
+                Instruction::TriangleWave { input, frequency } => {
+                    let input = &registers[lanes(input, len)];
+                    let frequency = &registers[lanes(frequency, len)];
+
+                    for lane in 0..len {
+                        let value = input[lane] * frequency[lane];
+                        let fract = value - (value + 0.5).floor();
+                        values[lane] = fract.abs() * 4.0 - 1.0;
+                    }
+                }
+                Instruction::Translate { block, x, y } => {
+                    let x = &registers[lanes(x, len)];
+                    let y = &registers[lanes(y, len)];
+                    let mut translated_xs = [0.0; LANES];
+                    let mut translated_ys = [0.0; LANES];
+
+                    for lane in 0..len {
+                        translated_xs[lane] = xs[lane] + x[lane];
+                        translated_ys[lane] = ys[lane] + y[lane];
+                    }
+
+                    let output = self.run_nested_batch(
+                        block,
+                        &translated_xs[..len],
+                        &translated_ys[..len],
+                        seed,
+                        registers,
+                    );
+                    values.copy_from_slice(&registers[lanes(output, len)]);
+                }
+                Instruction::Unary(op, input) => {
+                    for (value, &x) in values.iter_mut().zip(&registers[lanes(input, len)]) {
+                        *value = op.apply(x);
+                    }
+                }
+                Instruction::Binary(op, lhs, rhs) => {
+                    let lhs = &registers[lanes(lhs, len)];
+                    let rhs = &registers[lanes(rhs, len)];
+
+                    for ((value, &a), &b) in values.iter_mut().zip(lhs).zip(rhs) {
+                        *value = op.apply(a, b);
+                    }
+                }
+                Instruction::Ternary(op, a, b, c) => {
+                    let a = &registers[lanes(a, len)];
+                    let b = &registers[lanes(b, len)];
+                    let c = &registers[lanes(c, len)];
+
+                    for lane in 0..len {
+                        values[lane] = op.apply(a[lane], b[lane], c[lane]);
+                    }
+                }
+                Instruction::Spline {
+                    input,
+                    before,
+                    segment,
+                } => {
+                    let input = &registers[lanes(input, len)];
+                    let before = &registers[lanes(before, len)];
+
+                    for ((value, &x), &before) in values.iter_mut().zip(input).zip(before) {
+                        *value = segment.sample(x, before);
+                    }
+                }
+                Instruction::Seed { block, seed } => {
+                    let output = self.run_nested_batch(block, xs, ys, seed, registers);
+                    values.copy_from_slice(&registers[lanes(output, len)]);
+                }
+                Instruction::AddSeed { block, add } => {
+                    let output =
+                        self.run_nested_batch(block, xs, ys, seed.wrapping_add(add), registers);
+                    values.copy_from_slice(&registers[lanes(output, len)]);
+                }
+                Instruction::MulSeed { block, mul } => {
+                    let output =
+                        self.run_nested_batch(block, xs, ys, seed.wrapping_mul(mul), registers);
+                    values.copy_from_slice(&registers[lanes(output, len)]);
+                }
+            }
+
+            registers[lanes(base + i, len)].copy_from_slice(values);
+
+            if let Some(Slot::Record(record)) = slots.get_mut(i) {
+                record[points.clone()].copy_from_slice(values);
+            }
+        }
+
+        base + instructions.len() - 1
+    }
+}
+
+/// Where the values of the register's first `len` lanes are.
+fn lanes(register: usize, len: usize) -> std::ops::Range<usize> {
+    register * LANES..register * LANES + len
+}
+
+impl Sample<2> for Sampler {
+    fn sample_with_seed(&self, point: [f32; 2], seed: i32) -> f32 {
+        thread_local! {
+            static REGISTERS: RefCell<Registers> = RefCell::default();
+        }
+
+        REGISTERS.with(|registers| match registers.try_borrow_mut() {
+            Ok(mut registers) => self.sample_with_registers(point, seed, &mut registers),
+            // sampled while sampling another sampler on this thread
+            Err(_) => self.sample_with_registers(point, seed, &mut Registers::default()),
+        })
+    }
+}
+
+struct Compiler<'a> {
+    program: &'a ir::Program,
+    blocks: Vec<Block>,
+    registers: usize,
+    /// The block whose output is the op.
+    block_of_op: Vec<Option<usize>>,
+}
+
+impl Compiler<'_> {
+    /// Returns the block computing the op, compiling it if there is none yet.
+    fn block(&mut self, op: usize) -> usize {
+        if let Some(block) = self.block_of_op[op] {
+            return block;
+        }
+
+        let block = self.blocks.len();
+        self.block_of_op[op] = Some(block);
+        self.blocks.push(Block {
+            base: 0,
+            instructions: Vec::new(),
+            ops: Vec::new(),
+        });
+
+        let mut instructions = Vec::new();
+        let mut register_of_op = vec![None; self.program.ops.len()];
+        self.emit(op, &mut instructions, &mut register_of_op);
+
+        // every block has its own registers, so a block doesn't overwrite those of the block running it
+        let base = self.registers;
+        self.registers += instructions.len();
+
+        for instruction in &mut instructions {
+            instruction.offset_registers(base);
+        }
+
+        let mut ops = vec![0; instructions.len()];
+
+        for (op, register) in register_of_op.into_iter().enumerate() {
+            if let Some(register) = register {
+                ops[register] = op;
+            }
+        }
+
+        self.blocks[block] = Block {
+            base,
+            instructions,
+            ops,
+        };
+        block
+    }
+
+    /// Appends the instructions computing the op after those of its inputs
+    /// and returns its register, counted from the start of the block.
+    fn emit(
+        &mut self,
+        op: usize,
+        instructions: &mut Vec<Instruction>,
+        register_of_op: &mut [Option<usize>],
+    ) -> usize {
+        if let Some(register) = register_of_op[op] {
+            return register;
+        }
+
+        let instruction = match self.program.ops[op] {
+            Op::Constant(value) => Instruction::Constant(value),
+            Op::Position(axis) => Instruction::Position(axis),
+            Op::SeedValue => Instruction::SeedValue,
+            Op::Noise(primitive) => Instruction::Noise(primitive),
+            Op::Cell { kind, jitter } => Instruction::Cell {
+                kind,
+                jitter: self.emit(jitter, instructions, register_of_op),
+            },
+            Op::Cellular {
+                metric,
+                output,
+                jitter,
+                exponent,
+            } => Instruction::Cellular {
+                metric,
+                output,
+                jitter: self.emit(jitter, instructions, register_of_op),
+                exponent: self.emit(exponent, instructions, register_of_op),
+            },
+            Op::Fractal {
+                input,
+                octaves,
+                gain,
+                lacunarity,
+                weighted_strength,
+            } => Instruction::Fractal {
+                block: self.block(input),
+                octaves,
+                gain,
+                lacunarity,
+                weighted_strength,
+            },
+            Op::Frequency { input, frequency } => Instruction::Frequency {
+                block: self.block(input),
+                frequency: self.emit(frequency, instructions, register_of_op),
+            },
+            Op::TriangleWave { input, frequency } => Instruction::TriangleWave {
+                input: self.emit(input, instructions, register_of_op),
+                frequency: self.emit(frequency, instructions, register_of_op),
+            },
+            Op::Translate { input, x, y } => Instruction::Translate {
+                block: self.block(input),
+                x: self.emit(x, instructions, register_of_op),
+                y: self.emit(y, instructions, register_of_op),
+            },
+            Op::Unary(op, input) => {
+                Instruction::Unary(op, self.emit(input, instructions, register_of_op))
+            }
+            Op::Binary(op, lhs, rhs) => Instruction::Binary(
+                op,
+                self.emit(lhs, instructions, register_of_op),
+                self.emit(rhs, instructions, register_of_op),
+            ),
+            Op::Ternary(op, a, b, c) => Instruction::Ternary(
+                op,
+                self.emit(a, instructions, register_of_op),
+                self.emit(b, instructions, register_of_op),
+                self.emit(c, instructions, register_of_op),
+            ),
+            Op::Spline {
+                input,
+                before,
+                segment,
+            } => Instruction::Spline {
+                input: self.emit(input, instructions, register_of_op),
+                before: self.emit(before, instructions, register_of_op),
+                segment,
+            },
+            Op::Seed { input, seed } => Instruction::Seed {
+                block: self.block(input),
+                seed,
+            },
+            Op::AddSeed { input, add } => Instruction::AddSeed {
+                block: self.block(input),
+                add,
+            },
+            Op::MulSeed { input, mul } => Instruction::MulSeed {
+                block: self.block(input),
+                mul,
+            },
+        };
+
+        instructions.push(instruction);
+        register_of_op[op] = Some(instructions.len() - 1);
+        instructions.len() - 1
+    }
+}
+
+impl Instruction {
+    fn offset_registers(&mut self, base: usize) {
+        match self {
+            Instruction::Constant(_)
+            | Instruction::Position(_)
+            | Instruction::SeedValue
+            | Instruction::Noise(_)
+            | Instruction::Fractal { .. }
+            | Instruction::Seed { .. }
+            | Instruction::AddSeed { .. }
+            | Instruction::MulSeed { .. } => {}
+            Instruction::Cell { jitter, .. } => *jitter += base,
+            Instruction::Cellular {
+                jitter, exponent, ..
+            } => {
+                *jitter += base;
+                *exponent += base;
+            }
+            Instruction::Frequency { frequency, .. } => *frequency += base,
+            Instruction::TriangleWave { input, frequency } => {
+                *input += base;
+                *frequency += base;
+            }
+            Instruction::Translate { x, y, .. } => {
+                *x += base;
+                *y += base;
+            }
+            Instruction::Unary(_, input) => *input += base,
+            Instruction::Binary(_, lhs, rhs) => {
+                *lhs += base;
+                *rhs += base;
+            }
+            Instruction::Ternary(_, a, b, c) => {
+                *a += base;
+                *b += base;
+                *c += base;
+            }
+            Instruction::Spline { input, before, .. } => {
+                *input += base;
+                *before += base;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use noise_functions::Sample;
+
+    use super::{build_sampler, Error, Registers, Sampler};
+    use crate::graph::{
+        curve::{Curve, Interpolation},
+        ir::{self, Op, Program},
+        CellMetric, FalloffShape, Graph, Group, GroupId, GroupLibrary, InPinId, Node, NodeId,
+        OutPinId,
+    };
+
+    fn connect(graph: &mut Graph, from: NodeId, output: usize, to: NodeId, input: usize) {
+        graph.connect(OutPinId { node: from, output }, InPinId { node: to, input });
+    }
+
+    #[test]
+    fn batch_matches_single_points() {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        let perlin = graph.insert_node(pos, Node::Perlin);
+        let fractal = graph.insert_node(
+            pos,
+            Node::Fractal {
+                octaves: 3,
+                gain: 0.5,
+                lacunarity: 2.0,
+                weighted_strength: 0.5,
+            },
+        );
+        let position = graph.insert_node(pos, Node::Position);
+        let cell = graph.insert_node(pos, Node::CellDistance { jitter: 1.0 });
+        let frequency = graph.insert_node(pos, Node::Frequency { frequency: 1.0 });
+        let translate = graph.insert_node(pos, Node::TranslateXy { x: 0.0, y: 0.5 });
+        let add_seed = graph.insert_node(pos, Node::AddSeed { add: 7 });
+        let triangle = graph.insert_node(pos, Node::TriangleWave { frequency: 2.0 });
+        let sin = graph.insert_node(pos, Node::Sin);
+        let mul = graph.insert_node(pos, Node::Mul { lhs: 0.0, rhs: 0.0 });
+        let clamp = graph.insert_node(
+            pos,
+            Node::Clamp {
+                value: 0.0,
+                min: -0.5,
+                max: 0.5,
+            },
+        );
+
+        connect(&mut graph, perlin, 0, fractal, 0);
+        connect(&mut graph, fractal, 0, frequency, 0);
+        connect(&mut graph, position, 1, frequency, 1);
+        connect(&mut graph, cell, 0, translate, 0);
+        connect(&mut graph, fractal, 0, translate, 1);
+        connect(&mut graph, translate, 0, add_seed, 0);
+        connect(&mut graph, add_seed, 0, triangle, 0);
+        connect(&mut graph, frequency, 0, sin, 0);
+        connect(&mut graph, sin, 0, mul, 0);
+        connect(&mut graph, triangle, 0, mul, 1);
+        connect(&mut graph, mul, 0, clamp, 0);
+
+        let sampler = Sampler::new(
+            &graph,
+            OutPinId {
+                node: clamp,
+                output: 0,
+            },
+        )
+        .unwrap();
+
+        // more points than one batch has lanes
+        let xs: Vec<f32> = (0..150).map(|i| (i % 13) as f32 * 0.41 - 2.0).collect();
+        let ys: Vec<f32> = (0..150).map(|i| (i / 13) as f32 * 0.37 - 2.0).collect();
+        let mut batch = vec![0.0; xs.len()];
+        let mut registers = Registers::default();
+
+        for seed in [0, 1, 42] {
+            sampler.sample_batch(&xs, &ys, seed, &mut batch, &mut registers);
+
+            for ((&x, &y), &actual) in xs.iter().zip(&ys).zip(&batch) {
+                let expected = sampler.sample_with_seed([x, y], seed);
+                assert_eq!(
+                    expected.to_bits(),
+                    actual.to_bits(),
+                    "{expected} != {actual} at {:?} with seed {seed}",
+                    [x, y]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn broken_wires_are_errors() {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        let abs = graph.insert_node(pos, Node::Abs);
+        let neg = graph.insert_node(pos, Node::Neg);
+        let pin = OutPinId {
+            node: abs,
+            output: 0,
+        };
+
+        connect(&mut graph, NodeId(7), 0, abs, 0);
+        assert!(matches!(
+            build_sampler(&graph, pin),
+            Err(Error::InvalidWire(..))
+        ));
+        assert!(matches!(
+            Graph::from_text(&graph.to_text()),
+            Err(Error::InvalidWire(..))
+        ));
+
+        connect(&mut graph, neg, 0, abs, 0);
+        connect(&mut graph, abs, 0, neg, 0);
+        assert!(matches!(build_sampler(&graph, pin), Err(Error::Cycle(_))));
+        assert!(matches!(
+            Graph::from_text(&graph.to_text()),
+            Err(Error::Cycle(_))
+        ));
+        assert!(matches!(Program::new(&graph, pin), Err(Error::Cycle(_))));
+    }
+
+    #[test]
+    fn invalid_expressions_are_errors() {
+        let mut graph = Graph::new();
+        let node = graph.insert_node(
+            [0.0; 2],
+            Node::Expression {
+                expression: "a +".into(),
+                a: 0.0,
+                b: 0.0,
+                c: 0.0,
+            },
+        );
+
+        let pin = OutPinId { node, output: 0 };
+        let error = Sampler::new(&graph, pin).err().unwrap();
+        assert!(matches!(error, Error::Expression { node: n, .. } if n == node));
+    }
+
+    #[test]
+    fn groups_are_shared_by_their_nodes() {
+        let pos = [0.0; 2];
+
+        let mut inner = Graph::new();
+        let input = inner.insert_node(
+            pos,
+            Node::GroupInput {
+                name: "Input".into(),
+                value: 0.5,
+            },
+        );
+        let neg = inner.insert_node(pos, Node::Neg);
+        let output = inner.insert_node(
+            pos,
+            Node::GroupOutput {
+                name: "Output".into(),
+            },
+        );
+        connect(&mut inner, input, 0, neg, 0);
+        connect(&mut inner, neg, 0, output, 0);
+
+        let mut graph = Graph::new();
+        let group = graph
+            .groups_mut()
+            .insert(Group::new("Negate".into(), inner));
+
+        // the first node uses the value of the group input, the second its own
+        let [a, b] =
+            [vec![], vec![2.0]].map(|values| graph.insert_node(pos, Node::Group { group, values }));
+        let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+        connect(&mut graph, a, 0, add, 0);
+        connect(&mut graph, b, 0, add, 1);
+
+        let pin = OutPinId {
+            node: add,
+            output: 0,
+        };
+        let noise = build_sampler(&graph, pin).unwrap();
+        assert_eq!(noise.sample_with_seed([0.0, 0.0], 0), -2.5);
+
+        let mut other = GroupLibrary::default();
+        let renamed = Group {
+            name: "Renamed".into(),
+            ..graph.groups().get(group).unwrap().clone()
+        };
+        other.insert_with_id(group, renamed);
+        let same = graph.groups().clone();
+        assert!(graph.groups_mut().merge(&same).is_empty());
+        assert_eq!(graph.groups_mut().merge(&other)[&group], GroupId(1));
+
+        let nested = graph.insert_node(
+            pos,
+            Node::Group {
+                group,
+                values: vec![],
+            },
+        );
+        let inner = &mut graph.groups_mut().get_mut(group).unwrap().graph;
+        inner.insert_node(
+            pos,
+            Node::Group {
+                group,
+                values: vec![],
+            },
+        );
+        assert!(matches!(
+            build_sampler(&graph, pin),
+            Err(Error::RecursiveGroup(_))
+        ));
+
+        graph[nested] = Node::Group {
+            group: GroupId(7),
+            values: vec![],
+        };
+        graph
+            .groups_mut()
+            .insert_with_id(group, other.get(group).unwrap().clone());
+        assert!(matches!(
+            build_sampler(&graph, pin),
+            Err(Error::GroupNotFound(GroupId(7)))
+        ));
+    }
+
+    #[test]
+    fn hashes_depend_on_ops_not_indices() {
+        let pos = [0.0; 2];
+
+        // the hash of the frequency op, after the ops of another input if `behind` is set
+        let hash = |frequency: f32, behind: bool| {
+            let mut graph = Graph::new();
+            let perlin = graph.insert_node(pos, Node::Perlin);
+            let node = graph.insert_node(pos, Node::Frequency { frequency });
+            connect(&mut graph, perlin, 0, node, 0);
+
+            let mut pin = OutPinId { node, output: 0 };
+
+            if behind {
+                let position = graph.insert_node(pos, Node::Position);
+                let abs = graph.insert_node(pos, Node::Abs);
+                let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+                connect(&mut graph, position, 0, abs, 0);
+                connect(&mut graph, abs, 0, add, 0);
+                connect(&mut graph, node, 0, add, 1);
+                pin.node = add;
+            }
+
+            let program = Program::new(&graph, pin).unwrap();
+            let index = program
+                .ops
+                .iter()
+                .position(|op| matches!(op, Op::Frequency { .. }))
+                .unwrap();
+
+            program.hashes()[index]
+        };
+
+        assert_eq!(hash(2.0, false), hash(2.0, true));
+        assert_ne!(hash(2.0, false), hash(3.0, false));
+        assert_ne!(hash(0.0, false), hash(-0.0, false));
+    }
+
+    #[test]
+    fn deep_diamonds_are_lowered_once() {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        // every node adds the previous one to itself, which has 2^64 paths to the position
+        let mut node = graph.insert_node(pos, Node::Position);
+
+        for _ in 0..64 {
+            let add = graph.insert_node(pos, Node::Add { lhs: 0.0, rhs: 0.0 });
+            connect(&mut graph, node, 0, add, 0);
+            connect(&mut graph, node, 0, add, 1);
+            node = add;
+        }
+
+        let pin = OutPinId { node, output: 0 };
+        let program = Program::new(&graph, pin).unwrap();
+        assert_eq!(program.ops.len(), 65);
+
+        let sampler = Sampler::new(&graph, pin).unwrap();
+        assert_eq!(sampler.sample_with_seed([1.0, 0.0], 0), 2.0f32.powi(64));
+    }
+
+    /// Checks that the node computes `formula` of the value of its input `input`,
+    /// to which the x coordinate is connected.
+    fn assert_formula(node: Node, input: usize, formula: impl Fn(f32) -> f32) {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        let position = graph.insert_node(pos, Node::Position);
+        let name = node.name().to_owned();
+        let node = graph.insert_node(pos, node);
+        connect(&mut graph, position, 0, node, input);
+
+        let pin = OutPinId { node, output: 0 };
+        let sampler = Sampler::new(&graph, pin).unwrap();
+        let program = Program::new(&graph, pin).unwrap();
+
+        for i in -30..=30 {
+            let x = i as f32 * 0.1;
+            let expected = formula(x);
+
+            for actual in [
+                sampler.sample_with_seed([x, 0.0], 0),
+                program.sample([x, 0.0], 0),
+            ] {
+                assert!(
+                    (expected - actual).abs() <= 1e-6,
+                    "{name}: {expected} != {actual} at {x}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn interpolation_matches_formulas() {
+        let smoothstep = |x: f32| {
+            let t = ((x + 1.0) / 2.0).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        };
+
+        let smootherstep = |x: f32| {
+            let t = ((x + 1.0) / 2.0).clamp(0.0, 1.0);
+            6.0 * t.powi(5) - 15.0 * t.powi(4) + 10.0 * t.powi(3)
+        };
+
+        assert_formula(
+            Node::Lerp {
+                a: -1.0,
+                b: 3.0,
+                t: 0.0,
+            },
+            2,
+            |t| -1.0 + 4.0 * t,
+        );
+        assert_formula(
+            Node::Lerp {
+                a: 0.0,
+                b: 3.0,
+                t: 0.25,
+            },
+            0,
+            |a| 0.75 * a + 0.75,
+        );
+        assert_formula(
+            Node::InverseLerp {
+                a: -1.0,
+                b: 3.0,
+                value: 0.0,
+            },
+            2,
+            |x| (x + 1.0) / 4.0,
+        );
+        assert_formula(
+            Node::Smoothstep {
+                edge0: -1.0,
+                edge1: 1.0,
+                value: 0.0,
+            },
+            2,
+            smoothstep,
+        );
+        assert_formula(
+            Node::Smootherstep {
+                edge0: -1.0,
+                edge1: 1.0,
+                value: 0.0,
+            },
+            2,
+            smootherstep,
+        );
+        assert_formula(
+            Node::Step {
+                edge: 0.45,
+                value: 0.0,
+            },
+            1,
+            |x| if x < 0.45 { 0.0 } else { 1.0 },
+        );
+        assert_formula(
+            Node::Expression {
+                expression: "lerp(-1, 3, a) + smoothstep(-1, 1, a) * step(0, a)".into(),
+                a: 0.0,
+                b: 0.0,
+                c: 0.0,
+            },
+            0,
+            |x| -1.0 + 4.0 * x + smoothstep(x) * if x < 0.0 { 0.0 } else { 1.0 },
+        );
+    }
+
+    #[test]
+    fn remap_matches_formulas() {
+        let remap = |clamp| Node::Remap {
+            value: 0.0,
+            from_min: -1.0,
+            from_max: 1.0,
+            to_min: 2.0,
+            to_max: 4.0,
+            clamp,
+        };
+
+        assert_formula(remap(false), 0, |x| x + 3.0);
+        assert_formula(remap(true), 0, |x| x.clamp(-1.0, 1.0) + 3.0);
+        assert_formula(
+            Node::Remap {
+                value: 0.5,
+                from_min: 0.0,
+                from_max: 1.0,
+                to_min: 0.0,
+                to_max: 1.0,
+                clamp: false,
+            },
+            4,
+            |to_max| 0.5 * to_max,
+        );
+        assert_formula(Node::Normalize { min: 1.0, max: 3.0 }, 0, |x| x - 2.0);
+    }
+
+    #[test]
+    fn curve_matches_its_samples() {
+        for interpolation in Interpolation::ALL {
+            let curve = Curve {
+                points: vec![
+                    [-2.0, 1.0],
+                    [-0.5, -1.0],
+                    [0.0, 0.0],
+                    [0.0, 2.0],
+                    [2.5, 0.5],
+                ],
+                interpolation,
+            };
+
+            assert_formula(Node::Curve(curve.clone()), 0, |x| curve.sample(x));
+        }
+    }
+
+    #[test]
+    fn select_and_blend_match_formulas() {
+        let select = |falloff| Node::Select {
+            a: -1.0,
+            b: 2.0,
+            control: 0.0,
+            threshold: 0.5,
+            falloff,
+        };
+
+        assert_formula(select(0.0), 2, |x| if x < 0.5 { -1.0 } else { 2.0 });
+        assert_formula(select(1.0), 2, |x| {
+            let t = x.clamp(0.0, 1.0);
+            -1.0 + 3.0 * t * t * (3.0 - 2.0 * t)
+        });
+        assert_formula(
+            Node::Blend {
+                a: 1.0,
+                b: 3.0,
+                weight: 0.0,
+            },
+            2,
+            |x| 1.0 + 2.0 * x.clamp(0.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn terrace_and_quantize_match_formulas() {
+        let terrace = |smoothness, invert| Node::Terrace {
+            steps: 2,
+            heights: Vec::new(),
+            smoothness,
+            invert,
+        };
+
+        // terraces from -1 to 0 and from 0 to 1
+        let shape = |x: f32, shape: fn(f32) -> f32| {
+            let t = x.clamp(-1.0, 1.0) + 1.0;
+            let floor = t.floor().min(1.0);
+            -1.0 + floor + shape(t - floor)
+        };
+
+        assert_formula(terrace(0.5, false), 0, |x| shape(x, |t| t * t));
+        assert_formula(terrace(0.5, true), 0, |x| {
+            shape(x, |t| 1.0 - (1.0 - t) * (1.0 - t))
+        });
+        assert_formula(terrace(0.0, false), 0, |x| shape(x, |t| t.floor()));
+        assert_formula(
+            Node::Terrace {
+                steps: 1,
+                heights: vec![0.5, -0.5, 0.5],
+                smoothness: 1.0,
+                invert: false,
+            },
+            0,
+            |x| x.clamp(-0.5, 0.5),
+        );
+        assert_formula(
+            Node::Quantize {
+                levels: 3,
+                min: -1.5,
+                max: 1.5,
+            },
+            0,
+            |x| -1.5 + 1.5 * (x + 1.5).floor().clamp(0.0, 2.0),
+        );
+    }
+
+    #[test]
+    fn extended_math_matches_formulas() {
+        assert_formula(Node::Exp, 0, f32::exp);
+        assert_formula(Node::Sign, 0, |x| if x == 0.0 { 0.0 } else { x.signum() });
+        assert_formula(Node::Fract, 0, |x| x - x.floor());
+        assert_formula(Node::OneMinus, 0, |x| 1.0 - x);
+        assert_formula(Node::Saturate, 0, |x| x.clamp(0.0, 1.0));
+        assert_formula(Node::Atan2 { y: 0.5, x: 0.0 }, 1, |x| 0.5f32.atan2(x));
+        assert_formula(Node::Hypot { x: 0.0, y: 0.5 }, 0, |x| x.hypot(0.5));
+
+        let offset = |x: f32| {
+            let h = (0.5 - (x - 0.5).abs()).max(0.0) / 0.5;
+            h * h * 0.5 * 0.25
+        };
+
+        let smooth = |min: bool, k| {
+            let (lhs, rhs) = (0.0, 0.5);
+            if min {
+                Node::SmoothMin { lhs, rhs, k }
+            } else {
+                Node::SmoothMax { lhs, rhs, k }
+            }
+        };
+
+        assert_formula(smooth(true, 0.5), 0, |x| x.min(0.5) - offset(x));
+        assert_formula(smooth(false, 0.5), 0, |x| x.max(0.5) + offset(x));
+        assert_formula(smooth(true, 0.0), 0, |x| x.min(0.5));
+    }
+
+    #[test]
+    fn variadic_nodes_ignore_unconnected_inputs() {
+        let check = |node: Node, formula: fn(f32, f32) -> f32| {
+            let pos = [0.0; 2];
+            let mut graph = Graph::new();
+
+            // x and y connected to the first and third of three inputs
+            let position = graph.insert_node(pos, Node::Position);
+            let name = node.name().to_owned();
+            let node = graph.insert_node(pos, node);
+            connect(&mut graph, position, 0, node, 0);
+            connect(&mut graph, position, 1, node, 2);
+
+            let pin = OutPinId { node, output: 0 };
+            let sampler = Sampler::new(&graph, pin).unwrap();
+            let program = Program::new(&graph, pin).unwrap();
+
+            for point in [[0.5, -2.0], [-1.5, 0.25], [3.0, 3.0]] {
+                let expected = formula(point[0], point[1]);
+
+                for actual in [sampler.sample_with_seed(point, 0), program.sample(point, 0)] {
+                    assert_eq!(expected, actual, "{name} at {point:?}");
+                }
+            }
+        };
+
+        check(
+            Node::Sum {
+                weights: vec![2.0, 10.0, 0.5],
+            },
+            |x, y| x * 2.0 + y * 0.5,
+        );
+        check(Node::Product { inputs: 3 }, |x, y| x * y);
+        check(Node::Minimum { inputs: 3 }, f32::min);
+        check(Node::Maximum { inputs: 3 }, f32::max);
+        check(Node::Average { inputs: 3 }, |x, y| (x + y) / 2.0);
+
+        let empty = Node::Product { inputs: 2 };
+        let mut graph = Graph::new();
+        let node = graph.insert_node([0.0; 2], empty);
+        let sampler = Sampler::new(&graph, OutPinId { node, output: 0 }).unwrap();
+        assert_eq!(sampler.sample_with_seed([0.0, 0.0], 0), 1.0);
+    }
+
+    #[test]
+    fn patterns_match_shapes() {
+        let cases = [
+            (
+                Node::Checkerboard { size: 0.5 },
+                vec![
+                    ([0.25, 0.25], 1.0),
+                    ([0.75, 0.25], -1.0),
+                    ([-0.25, 0.25], -1.0),
+                ],
+            ),
+            (
+                Node::Stripes {
+                    width: 1.0,
+                    angle: 90.0,
+                },
+                vec![([0.0, 0.5], 1.0), ([0.0, 1.5], -1.0), ([0.0, -0.5], -1.0)],
+            ),
+            (
+                Node::Rings { width: 1.0 },
+                vec![([0.5, 0.0], 1.0), ([1.5, 0.0], -1.0), ([0.0, -2.5], 1.0)],
+            ),
+            (
+                Node::RadialGradient {
+                    x: 1.0,
+                    y: 0.0,
+                    radius: 2.0,
+                },
+                vec![([1.0, 0.0], 1.0), ([2.0, 0.0], 0.0), ([4.0, 0.0], -1.0)],
+            ),
+            (
+                Node::LinearGradient {
+                    angle: 0.0,
+                    length: 2.0,
+                },
+                vec![([0.5, 3.0], 0.5), ([-5.0, 0.0], -1.0)],
+            ),
+            (
+                Node::CircleSdf {
+                    x: 1.0,
+                    y: 1.0,
+                    radius: 1.0,
+                },
+                vec![([1.0, 1.0], -1.0), ([4.0, 5.0], 4.0)],
+            ),
+            (
+                Node::BoxSdf {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 2.0,
+                    height: 4.0,
+                },
+                vec![
+                    ([0.0, 0.0], -1.0),
+                    ([0.5, 1.0], -0.5),
+                    ([3.0, 0.0], 2.0),
+                    ([4.0, 6.0], 5.0),
+                ],
+            ),
+            (
+                Node::GridLines {
+                    spacing: 1.0,
+                    thickness: 0.2,
+                },
+                vec![([0.05, 0.5], 1.0), ([0.5, 0.5], -1.0), ([0.5, 2.02], 1.0)],
+            ),
+            (
+                Node::HexGrid {
+                    size: 1.0,
+                    thickness: 0.1,
+                },
+                vec![([0.0, 0.0], -1.0), ([0.5, 0.0], 1.0), ([0.5, 0.866], -1.0)],
+            ),
+            (
+                Node::Falloff {
+                    shape: FalloffShape::Circle,
+                    x: 1.0,
+                    y: 0.0,
+                    radius: 2.0,
+                    exponent: 1.0,
+                    invert: false,
+                },
+                vec![([1.0, 0.0], 1.0), ([2.0, 0.0], 0.5), ([4.0, 0.0], 0.0)],
+            ),
+            (
+                Node::Falloff {
+                    shape: FalloffShape::Square,
+                    x: 0.0,
+                    y: 0.0,
+                    radius: 2.0,
+                    exponent: 2.0,
+                    invert: true,
+                },
+                vec![([0.0, 0.0], 0.0), ([1.0, -1.0], 0.25), ([0.5, 3.0], 1.0)],
+            ),
+            (
+                Node::Falloff {
+                    shape: FalloffShape::RoundedBox,
+                    x: 0.0,
+                    y: 0.0,
+                    radius: 1.0,
+                    exponent: 1.0,
+                    invert: false,
+                },
+                vec![
+                    ([0.5, 0.5], 0.5),
+                    ([0.75, 0.75], 0.146_447),
+                    ([1.0, 0.0], 0.0),
+                ],
+            ),
+        ];
+
+        for (node, samples) in cases {
+            let name = node.name().to_owned();
+            let mut graph = Graph::new();
+            let node = graph.insert_node([0.0; 2], node);
+
+            let pin = OutPinId { node, output: 0 };
+            let sampler = Sampler::new(&graph, pin).unwrap();
+            let program = Program::new(&graph, pin).unwrap();
+
+            for (point, expected) in samples {
+                for actual in [sampler.sample_with_seed(point, 0), program.sample(point, 0)] {
+                    assert!(
+                        (expected - actual).abs() <= 1e-5,
+                        "{name}: {expected} != {actual} at {point:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cellular_outputs_agree() {
+        let pos = [0.0; 2];
+        let mut graph = Graph::new();
+
+        let mut cellular = |metric, exponent| {
+            graph.insert_node(
+                pos,
+                Node::Cellular {
+                    metric,
+                    jitter: 1.0,
+                    exponent,
+                },
+            )
+        };
+        let euclidean = cellular(CellMetric::Euclidean, 2.0);
+        let minkowski = cellular(CellMetric::Minkowski, 2.0);
+        let manhattan = cellular(CellMetric::Manhattan, 2.0);
+        let chebyshev = cellular(CellMetric::Chebyshev, 2.0);
+
+        let outputs = |node: NodeId| {
+            (0..5)
+                .map(|output| {
+                    let pin = OutPinId { node, output };
+                    (
+                        Sampler::new(&graph, pin).unwrap(),
+                        Program::new(&graph, pin).unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let samplers = [euclidean, minkowski, manhattan, chebyshev].map(outputs);
+
+        for i in 0..200 {
+            let point = [(i % 20) as f32 * 0.37 - 3.0, (i / 20) as f32 * 0.53 - 2.0];
+            let seed = i % 3;
+
+            // the outputs of each metric as F1, F2, F2 - F1, F1 · F2 and the value
+            let values = samplers.each_ref().map(|outputs| {
+                outputs
+                    .iter()
+                    .map(|(sampler, program)| {
+                        let value = sampler.sample_with_seed(point, seed);
+                        assert_eq!(value, program.sample(point, seed));
+                        value
+                    })
+                    .collect::<Vec<_>>()
+            });
+
+            for values in &values {
+                let [f1, f2] = [values[0] + 1.0, values[1] + 1.0];
+                assert!(f1 <= f2, "{f1} > {f2} at {point:?}");
+                assert!((values[2] - (f2 - f1 - 1.0)).abs() <= 1e-5);
+                assert!((values[3] - (f1 * f2 * 0.5 - 1.0)).abs() <= 1e-5);
+                assert!((-1.0..=1.0).contains(&values[4]));
+            }
+
+            let [euclidean, minkowski, manhattan, chebyshev] = values.map(|values| values[0]);
+            assert!(
+                (euclidean - minkowski).abs() <= 1e-5,
+                "{euclidean} != {minkowski}"
+            );
+            assert!(chebyshev <= euclidean && euclidean <= manhattan);
+        }
+    }
+
+    #[test]
+    fn cells_stay_finite() {
+        // far away points and exponents down to 0 or below
+        for point in [[f32::MAX, -f32::MAX], [1e30, 0.5], [f32::INFINITY, 0.0]] {
+            for exponent in [0.0, -1.0, 1e-9] {
+                let cells = ir::cells(point, 0, 1.0, CellMetric::Minkowski, exponent);
+                assert!(!cells.f1.is_nan() && !cells.f2.is_nan(), "at {point:?}");
+            }
+        }
+
+        let cells = ir::cells([0.3, 0.4], 0, 0.0, CellMetric::Minkowski, 0.0);
+        let expected = CellMetric::Minkowski.length([0.3, 0.4], CellMetric::MIN_EXPONENT);
+        assert_eq!(cells.f1, expected);
+    }
+}
@@ -0,0 +1,559 @@
+//! The math language of [`Node::Expression`](super::Node::Expression).
+//!
+//! An expression combines the variables `a`, `b`, `c`, `x`, `y` and `seed`, numbers,
+//! the constants `pi`, `tau` and `e`, the operators `+ - * / %`, `^` for powers and the [`FUNCTIONS`],
+//! like `sin(x * 3) * 0.5 + a`.
+//!
+//! Expressions are lowered to the same [`ir`](super::ir) ops as the other nodes,
+//! so they evaluate anywhere graphs do, including the web and shaders.
+
+use std::fmt;
+
+use super::ir::{BinaryOp, TernaryOp, UnaryOp};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    Number(f32),
+    Variable(Variable),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Ternary(TernaryOp, Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variable {
+    /// The inputs of the node.
+    A,
+    B,
+    C,
+    /// The coordinates of the point.
+    X,
+    Y,
+    Seed,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Function {
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    Ternary(TernaryOp),
+}
+
+/// The functions expressions can call, by name.
+pub const FUNCTIONS: &[(&str, Function)] = &[
+    ("abs", Function::Unary(UnaryOp::Abs)),
+    ("sqrt", Function::Unary(UnaryOp::Sqrt)),
+    ("floor", Function::Unary(UnaryOp::Floor)),
+    ("ceil", Function::Unary(UnaryOp::Ceil)),
+    ("round", Function::Unary(UnaryOp::Round)),
+    ("sin", Function::Unary(UnaryOp::Sin)),
+    ("cos", Function::Unary(UnaryOp::Cos)),
+    ("tan", Function::Unary(UnaryOp::Tan)),
+    ("exp", Function::Unary(UnaryOp::Exp)),
+    ("ln", Function::Unary(UnaryOp::Ln)),
+    ("log2", Function::Unary(UnaryOp::Log2)),
+    ("sign", Function::Unary(UnaryOp::Sign)),
+    ("fract", Function::Unary(UnaryOp::Fract)),
+    ("reciprocal", Function::Unary(UnaryOp::Reciprocal)),
+    ("one_minus", Function::Unary(UnaryOp::OneMinus)),
+    ("saturate", Function::Unary(UnaryOp::Saturate)),
+    ("pow", Function::Binary(BinaryOp::Pow)),
+    ("min", Function::Binary(BinaryOp::Min)),
+    ("max", Function::Binary(BinaryOp::Max)),
+    ("step", Function::Binary(BinaryOp::Step)),
+    ("atan2", Function::Binary(BinaryOp::Atan2)),
+    ("hypot", Function::Binary(BinaryOp::Hypot)),
+    ("clamp", Function::Ternary(TernaryOp::Clamp)),
+    ("lerp", Function::Ternary(TernaryOp::Lerp)),
+    ("inverse_lerp", Function::Ternary(TernaryOp::InverseLerp)),
+    ("smoothstep", Function::Ternary(TernaryOp::Smoothstep)),
+    ("smootherstep", Function::Ternary(TernaryOp::Smootherstep)),
+    ("smooth_min", Function::Ternary(TernaryOp::SmoothMin)),
+    ("smooth_max", Function::Ternary(TernaryOp::SmoothMax)),
+];
+
+const VARIABLES: &[Variable] = &[
+    Variable::A,
+    Variable::B,
+    Variable::C,
+    Variable::X,
+    Variable::Y,
+    Variable::Seed,
+];
+
+const CONSTANTS: &[(&str, f32)] = &[
+    ("pi", std::f32::consts::PI),
+    ("tau", std::f32::consts::TAU),
+    ("e", std::f32::consts::E),
+];
+
+/// How deeply parentheses, calls, signs and powers can be nested, so that parsing an
+/// expression and walking it recursively don't overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+/// How many operators like `+` and `*` an expression can have. They are parsed in a loop,
+/// but a chain of them still makes a tree as deep as it is long.
+const MAX_OPERATORS: usize = 1024;
+
+/// Why an expression can't be parsed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub message: String,
+    /// The character the error was found at, counting from 0.
+    pub position: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Variable {
+    pub fn name(self) -> &'static str {
+        match self {
+            Variable::A => "a",
+            Variable::B => "b",
+            Variable::C => "c",
+            Variable::X => "x",
+            Variable::Y => "y",
+            Variable::Seed => "seed",
+        }
+    }
+}
+
+impl Function {
+    pub fn arguments(self) -> usize {
+        match self {
+            Function::Unary(_) => 1,
+            Function::Binary(_) => 2,
+            Function::Ternary(_) => 3,
+        }
+    }
+}
+
+impl Expr {
+    /// Whether the variable appears in the expression.
+    pub fn uses(&self, variable: Variable) -> bool {
+        match self {
+            Expr::Number(_) => false,
+            Expr::Variable(v) => *v == variable,
+            Expr::Unary(_, x) => x.uses(variable),
+            Expr::Binary(_, lhs, rhs) => lhs.uses(variable) || rhs.uses(variable),
+            Expr::Ternary(_, a, b, c) => a.uses(variable) || b.uses(variable) || c.uses(variable),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        text,
+        offset: 0,
+        depth: 0,
+        operators: 0,
+    };
+
+    if parser.peek().is_none() {
+        return Err(parser.error(parser.offset, "the expression is empty"));
+    }
+
+    let expr = parser.sum()?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(')') => Err(parser.error(parser.offset, "unmatched `)`")),
+        Some(c) => Err(parser.error(parser.offset, format!("expected an operator, found `{c}`"))),
+    }
+}
+
+/// A recursive descent parser, with a method for every level of precedence.
+struct Parser<'a> {
+    text: &'a str,
+    /// The byte offset of the next character.
+    offset: usize,
+    /// How many levels the part being parsed is nested in the expression, up to [`MAX_DEPTH`].
+    depth: usize,
+    /// The operators parsed so far, up to [`MAX_OPERATORS`].
+    operators: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            position: self.text[..offset].chars().count(),
+        }
+    }
+
+    /// Skips whitespace and returns the next character.
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.text[self.offset..];
+        self.offset += rest.len() - rest.trim_start().len();
+        self.text[self.offset..].chars().next()
+    }
+
+    /// Consumes the next character if it is `c`.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.offset += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Goes a level deeper into the expression, the caller goes back up when it's done.
+    fn descend(&mut self) -> Result<(), ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(self.offset, "the expression is nested too deeply"));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Counts an operator that continues a chain like `a + b - c`.
+    fn chain(&mut self) -> Result<(), ParseError> {
+        if self.operators == MAX_OPERATORS {
+            return Err(self.error(self.offset, "the expression is too long"));
+        }
+
+        self.operators += 1;
+        Ok(())
+    }
+
+    /// Consumes characters while `f` returns true and returns them.
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let text = self.text;
+        let start = self.offset;
+        let len = text[start..].find(|c| !f(c)).unwrap_or(text.len() - start);
+        self.offset += len;
+        &text[start..self.offset]
+    }
+
+    /// `a + b` and `a - b`
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.product()?;
+
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+
+            self.chain()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+    }
+
+    /// `a * b`, `a / b` and `a % b`
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.negation()?;
+
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Mul
+            } else if self.eat('/') {
+                BinaryOp::Div
+            } else if self.eat('%') {
+                BinaryOp::Rem
+            } else {
+                return Ok(lhs);
+            };
+
+            self.chain()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.negation()?));
+        }
+    }
+
+    /// `-a` and `+a`
+    fn negation(&mut self) -> Result<Expr, ParseError> {
+        let negate = if self.eat('-') {
+            true
+        } else if self.eat('+') {
+            false
+        } else {
+            return self.power();
+        };
+
+        self.descend()?;
+        let value = self.negation()?;
+        self.depth -= 1;
+
+        Ok(if negate {
+            Expr::Unary(UnaryOp::Neg, Box::new(value))
+        } else {
+            value
+        })
+    }
+
+    /// `a ^ b`, which groups to the right and binds tighter than negation, so `-a ^ 2` is `-(a ^ 2)`.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.primary()?;
+
+        if self.eat('^') {
+            self.descend()?;
+            let exponent = self.negation()?;
+            self.depth -= 1;
+
+            Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// Numbers, names, calls and parentheses.
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let next = self.peek();
+        let start = self.offset;
+
+        match next {
+            Some('(') => {
+                self.offset += 1;
+                self.descend()?;
+                let expr = self.sum()?;
+                self.depth -= 1;
+
+                if !self.eat(')') {
+                    return Err(self.error(self.offset, "expected `)`"));
+                }
+
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                self.take_while(|c| c.is_ascii_digit() || c == '.');
+                self.exponent();
+                let number = &self.text[start..self.offset];
+
+                number
+                    .parse()
+                    .map(Expr::Number)
+                    .map_err(|_| self.error(start, format!("invalid number `{number}`")))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+
+                if self.peek() == Some('(') {
+                    return self.call(name, start);
+                }
+
+                if let Some(&variable) = VARIABLES.iter().find(|v| v.name() == name) {
+                    return Ok(Expr::Variable(variable));
+                }
+
+                if let Some(&(_, value)) = CONSTANTS.iter().find(|(n, _)| *n == name) {
+                    return Ok(Expr::Number(value));
+                }
+
+                Err(self.error(start, format!("unknown variable `{name}`")))
+            }
+            Some(c) => Err(self.error(start, format!("expected a value, found `{c}`"))),
+            None => Err(self.error(start, "expected a value")),
+        }
+    }
+
+    /// Consumes the exponent of a number like `1e-3`, if there is one,
+    /// and leaves an `e` that isn't followed by digits.
+    fn exponent(&mut self) {
+        let Some(exponent) = self.text[self.offset..].strip_prefix(['e', 'E']) else {
+            return;
+        };
+
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+
+        if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            self.offset = self.text.len() - digits.len();
+            self.take_while(|c| c.is_ascii_digit());
+        }
+    }
+
+    /// The arguments of a call to the function `name` that starts at `start`.
+    fn call(&mut self, name: &'a str, start: usize) -> Result<Expr, ParseError> {
+        let Some(&(_, function)) = FUNCTIONS.iter().find(|(n, _)| *n == name) else {
+            return Err(self.error(start, format!("unknown function `{name}`")));
+        };
+
+        // the opening parenthesis
+        self.offset += 1;
+        let mut args = Vec::new();
+        self.descend()?;
+
+        if !self.eat(')') {
+            loop {
+                args.push(self.sum()?);
+
+                if self.eat(')') {
+                    break;
+                }
+
+                if !self.eat(',') {
+                    return Err(self.error(self.offset, "expected `,` or `)`"));
+                }
+            }
+        }
+
+        self.depth -= 1;
+
+        if args.len() != function.arguments() {
+            return Err(self.error(
+                start,
+                format!(
+                    "`{name}` takes {} argument{}, not {}",
+                    function.arguments(),
+                    if function.arguments() == 1 { "" } else { "s" },
+                    args.len()
+                ),
+            ));
+        }
+
+        let mut args = args.into_iter().map(Box::new);
+        let mut arg = || args.next().unwrap();
+
+        Ok(match function {
+            Function::Unary(op) => Expr::Unary(op, arg()),
+            Function::Binary(op) => Expr::Binary(op, arg(), arg()),
+            Function::Ternary(op) => Expr::Ternary(op, arg(), arg(), arg()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Expr, Variable};
+    use crate::graph::ir::{BinaryOp, TernaryOp, UnaryOp};
+
+    fn var(variable: Variable) -> Box<Expr> {
+        Box::new(Expr::Variable(variable))
+    }
+
+    fn num(value: f32) -> Box<Expr> {
+        Box::new(Expr::Number(value))
+    }
+
+    #[test]
+    fn precedence() {
+        // `^` binds tighter than `*`, which binds tighter than `+`
+        assert_eq!(
+            parse("a + b * c ^ 2").unwrap(),
+            Expr::Binary(
+                BinaryOp::Add,
+                var(Variable::A),
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    var(Variable::B),
+                    Box::new(Expr::Binary(BinaryOp::Pow, var(Variable::C), num(2.0))),
+                )),
+            )
+        );
+
+        assert_eq!(
+            parse("-x ^ 2").unwrap(),
+            Expr::Unary(
+                UnaryOp::Neg,
+                Box::new(Expr::Binary(BinaryOp::Pow, var(Variable::X), num(2.0))),
+            )
+        );
+
+        assert_eq!(
+            parse("2 ^ 3 ^ y").unwrap(),
+            Expr::Binary(
+                BinaryOp::Pow,
+                num(2.0),
+                Box::new(Expr::Binary(BinaryOp::Pow, num(3.0), var(Variable::Y))),
+            )
+        );
+
+        assert_eq!(
+            parse("a - b - c").unwrap(),
+            Expr::Binary(
+                BinaryOp::Sub,
+                Box::new(Expr::Binary(
+                    BinaryOp::Sub,
+                    var(Variable::A),
+                    var(Variable::B)
+                )),
+                var(Variable::C),
+            )
+        );
+    }
+
+    #[test]
+    fn calls() {
+        assert_eq!(
+            parse("clamp(sin(seed), -1, pi)").unwrap(),
+            Expr::Ternary(
+                TernaryOp::Clamp,
+                Box::new(Expr::Unary(UnaryOp::Sin, var(Variable::Seed))),
+                Box::new(Expr::Unary(UnaryOp::Neg, num(1.0))),
+                num(std::f32::consts::PI),
+            )
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let error = |text| {
+            let error = parse(text).unwrap_err();
+            (error.message, error.position)
+        };
+
+        assert_eq!(error(""), ("the expression is empty".into(), 0));
+        assert_eq!(error("a + d"), ("unknown variable `d`".into(), 4));
+        assert_eq!(error("a + foo(x)"), ("unknown function `foo`".into(), 4));
+        assert_eq!(
+            error("min(a)"),
+            ("`min` takes 2 arguments, not 1".into(), 0)
+        );
+        assert_eq!(error("(a + b"), ("expected `)`".into(), 6));
+        assert_eq!(error("a b"), ("expected an operator, found `b`".into(), 2));
+        assert_eq!(error("a * "), ("expected a value".into(), 4));
+        assert_eq!(error("1.2.3"), ("invalid number `1.2.3`".into(), 0));
+        assert_eq!(error("2e"), ("expected an operator, found `e`".into(), 1));
+    }
+
+    #[test]
+    fn numbers_can_have_exponents() {
+        assert_eq!(parse("1e3").unwrap(), *num(1000.0));
+        assert_eq!(parse("2.5E-2").unwrap(), *num(0.025));
+        assert_eq!(
+            parse("2e+x").unwrap_err().message,
+            "expected an operator, found `e`"
+        );
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let nested = |depth: usize, open: &str, close: &str| {
+            format!("{}a{}", open.repeat(depth), close.repeat(depth))
+        };
+
+        for (open, close) in [("(", ")"), ("sin(", ")"), ("-", ""), ("a ^ ", "")] {
+            assert!(parse(&nested(100, open, close)).is_ok(), "{open}");
+
+            let error = parse(&nested(100_000, open, close)).unwrap_err();
+            assert_eq!(
+                error.message, "the expression is nested too deeply",
+                "{open}"
+            );
+        }
+    }
+
+    #[test]
+    fn long_chains_are_not_nesting() {
+        for operator in [" + ", " * "] {
+            assert!(parse(&vec!["a"; 1000].join(operator)).is_ok(), "{operator}");
+
+            let error = parse(&vec!["a"; 100_000].join(operator)).unwrap_err();
+            assert_eq!(error.message, "the expression is too long", "{operator}");
+        }
+    }
+}
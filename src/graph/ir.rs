@@ -0,0 +1,1201 @@
+//! A flat, language independent representation of a graph's noise
+//! that the [`Sampler`](super::Sampler) and the shader backends are compiled from.
+//!
+//! Every [`Op`] is a function of a point and a seed, like a [`Sample<2>`](noise_functions::Sample).
+//! [`Program::sample`] evaluates the ops recursively the same way the generated shaders do.
+
+mod cellular;
+mod pattern;
+
+use std::collections::HashMap;
+
+use noise_functions::Sample;
+
+use super::{
+    curve::Segment,
+    expression::{self, Expr, Variable},
+    CellMetric, Error, Graph, InPinId, Node, NodeId, OutPinId, Scope,
+};
+
+pub use cellular::{cells, Cells, CellularOutput};
+
+/// √3, which the hexagons of [`Node::HexGrid`] are measured with.
+pub const SQRT_3: f32 = 1.732_050_8;
+
+/// Ops in dependency order, an op only refers to ops before it.
+pub struct Program {
+    pub ops: Vec<Op>,
+    pub output: usize,
+    /// The ops that the outputs of the graph's nodes were lowered to, in order.
+    /// The other ops compute parts of a node.
+    pub node_outputs: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Op {
+    Constant(f32),
+    /// The x (0) or y (1) coordinate of the point.
+    Position(usize),
+    /// The seed as a number.
+    SeedValue,
+    Noise(Primitive),
+    Cell {
+        kind: CellKind,
+        jitter: usize,
+    },
+    Cellular {
+        metric: CellMetric,
+        output: CellularOutput,
+        jitter: usize,
+        exponent: usize,
+    },
+    Fractal {
+        input: usize,
+        octaves: u32,
+        gain: f32,
+        lacunarity: f32,
+        weighted_strength: f32,
+    },
+    Frequency {
+        input: usize,
+        frequency: usize,
+    },
+    TriangleWave {
+        input: usize,
+        frequency: usize,
+    },
+    Translate {
+        input: usize,
+        x: usize,
+        y: usize,
+    },
+    Unary(UnaryOp, usize),
+    Binary(BinaryOp, usize, usize),
+    Ternary(TernaryOp, usize, usize, usize),
+    /// A segment of a curve at the input, or the value of `before` left of the segment.
+    ///
+    /// A curve is a chain of these.
+    Spline {
+        input: usize,
+        before: usize,
+        segment: Segment,
+    },
+    Seed {
+        input: usize,
+        seed: i32,
+    },
+    AddSeed {
+        input: usize,
+        add: i32,
+    },
+    MulSeed {
+        input: usize,
+        mul: i32,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Primitive {
+    Value,
+    ValueCubic,
+    Perlin,
+    Simplex,
+    OpenSimplex2,
+    OpenSimplex2s,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CellKind {
+    Value,
+    Distance,
+    DistanceSq,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UnaryOp {
+    Abs,
+    Neg,
+    Sqrt,
+    Floor,
+    Ceil,
+    Round,
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    /// The natural logarithm.
+    Ln,
+    Log2,
+    /// -1, 0 or 1, like the sign of the operand.
+    Sign,
+    /// The part after the floor, in 0..1 also for negative numbers.
+    Fract,
+    Reciprocal,
+    OneMinus,
+    /// Limited to 0..1.
+    Saturate,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Min,
+    Max,
+    /// 0 if the second operand is less than the first, the edge, and 1 otherwise.
+    Step,
+    /// The angle of the point (second operand, first operand), like `f32::atan2`.
+    Atan2,
+    /// The length of the vector of the operands.
+    Hypot,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TernaryOp {
+    /// The first operand limited to the range of the other two.
+    Clamp,
+    /// From the first to the second operand by the third.
+    Lerp,
+    /// Where the third operand is between the first and the second, the inverse of `Lerp`.
+    InverseLerp,
+    /// Like `InverseLerp` limited to 0..1, eased with `3t² - 2t³`.
+    Smoothstep,
+    /// Like `Smoothstep`, eased with `6t⁵ - 15t⁴ + 10t³`.
+    Smootherstep,
+    /// The minimum of the first two operands, rounded off where they are closer than the third.
+    SmoothMin,
+    /// The maximum of the first two operands, rounded off where they are closer than the third.
+    SmoothMax,
+}
+
+impl Op {
+    /// Calls `f` with the index of every op this op uses.
+    pub fn for_each_input(&mut self, mut f: impl FnMut(&mut usize)) {
+        match self {
+            Op::Constant(_) | Op::Position(_) | Op::SeedValue | Op::Noise(_) => {}
+            Op::Cell { jitter: input, .. }
+            | Op::Fractal { input, .. }
+            | Op::Seed { input, .. }
+            | Op::AddSeed { input, .. }
+            | Op::MulSeed { input, .. }
+            | Op::Unary(_, input) => f(input),
+            Op::Frequency { input, frequency } | Op::TriangleWave { input, frequency } => {
+                f(input);
+                f(frequency);
+            }
+            Op::Cellular {
+                jitter, exponent, ..
+            } => {
+                f(jitter);
+                f(exponent);
+            }
+            Op::Translate { input, x, y } => {
+                f(input);
+                f(x);
+                f(y);
+            }
+            Op::Binary(_, lhs, rhs) => {
+                f(lhs);
+                f(rhs);
+            }
+            Op::Ternary(_, a, b, c) => {
+                f(a);
+                f(b);
+                f(c);
+            }
+            Op::Spline { input, before, .. } => {
+                f(input);
+                f(before);
+            }
+        }
+    }
+
+    /// The kind of the op and its fields, with floats as their bits,
+    /// which is the same for ops that compute the same noise and can be hashed.
+    fn key(&self) -> [u64; 9] {
+        fn key(kind: u64, fields: &[u64]) -> [u64; 9] {
+            let mut key = [0; 9];
+            key[0] = kind;
+            key[1..=fields.len()].copy_from_slice(fields);
+            key
+        }
+
+        let float = |value: f32| u64::from(value.to_bits());
+        let int = |value: i32| u64::from(value as u32);
+
+        match *self {
+            Op::Constant(value) => key(0, &[float(value)]),
+            Op::Position(axis) => key(1, &[axis as u64]),
+            Op::SeedValue => key(2, &[]),
+            Op::Noise(primitive) => key(3, &[primitive as u64]),
+            Op::Cell { kind, jitter } => key(4, &[kind as u64, jitter as u64]),
+            Op::Cellular {
+                metric,
+                output,
+                jitter,
+                exponent,
+            } => key(
+                5,
+                &[metric as u64, output as u64, jitter as u64, exponent as u64],
+            ),
+            Op::Fractal {
+                input,
+                octaves,
+                gain,
+                lacunarity,
+                weighted_strength,
+            } => key(
+                6,
+                &[
+                    input as u64,
+                    u64::from(octaves),
+                    float(gain),
+                    float(lacunarity),
+                    float(weighted_strength),
+                ],
+            ),
+            Op::Frequency { input, frequency } => key(7, &[input as u64, frequency as u64]),
+            Op::TriangleWave { input, frequency } => key(8, &[input as u64, frequency as u64]),
+            Op::Translate { input, x, y } => key(9, &[input as u64, x as u64, y as u64]),
+            Op::Unary(op, input) => key(10, &[op as u64, input as u64]),
+            Op::Binary(op, lhs, rhs) => key(11, &[op as u64, lhs as u64, rhs as u64]),
+            Op::Ternary(op, a, b, c) => key(12, &[op as u64, a as u64, b as u64, c as u64]),
+            Op::Spline {
+                input,
+                before,
+                segment,
+            } => {
+                let Segment { from, to, slopes } = segment;
+                let [from, to, slopes] = [from, to, slopes].map(|pair| pair.map(float));
+
+                key(
+                    13,
+                    &[
+                        input as u64,
+                        before as u64,
+                        from[0],
+                        from[1],
+                        to[0],
+                        to[1],
+                        slopes[0],
+                        slopes[1],
+                    ],
+                )
+            }
+            Op::Seed { input, seed } => key(14, &[input as u64, int(seed)]),
+            Op::AddSeed { input, add } => key(15, &[input as u64, int(add)]),
+            Op::MulSeed { input, mul } => key(16, &[input as u64, int(mul)]),
+        }
+    }
+}
+
+impl Primitive {
+    pub fn sample(self, point: [f32; 2], seed: i32) -> f32 {
+        match self {
+            Primitive::Value => noise_functions::Value.sample_with_seed(point, seed),
+            Primitive::ValueCubic => noise_functions::ValueCubic.sample_with_seed(point, seed),
+            Primitive::Perlin => noise_functions::Perlin.sample_with_seed(point, seed),
+            Primitive::Simplex => noise_functions::Simplex.sample_with_seed(point, seed),
+            Primitive::OpenSimplex2 => noise_functions::OpenSimplex2.sample_with_seed(point, seed),
+            Primitive::OpenSimplex2s => {
+                noise_functions::OpenSimplex2s.sample_with_seed(point, seed)
+            }
+        }
+    }
+
+    /// Like [`Primitive::sample`] at every point, matching the primitive once
+    /// so that the calls in the loop can be inlined.
+    pub fn sample_batch(self, xs: &[f32], ys: &[f32], seed: i32, out: &mut [f32]) {
+        fn sample(noise: impl Sample<2>, xs: &[f32], ys: &[f32], seed: i32, out: &mut [f32]) {
+            for ((value, &x), &y) in out.iter_mut().zip(xs).zip(ys) {
+                *value = noise.sample_with_seed([x, y], seed);
+            }
+        }
+
+        match self {
+            Primitive::Value => sample(noise_functions::Value, xs, ys, seed, out),
+            Primitive::ValueCubic => sample(noise_functions::ValueCubic, xs, ys, seed, out),
+            Primitive::Perlin => sample(noise_functions::Perlin, xs, ys, seed, out),
+            Primitive::Simplex => sample(noise_functions::Simplex, xs, ys, seed, out),
+            Primitive::OpenSimplex2 => sample(noise_functions::OpenSimplex2, xs, ys, seed, out),
+            Primitive::OpenSimplex2s => sample(noise_functions::OpenSimplex2s, xs, ys, seed, out),
+        }
+    }
+}
+
+impl CellKind {
+    pub fn sample(self, jitter: f32, point: [f32; 2], seed: i32) -> f32 {
+        match self {
+            CellKind::Value => noise_functions::CellValue { jitter }.sample_with_seed(point, seed),
+            CellKind::Distance => {
+                noise_functions::CellDistance { jitter }.sample_with_seed(point, seed)
+            }
+            CellKind::DistanceSq => {
+                noise_functions::CellDistanceSq { jitter }.sample_with_seed(point, seed)
+            }
+        }
+    }
+}
+
+impl UnaryOp {
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
+            UnaryOp::Abs => x.abs(),
+            UnaryOp::Neg => -x,
+            UnaryOp::Sqrt => x.sqrt(),
+            UnaryOp::Floor => x.floor(),
+            UnaryOp::Ceil => x.ceil(),
+            UnaryOp::Round => x.round(),
+            UnaryOp::Sin => x.sin(),
+            UnaryOp::Cos => x.cos(),
+            UnaryOp::Tan => x.tan(),
+            UnaryOp::Exp => x.exp(),
+            UnaryOp::Ln => x.ln(),
+            UnaryOp::Log2 => x.log2(),
+            UnaryOp::Sign => {
+                if x > 0.0 {
+                    1.0
+                } else if x < 0.0 {
+                    -1.0
+                } else {
+                    x
+                }
+            }
+            UnaryOp::Fract => x - x.floor(),
+            UnaryOp::Reciprocal => 1.0 / x,
+            UnaryOp::OneMinus => 1.0 - x,
+            UnaryOp::Saturate => x.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl BinaryOp {
+    pub fn apply(self, a: f32, b: f32) -> f32 {
+        match self {
+            BinaryOp::Add => a + b,
+            BinaryOp::Sub => a - b,
+            BinaryOp::Mul => a * b,
+            BinaryOp::Div => a / b,
+            BinaryOp::Rem => a % b,
+            BinaryOp::Pow => a.powf(b),
+            BinaryOp::Min => a.min(b),
+            BinaryOp::Max => a.max(b),
+            BinaryOp::Step => {
+                if b < a {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            BinaryOp::Atan2 => a.atan2(b),
+            BinaryOp::Hypot => a.hypot(b),
+        }
+    }
+}
+
+impl TernaryOp {
+    pub fn apply(self, a: f32, b: f32, c: f32) -> f32 {
+        match self {
+            TernaryOp::Clamp => a.max(b).min(c),
+            TernaryOp::Lerp => lerp(a, b, c),
+            TernaryOp::InverseLerp => (c - a) / (b - a),
+            TernaryOp::Smoothstep => {
+                let t = ((c - a) / (b - a)).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            TernaryOp::Smootherstep => {
+                let t = ((c - a) / (b - a)).clamp(0.0, 1.0);
+                t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+            }
+            TernaryOp::SmoothMin => a.min(b) - smooth_min_offset(a, b, c),
+            TernaryOp::SmoothMax => a.max(b) + smooth_min_offset(a, b, c),
+        }
+    }
+}
+
+impl Program {
+    /// Returns the program computing the output pin, or why the pin can't be evaluated.
+    pub fn new(graph: &Graph, pin: OutPinId) -> Result<Self, Error> {
+        if graph.node(pin.node).is_none() {
+            return Err(Error::NodeNotFound(pin.node));
+        }
+
+        graph.check()?;
+
+        let mut lowering = Lowering::default();
+
+        let Some(output) = lower(&mut lowering, graph, pin, None) else {
+            return Err(lowering.error.unwrap_or(Error::InvalidOutput(pin)));
+        };
+
+        let mut node_outputs: Vec<usize> = lowering.pins.into_values().collect();
+        node_outputs.sort_unstable();
+        node_outputs.dedup();
+
+        Ok(Self {
+            ops: lowering.ops,
+            output,
+            node_outputs,
+        })
+    }
+
+    pub fn sample(&self, point: [f32; 2], seed: i32) -> f32 {
+        self.sample_op(self.output, point, seed)
+    }
+
+    /// A hash of every op together with its inputs, which is the same for ops that compute the same noise
+    /// in any program.
+    pub fn hashes(&self) -> Vec<u64> {
+        use std::hash::{Hash, Hasher};
+
+        let mut hashes: Vec<u64> = Vec::with_capacity(self.ops.len());
+
+        for op in &self.ops {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            let mut op = *op;
+
+            op.for_each_input(|input| {
+                hashes[*input].hash(&mut hasher);
+                // the indices differ between programs
+                *input = 0;
+            });
+
+            op.key().hash(&mut hasher);
+            hashes.push(hasher.finish());
+        }
+
+        hashes
+    }
+
+    fn sample_op(&self, op: usize, point: [f32; 2], seed: i32) -> f32 {
+        let sample = |op: usize| self.sample_op(op, point, seed);
+
+        match self.ops[op] {
+            Op::Constant(value) => value,
+            Op::Position(axis) => point[axis],
+            Op::SeedValue => seed as f32,
+            Op::Noise(primitive) => primitive.sample(point, seed),
+            Op::Cell { kind, jitter } => kind.sample(sample(jitter), point, seed),
+            Op::Cellular {
+                metric,
+                output,
+                jitter,
+                exponent,
+            } => output.of(&cells(
+                point,
+                seed,
+                sample(jitter),
+                metric,
+                sample(exponent),
+            )),
+            Op::Fractal {
+                input,
+                octaves,
+                gain,
+                lacunarity,
+                weighted_strength,
+            } => {
+                let mut sum = 0.0;
+                let mut amp = fractal_bounding(octaves, gain);
+                let mut point = point;
+                let mut seed = seed;
+
+                for _ in 0..octaves {
+                    let noise = self.sample_op(input, point, seed);
+                    seed = seed.wrapping_add(1);
+                    sum += noise * amp;
+                    amp *= lerp(1.0, (noise + 1.0).min(2.0) * 0.5, weighted_strength);
+                    point = point.map(|x| x * lacunarity);
+                    amp *= gain;
+                }
+
+                sum
+            }
+            Op::Frequency { input, frequency } => {
+                let frequency = sample(frequency);
+                self.sample_op(input, point.map(|x| x * frequency), seed)
+            }
+            Op::TriangleWave { input, frequency } => {
+                let value = sample(input) * sample(frequency);
+                let fract = value - (value + 0.5).floor();
+                fract.abs() * 4.0 - 1.0
+            }
+            Op::Translate { input, x, y } => {
+                let point = [point[0] + sample(x), point[1] + sample(y)];
+                self.sample_op(input, point, seed)
+            }
+            Op::Unary(op, input) => op.apply(sample(input)),
+            Op::Binary(op, lhs, rhs) => op.apply(sample(lhs), sample(rhs)),
+            Op::Ternary(op, a, b, c) => op.apply(sample(a), sample(b), sample(c)),
+            Op::Spline {
+                input,
+                before,
+                segment,
+            } => segment.sample(sample(input), sample(before)),
+            Op::Seed { input, seed } => self.sample_op(input, point, seed),
+            Op::AddSeed { input, add } => self.sample_op(input, point, seed.wrapping_add(add)),
+            Op::MulSeed { input, mul } => self.sample_op(input, point, seed.wrapping_mul(mul)),
+        }
+    }
+}
+
+/// The initial amplitude that keeps the sum of all octaves in -1..1.
+pub fn fractal_bounding(octaves: u32, gain: f32) -> f32 {
+    let gain = gain.abs();
+    let mut amp = gain;
+    let mut amp_fractal = 1.0;
+
+    for _ in 1..octaves {
+        amp_fractal += amp;
+        amp *= gain;
+    }
+
+    1.0 / amp_fractal
+}
+
+/// The heights of [`Node::Terrace`] in ascending order without duplicates.
+pub fn terrace_heights(steps: u32, heights: &[f32]) -> Vec<f32> {
+    let mut heights: Vec<f32> = if heights.is_empty() {
+        let steps = steps.max(1);
+        (0..=steps)
+            .map(|i| -1.0 + 2.0 * i as f32 / steps as f32)
+            .collect()
+    } else {
+        heights.iter().copied().filter(|h| h.is_finite()).collect()
+    };
+
+    heights.sort_by(f32::total_cmp);
+    heights.dedup();
+    heights
+}
+
+/// How much the polynomial smooth minimum is less than the minimum.
+fn smooth_min_offset(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0.0 {
+        return 0.0;
+    }
+
+    let h = (k - (a - b).abs()).max(0.0) / k;
+    h * h * k * 0.25
+}
+
+pub(super) fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// The ops of a program while its graph is lowered.
+#[derive(Default)]
+struct Lowering {
+    ops: Vec<Op>,
+    /// The index of every op by its [`Op::key`].
+    indices: HashMap<[u64; 9], usize>,
+    /// The op of every output pin lowered so far, by the group nodes around it and the pin.
+    pins: HashMap<(Vec<NodeId>, OutPinId), usize>,
+    /// Why lowering stopped, if it wasn't an output that can't be evaluated.
+    error: Option<Error>,
+}
+
+/// Appends the op unless an equal one exists already, so shared inputs are only computed once.
+fn push(ops: &mut Lowering, op: Op) -> usize {
+    *ops.indices.entry(op.key()).or_insert_with(|| {
+        ops.ops.push(op);
+        ops.ops.len() - 1
+    })
+}
+
+fn in_pin_or(
+    ops: &mut Lowering,
+    graph: &Graph,
+    pin: InPinId,
+    default: f32,
+    scope: Option<&Scope>,
+) -> Option<usize> {
+    if let Some(pin) = graph.input(pin) {
+        lower(ops, graph, pin, scope)
+    } else {
+        Some(push(ops, Op::Constant(default)))
+    }
+}
+
+/// Lowers the connected inputs of a variadic node like [`Node::Sum`], with their input index.
+fn connected_inputs(
+    ops: &mut Lowering,
+    graph: &Graph,
+    node: NodeId,
+    count: usize,
+    scope: Option<&Scope>,
+) -> Option<Vec<(usize, usize)>> {
+    let mut inputs = Vec::new();
+
+    for input in 0..count {
+        if let Some(remote) = graph.input(InPinId { node, input }) {
+            inputs.push((input, lower(ops, graph, remote, scope)?));
+        }
+    }
+
+    Some(inputs)
+}
+
+/// Combines the operands with the op from left to right, or is `empty` if there are none.
+fn fold(ops: &mut Lowering, operands: &[usize], op: BinaryOp, empty: f32) -> usize {
+    match operands.split_first() {
+        Some((&first, rest)) => rest
+            .iter()
+            .fold(first, |lhs, &rhs| push(ops, Op::Binary(op, lhs, rhs))),
+        None => push(ops, Op::Constant(empty)),
+    }
+}
+
+/// Lowers the output pin once, later uses of the pin get the op of the first time.
+fn lower(ops: &mut Lowering, graph: &Graph, pin: OutPinId, scope: Option<&Scope>) -> Option<usize> {
+    let key = (Scope::path(scope), pin);
+
+    if let Some(&op) = ops.pins.get(&key) {
+        return Some(op);
+    }
+
+    let op = lower_node(ops, graph, pin, scope)?;
+    ops.pins.insert(key, op);
+    Some(op)
+}
+
+fn lower_node(
+    ops: &mut Lowering,
+    graph: &Graph,
+    pin: OutPinId,
+    scope: Option<&Scope>,
+) -> Option<usize> {
+    let mut input_or = |i: usize, default: f32| {
+        let pin = InPinId {
+            node: pin.node,
+            input: i,
+        };
+
+        in_pin_or(ops, graph, pin, default, scope)
+    };
+
+    let op = match graph[pin.node] {
+        Node::Value => Op::Noise(Primitive::Value),
+        Node::ValueCubic => Op::Noise(Primitive::ValueCubic),
+        Node::Perlin => Op::Noise(Primitive::Perlin),
+        Node::Simplex => Op::Noise(Primitive::Simplex),
+        Node::OpenSimplex2 => Op::Noise(Primitive::OpenSimplex2),
+        Node::OpenSimplex2s => Op::Noise(Primitive::OpenSimplex2s),
+        Node::CellValue { jitter } => Op::Cell {
+            kind: CellKind::Value,
+            jitter: input_or(0, jitter)?,
+        },
+        Node::CellDistance { jitter } => Op::Cell {
+            kind: CellKind::Distance,
+            jitter: input_or(0, jitter)?,
+        },
+        Node::CellDistanceSq { jitter } => Op::Cell {
+            kind: CellKind::DistanceSq,
+            jitter: input_or(0, jitter)?,
+        },
+        Node::Cellular {
+            metric,
+            jitter,
+            exponent,
+        } => Op::Cellular {
+            metric,
+            output: *CellularOutput::ALL.get(pin.output)?,
+            jitter: input_or(0, jitter)?,
+            exponent: input_or(1, exponent)?,
+        },
+        Node::Checkerboard { size } => {
+            let size = input_or(0, size)?;
+            return Some(pattern::checkerboard(ops, size));
+        }
+        Node::Stripes { width, angle } => {
+            let width = input_or(0, width)?;
+            let angle = input_or(1, angle)?;
+            return Some(pattern::stripes(ops, width, angle));
+        }
+        Node::Rings { width } => {
+            let width = input_or(0, width)?;
+            return Some(pattern::rings(ops, width));
+        }
+        Node::RadialGradient { x, y, radius } => {
+            let center = [input_or(0, x)?, input_or(1, y)?];
+            let radius = input_or(2, radius)?;
+            return Some(pattern::radial_gradient(ops, center, radius));
+        }
+        Node::LinearGradient { angle, length } => {
+            let angle = input_or(0, angle)?;
+            let length = input_or(1, length)?;
+            return Some(pattern::linear_gradient(ops, angle, length));
+        }
+        Node::CircleSdf { x, y, radius } => {
+            let center = [input_or(0, x)?, input_or(1, y)?];
+            let radius = input_or(2, radius)?;
+            return Some(pattern::circle_sdf(ops, center, radius));
+        }
+        Node::BoxSdf {
+            x,
+            y,
+            width,
+            height,
+        } => {
+            let center = [input_or(0, x)?, input_or(1, y)?];
+            let size = [input_or(2, width)?, input_or(3, height)?];
+            return Some(pattern::box_sdf(ops, center, size));
+        }
+        Node::GridLines { spacing, thickness } => {
+            let spacing = input_or(0, spacing)?;
+            let thickness = input_or(1, thickness)?;
+            return Some(pattern::grid_lines(ops, spacing, thickness));
+        }
+        Node::HexGrid { size, thickness } => {
+            let size = input_or(0, size)?;
+            let thickness = input_or(1, thickness)?;
+            return Some(pattern::hex_grid(ops, size, thickness));
+        }
+        Node::Falloff {
+            shape,
+            x,
+            y,
+            radius,
+            exponent,
+            invert,
+        } => {
+            let center = [input_or(0, x)?, input_or(1, y)?];
+            let radius = input_or(2, radius)?;
+            let exponent = input_or(3, exponent)?;
+            return Some(pattern::falloff(
+                ops, shape, center, radius, exponent, invert,
+            ));
+        }
+        Node::Fractal {
+            octaves,
+            gain,
+            lacunarity,
+            weighted_strength,
+        } => Op::Fractal {
+            input: input_or(0, 0.0)?,
+            octaves,
+            gain,
+            lacunarity,
+            weighted_strength,
+        },
+        Node::Frequency { frequency } => Op::Frequency {
+            input: input_or(0, 0.0)?,
+            frequency: input_or(1, frequency)?,
+        },
+        Node::TriangleWave { frequency } => Op::TriangleWave {
+            input: input_or(0, 0.0)?,
+            frequency: input_or(1, frequency)?,
+        },
+        Node::TranslateXy { x, y } => Op::Translate {
+            input: input_or(0, 0.0)?,
+            x: input_or(1, x)?,
+            y: input_or(2, y)?,
+        },
+        Node::Abs => Op::Unary(UnaryOp::Abs, input_or(0, 0.0)?),
+        Node::Neg => Op::Unary(UnaryOp::Neg, input_or(0, 0.0)?),
+        Node::Sqrt => Op::Unary(UnaryOp::Sqrt, input_or(0, 0.0)?),
+        Node::Floor => Op::Unary(UnaryOp::Floor, input_or(0, 0.0)?),
+        Node::Ceil => Op::Unary(UnaryOp::Ceil, input_or(0, 0.0)?),
+        Node::Round => Op::Unary(UnaryOp::Round, input_or(0, 0.0)?),
+        Node::Sin => Op::Unary(UnaryOp::Sin, input_or(0, 0.0)?),
+        Node::Cos => Op::Unary(UnaryOp::Cos, input_or(0, 0.0)?),
+        Node::Tan => Op::Unary(UnaryOp::Tan, input_or(0, 0.0)?),
+        Node::Exp => Op::Unary(UnaryOp::Exp, input_or(0, 0.0)?),
+        Node::Ln => Op::Unary(UnaryOp::Ln, input_or(0, 0.0)?),
+        Node::Log2 => Op::Unary(UnaryOp::Log2, input_or(0, 0.0)?),
+        Node::Sign => Op::Unary(UnaryOp::Sign, input_or(0, 0.0)?),
+        Node::Fract => Op::Unary(UnaryOp::Fract, input_or(0, 0.0)?),
+        Node::Reciprocal => Op::Unary(UnaryOp::Reciprocal, input_or(0, 0.0)?),
+        Node::OneMinus => Op::Unary(UnaryOp::OneMinus, input_or(0, 0.0)?),
+        Node::Saturate => Op::Unary(UnaryOp::Saturate, input_or(0, 0.0)?),
+        Node::Add { lhs, rhs } => Op::Binary(BinaryOp::Add, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Sub { lhs, rhs } => Op::Binary(BinaryOp::Sub, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Mul { lhs, rhs } => Op::Binary(BinaryOp::Mul, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Div { lhs, rhs } => Op::Binary(BinaryOp::Div, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Rem { lhs, rhs } => Op::Binary(BinaryOp::Rem, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Pow { lhs, rhs } => Op::Binary(BinaryOp::Pow, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Min { lhs, rhs } => Op::Binary(BinaryOp::Min, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Max { lhs, rhs } => Op::Binary(BinaryOp::Max, input_or(0, lhs)?, input_or(1, rhs)?),
+        Node::Step { edge, value } => {
+            Op::Binary(BinaryOp::Step, input_or(0, edge)?, input_or(1, value)?)
+        }
+        Node::Atan2 { y, x } => Op::Binary(BinaryOp::Atan2, input_or(0, y)?, input_or(1, x)?),
+        Node::Hypot { x, y } => Op::Binary(BinaryOp::Hypot, input_or(0, x)?, input_or(1, y)?),
+        Node::SmoothMin { lhs, rhs, k } => Op::Ternary(
+            TernaryOp::SmoothMin,
+            input_or(0, lhs)?,
+            input_or(1, rhs)?,
+            input_or(2, k)?,
+        ),
+        Node::SmoothMax { lhs, rhs, k } => Op::Ternary(
+            TernaryOp::SmoothMax,
+            input_or(0, lhs)?,
+            input_or(1, rhs)?,
+            input_or(2, k)?,
+        ),
+        Node::Lerp { a, b, t } => Op::Ternary(
+            TernaryOp::Lerp,
+            input_or(0, a)?,
+            input_or(1, b)?,
+            input_or(2, t)?,
+        ),
+        Node::InverseLerp { a, b, value } => Op::Ternary(
+            TernaryOp::InverseLerp,
+            input_or(0, a)?,
+            input_or(1, b)?,
+            input_or(2, value)?,
+        ),
+        Node::Smoothstep {
+            edge0,
+            edge1,
+            value,
+        } => Op::Ternary(
+            TernaryOp::Smoothstep,
+            input_or(0, edge0)?,
+            input_or(1, edge1)?,
+            input_or(2, value)?,
+        ),
+        Node::Smootherstep {
+            edge0,
+            edge1,
+            value,
+        } => Op::Ternary(
+            TernaryOp::Smootherstep,
+            input_or(0, edge0)?,
+            input_or(1, edge1)?,
+            input_or(2, value)?,
+        ),
+        Node::Clamp { value, min, max } => Op::Ternary(
+            TernaryOp::Clamp,
+            input_or(0, value)?,
+            input_or(1, min)?,
+            input_or(2, max)?,
+        ),
+        Node::Remap {
+            value,
+            from_min,
+            from_max,
+            to_min,
+            to_max,
+            clamp,
+        } => {
+            let value = input_or(0, value)?;
+            let from = [input_or(1, from_min)?, input_or(2, from_max)?];
+            let to = [input_or(3, to_min)?, input_or(4, to_max)?];
+            return Some(remap(ops, value, from, to, clamp));
+        }
+        Node::Normalize { min, max } => {
+            let value = input_or(0, 0.0)?;
+            let [min, max, to_min, to_max] =
+                [min, max, -1.0, 1.0].map(|value| push(ops, Op::Constant(value)));
+            return Some(remap(ops, value, [min, max], [to_min, to_max], false));
+        }
+        Node::Expression {
+            ref expression,
+            a,
+            b,
+            c,
+        } => {
+            let expression = match expression::parse(expression) {
+                Ok(expression) => expression,
+                Err(error) => {
+                    ops.error = Some(Error::Expression {
+                        node: pin.node,
+                        error,
+                    });
+                    return None;
+                }
+            };
+
+            return lower_expression(ops, &expression, &mut |ops, variable| {
+                let (input, default) = match variable {
+                    Variable::A => (0, a),
+                    Variable::B => (1, b),
+                    Variable::C => (2, c),
+                    Variable::X => return Some(push(ops, Op::Position(0))),
+                    Variable::Y => return Some(push(ops, Op::Position(1))),
+                    Variable::Seed => return Some(push(ops, Op::SeedValue)),
+                };
+
+                let pin = InPinId {
+                    node: pin.node,
+                    input,
+                };
+
+                in_pin_or(ops, graph, pin, default, scope)
+            });
+        }
+        Node::Curve(ref curve) => {
+            let input = input_or(0, 0.0)?;
+            let mut value = push(ops, Op::Constant(curve.start()));
+
+            for segment in curve.segments() {
+                value = push(
+                    ops,
+                    Op::Spline {
+                        input,
+                        before: value,
+                        segment,
+                    },
+                );
+            }
+
+            return Some(value);
+        }
+        Node::Terrace {
+            steps,
+            ref heights,
+            smoothness,
+            invert,
+        } => {
+            let input = input_or(0, 0.0)?;
+            let heights = terrace_heights(steps, heights);
+
+            let Some(&lowest) = heights.first() else {
+                return Some(push(ops, Op::Constant(0.0)));
+            };
+
+            let zero = push(ops, Op::Constant(0.0));
+            let one = push(ops, Op::Constant(1.0));
+            let mut sum = push(ops, Op::Constant(lowest));
+
+            // every terrace adds its height once the input passed it
+            for pair in heights.windows(2) {
+                let from = push(ops, Op::Constant(pair[0]));
+                let to = push(ops, Op::Constant(pair[1]));
+                let t = push(ops, Op::Ternary(TernaryOp::InverseLerp, from, to, input));
+                let mut t = push(ops, Op::Ternary(TernaryOp::Clamp, t, zero, one));
+
+                if invert {
+                    t = push(ops, Op::Binary(BinaryOp::Sub, one, t));
+                }
+
+                let mut shaped = if smoothness <= 0.0 {
+                    push(ops, Op::Binary(BinaryOp::Step, one, t))
+                } else {
+                    let exponent = push(ops, Op::Constant(1.0 / smoothness));
+                    push(ops, Op::Binary(BinaryOp::Pow, t, exponent))
+                };
+
+                if invert {
+                    shaped = push(ops, Op::Binary(BinaryOp::Sub, one, shaped));
+                }
+
+                let height = push(ops, Op::Constant(pair[1] - pair[0]));
+                let height = push(ops, Op::Binary(BinaryOp::Mul, shaped, height));
+                sum = push(ops, Op::Binary(BinaryOp::Add, sum, height));
+            }
+
+            return Some(sum);
+        }
+        Node::Quantize { levels, min, max } => {
+            let input = input_or(0, 0.0)?;
+            let min = input_or(1, min)?;
+            let max = input_or(2, max)?;
+
+            // one level would divide by zero
+            let levels = levels.max(2) as f32;
+            let zero = push(ops, Op::Constant(0.0));
+            let one = push(ops, Op::Constant(1.0));
+            let count = push(ops, Op::Constant(levels));
+            let last = push(ops, Op::Constant(levels - 1.0));
+
+            let t = push(ops, Op::Ternary(TernaryOp::InverseLerp, min, max, input));
+            let t = push(ops, Op::Ternary(TernaryOp::Clamp, t, zero, one));
+            let level = push(ops, Op::Binary(BinaryOp::Mul, t, count));
+            let level = push(ops, Op::Unary(UnaryOp::Floor, level));
+            let level = push(ops, Op::Binary(BinaryOp::Min, level, last));
+            let t = push(ops, Op::Binary(BinaryOp::Div, level, last));
+            Op::Ternary(TernaryOp::Lerp, min, max, t)
+        }
+        Node::Select {
+            a,
+            b,
+            control,
+            threshold,
+            falloff,
+        } => {
+            let a = input_or(0, a)?;
+            let b = input_or(1, b)?;
+            let control = input_or(2, control)?;
+            let threshold = input_or(3, threshold)?;
+
+            let falloff_pin = InPinId {
+                node: pin.node,
+                input: 4,
+            };
+
+            // a smoothstep between equal edges would divide by zero
+            let weight = if graph.input(falloff_pin).is_none() && falloff <= 0.0 {
+                push(ops, Op::Binary(BinaryOp::Step, threshold, control))
+            } else {
+                let falloff = input_or(4, falloff)?;
+                let half = push(ops, Op::Constant(0.5));
+                let half = push(ops, Op::Binary(BinaryOp::Mul, falloff, half));
+                let edge0 = push(ops, Op::Binary(BinaryOp::Sub, threshold, half));
+                let edge1 = push(ops, Op::Binary(BinaryOp::Add, threshold, half));
+                push(
+                    ops,
+                    Op::Ternary(TernaryOp::Smoothstep, edge0, edge1, control),
+                )
+            };
+
+            Op::Ternary(TernaryOp::Lerp, a, b, weight)
+        }
+        Node::Blend { a, b, weight } => {
+            let a = input_or(0, a)?;
+            let b = input_or(1, b)?;
+            let weight = input_or(2, weight)?;
+            let zero = push(ops, Op::Constant(0.0));
+            let one = push(ops, Op::Constant(1.0));
+            let weight = push(ops, Op::Ternary(TernaryOp::Clamp, weight, zero, one));
+            Op::Ternary(TernaryOp::Lerp, a, b, weight)
+        }
+        Node::Sum { ref weights } => {
+            let inputs = connected_inputs(ops, graph, pin.node, weights.len(), scope)?;
+
+            let terms: Vec<usize> = inputs
+                .into_iter()
+                .map(|(input, op)| {
+                    let weight = weights[input];
+
+                    if weight == 1.0 {
+                        return op;
+                    }
+
+                    let weight = push(ops, Op::Constant(weight));
+                    push(ops, Op::Binary(BinaryOp::Mul, op, weight))
+                })
+                .collect();
+
+            return Some(fold(ops, &terms, BinaryOp::Add, 0.0));
+        }
+        Node::Product { inputs } | Node::Minimum { inputs } | Node::Maximum { inputs } => {
+            let (op, empty) = match graph[pin.node] {
+                Node::Product { .. } => (BinaryOp::Mul, 1.0),
+                Node::Minimum { .. } => (BinaryOp::Min, 0.0),
+                _ => (BinaryOp::Max, 0.0),
+            };
+
+            let inputs = connected_inputs(ops, graph, pin.node, inputs, scope)?;
+            let operands: Vec<usize> = inputs.into_iter().map(|(_, op)| op).collect();
+            return Some(fold(ops, &operands, op, empty));
+        }
+        Node::Average { inputs } => {
+            let inputs = connected_inputs(ops, graph, pin.node, inputs, scope)?;
+            let operands: Vec<usize> = inputs.into_iter().map(|(_, op)| op).collect();
+            let sum = fold(ops, &operands, BinaryOp::Add, 0.0);
+
+            if operands.len() < 2 {
+                return Some(sum);
+            }
+
+            let count = push(ops, Op::Constant(operands.len() as f32));
+            Op::Binary(BinaryOp::Div, sum, count)
+        }
+        Node::Seed { seed } => Op::Seed {
+            input: input_or(0, 0.0)?,
+            seed,
+        },
+        Node::AddSeed { add } => Op::AddSeed {
+            input: input_or(0, 0.0)?,
+            add,
+        },
+        Node::MulSeed { mul } => Op::MulSeed {
+            input: input_or(0, 0.0)?,
+            mul,
+        },
+        Node::Position => match pin.output {
+            0 | 1 => Op::Position(pin.output),
+            _ => return None,
+        },
+        Node::Number { value } => return input_or(0, value),
+        Node::Group { group: id, .. } => {
+            let group = Scope::root(graph, scope).groups().get(id)?;
+            let output = group.outputs().nth(pin.output)?;
+
+            let scope = Scope {
+                graph,
+                node: pin.node,
+                parent: scope,
+            };
+
+            let pin = InPinId {
+                node: output,
+                input: 0,
+            };
+
+            return in_pin_or(ops, &group.graph, pin, 0.0, Some(&scope));
+        }
+        Node::GroupInput { value, .. } => {
+            let Some(scope) = scope else {
+                return Some(push(ops, Op::Constant(value)));
+            };
+
+            let input = scope.group()?.inputs().position(|id| id == pin.node)?;
+
+            let pin = InPinId {
+                node: scope.node,
+                input,
+            };
+
+            let value = scope.input_value(input, value);
+            return in_pin_or(ops, scope.graph, pin, value, scope.parent);
+        }
+        Node::GroupOutput { .. } => return None,
+    };
+
+    Some(push(ops, op))
+}
+
+/// Appends the ops of the expression, with `variable` returning the op of a variable.
+fn lower_expression(
+    ops: &mut Lowering,
+    expr: &Expr,
+    variable: &mut impl FnMut(&mut Lowering, Variable) -> Option<usize>,
+) -> Option<usize> {
+    let op = match *expr {
+        Expr::Number(value) => Op::Constant(value),
+        Expr::Variable(v) => return variable(ops, v),
+        Expr::Unary(op, ref input) => Op::Unary(op, lower_expression(ops, input, variable)?),
+        Expr::Binary(op, ref lhs, ref rhs) => Op::Binary(
+            op,
+            lower_expression(ops, lhs, variable)?,
+            lower_expression(ops, rhs, variable)?,
+        ),
+        Expr::Ternary(op, ref a, ref b, ref c) => Op::Ternary(
+            op,
+            lower_expression(ops, a, variable)?,
+            lower_expression(ops, b, variable)?,
+            lower_expression(ops, c, variable)?,
+        ),
+    };
+
+    Some(push(ops, op))
+}
+
+/// Appends the ops mapping the value from the range `from` to the range `to`.
+fn remap(
+    ops: &mut Lowering,
+    value: usize,
+    [from_min, from_max]: [usize; 2],
+    [to_min, to_max]: [usize; 2],
+    clamp: bool,
+) -> usize {
+    let mut t = push(
+        ops,
+        Op::Ternary(TernaryOp::InverseLerp, from_min, from_max, value),
+    );
+
+    if clamp {
+        let zero = push(ops, Op::Constant(0.0));
+        let one = push(ops, Op::Constant(1.0));
+        t = push(ops, Op::Ternary(TernaryOp::Clamp, t, zero, one));
+    }
+
+    push(ops, Op::Ternary(TernaryOp::Lerp, to_min, to_max, t))
+}
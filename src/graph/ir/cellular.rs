@@ -0,0 +1,111 @@
+//! The cell noise of [`Node::Cellular`](crate::graph::Node::Cellular).
+//!
+//! Unlike [`CellKind`](super::CellKind) it is computed here the same way as `noise_cellular`
+//! in the shader preludes, so the shaders show the same cells as the sampler.
+
+use crate::graph::CellMetric;
+
+const PRIME_X: i32 = 501125321;
+const PRIME_Y: i32 = 1136930381;
+
+/// The outputs of [`Node::Cellular`](crate::graph::Node::Cellular),
+/// like FastNoiseLite's cellular return types.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CellularOutput {
+    /// The distance to the nearest cell point.
+    F1,
+    /// The distance to the second nearest cell point.
+    F2,
+    /// F2 - F1, which is 0 on the edges between cells.
+    Difference,
+    /// F1 · F2.
+    Product,
+    /// The value of the nearest cell's hash in -1..1.
+    Value,
+}
+
+/// The distances to the nearest and the second nearest cell point and the nearest cell's hash.
+pub struct Cells {
+    pub f1: f32,
+    pub f2: f32,
+    pub hash: i32,
+}
+
+impl CellularOutput {
+    /// In the order of the node's outputs.
+    pub const ALL: [CellularOutput; 5] = [
+        CellularOutput::F1,
+        CellularOutput::F2,
+        CellularOutput::Difference,
+        CellularOutput::Product,
+        CellularOutput::Value,
+    ];
+
+    /// The output, with the distances moved down by 1 like FastNoiseLite does.
+    pub fn of(self, cells: &Cells) -> f32 {
+        match self {
+            CellularOutput::F1 => cells.f1 - 1.0,
+            CellularOutput::F2 => cells.f2 - 1.0,
+            CellularOutput::Difference => cells.f2 - cells.f1 - 1.0,
+            CellularOutput::Product => cells.f1 * cells.f2 * 0.5 - 1.0,
+            CellularOutput::Value => cells.hash as f32 * (1.0 / 2147483648.0),
+        }
+    }
+}
+
+impl CellMetric {
+    /// The smallest exponent of [`CellMetric::Minkowski`], smaller ones are raised to it
+    /// because the length is infinite or undefined towards 0.
+    pub const MIN_EXPONENT: f32 = 0.1;
+
+    /// The length of the vector in this metric.
+    pub fn length(self, [x, y]: [f32; 2], exponent: f32) -> f32 {
+        match self {
+            CellMetric::Euclidean => x.hypot(y),
+            CellMetric::Manhattan => x.abs() + y.abs(),
+            CellMetric::Chebyshev => x.abs().max(y.abs()),
+            CellMetric::Minkowski => {
+                let exponent = exponent.max(Self::MIN_EXPONENT);
+                (x.abs().powf(exponent) + y.abs().powf(exponent)).powf(1.0 / exponent)
+            }
+        }
+    }
+}
+
+/// Searches the cells around the point, whose points are moved from their centers
+/// by up to `jitter` times a bit less than half a cell.
+pub fn cells(point: [f32; 2], seed: i32, jitter: f32, metric: CellMetric, exponent: f32) -> Cells {
+    let round = |x: f32| (x + if x >= 0.0 { 0.5 } else { -0.5 }) as i32;
+    let [xr, yr] = point.map(round);
+    let cell_jitter = 0.43701595 * jitter;
+
+    let mut cells = Cells {
+        f1: 1e10,
+        f2: 1e10,
+        hash: 0,
+    };
+
+    // far away points round to the largest cells
+    for xi in xr.saturating_sub(1)..=xr.saturating_add(1) {
+        for yi in yr.saturating_sub(1)..=yr.saturating_add(1) {
+            let hash = (seed ^ xi.wrapping_mul(PRIME_X) ^ yi.wrapping_mul(PRIME_Y))
+                .wrapping_mul(668265261);
+            let angle = ((hash >> 1) & 255) as f32 * (std::f32::consts::TAU / 256.0);
+            let offset = [
+                xi as f32 - point[0] + angle.cos() * cell_jitter,
+                yi as f32 - point[1] + angle.sin() * cell_jitter,
+            ];
+            let distance = metric.length(offset, exponent);
+
+            if distance < cells.f1 {
+                cells.f2 = cells.f1;
+                cells.f1 = distance;
+                cells.hash = hash;
+            } else if distance < cells.f2 {
+                cells.f2 = distance;
+            }
+        }
+    }
+
+    cells
+}
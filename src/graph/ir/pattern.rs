@@ -0,0 +1,279 @@
+//! The ops of the pattern nodes like [`Node::Checkerboard`](crate::graph::Node::Checkerboard),
+//! which are math on the position.
+//!
+//! The functions take the ops of the node's inputs and return the op of its output.
+
+use super::{push, BinaryOp, Lowering, Op, TernaryOp, UnaryOp, SQRT_3};
+use crate::graph::FalloffShape;
+
+/// Pushes the ops of a formula.
+struct Formula<'a>(&'a mut Lowering);
+
+impl Formula<'_> {
+    fn constant(&mut self, value: f32) -> usize {
+        push(self.0, Op::Constant(value))
+    }
+
+    fn unary(&mut self, op: UnaryOp, x: usize) -> usize {
+        push(self.0, Op::Unary(op, x))
+    }
+
+    fn binary(&mut self, op: BinaryOp, a: usize, b: usize) -> usize {
+        push(self.0, Op::Binary(op, a, b))
+    }
+
+    fn position(&mut self) -> [usize; 2] {
+        [push(self.0, Op::Position(0)), push(self.0, Op::Position(1))]
+    }
+
+    /// The position relative to the center.
+    fn relative(&mut self, [x, y]: [usize; 2]) -> [usize; 2] {
+        let [px, py] = self.position();
+        [
+            self.binary(BinaryOp::Sub, px, x),
+            self.binary(BinaryOp::Sub, py, y),
+        ]
+    }
+
+    /// How far the position is along the angle in degrees.
+    fn along(&mut self, angle: usize) -> usize {
+        let [x, y] = self.position();
+        let to_radians = self.constant(std::f32::consts::PI / 180.0);
+        let radians = self.binary(BinaryOp::Mul, angle, to_radians);
+        let cos = self.unary(UnaryOp::Cos, radians);
+        let sin = self.unary(UnaryOp::Sin, radians);
+        let x = self.binary(BinaryOp::Mul, x, cos);
+        let y = self.binary(BinaryOp::Mul, y, sin);
+        self.binary(BinaryOp::Add, x, y)
+    }
+
+    /// 1 where the floor of `t` is even and -1 where it is odd.
+    fn alternate(&mut self, t: usize) -> usize {
+        let one = self.constant(1.0);
+        let two = self.constant(2.0);
+        let half = self.constant(0.5);
+        let t = self.unary(UnaryOp::Floor, t);
+        let halved = self.binary(BinaryOp::Mul, t, half);
+        let halved = self.unary(UnaryOp::Floor, halved);
+        let pairs = self.binary(BinaryOp::Mul, two, halved);
+        let odd = self.binary(BinaryOp::Sub, t, pairs);
+        let odd = self.binary(BinaryOp::Mul, two, odd);
+        self.binary(BinaryOp::Sub, one, odd)
+    }
+
+    /// 1 where the distance is less than half the thickness and -1 elsewhere.
+    fn line(&mut self, distance: usize, thickness: usize) -> usize {
+        let one = self.constant(1.0);
+        let two = self.constant(2.0);
+        let half = self.constant(0.5);
+        let edge = self.binary(BinaryOp::Mul, thickness, half);
+        let outside = self.binary(BinaryOp::Step, edge, distance);
+        let outside = self.binary(BinaryOp::Mul, two, outside);
+        self.binary(BinaryOp::Sub, one, outside)
+    }
+
+    /// `value` modulo `modulus`, with the sign of the modulus.
+    fn modulo(&mut self, value: usize, modulus: usize) -> usize {
+        let times = self.binary(BinaryOp::Div, value, modulus);
+        let times = self.unary(UnaryOp::Floor, times);
+        let whole = self.binary(BinaryOp::Mul, modulus, times);
+        self.binary(BinaryOp::Sub, value, whole)
+    }
+}
+
+pub(super) fn checkerboard(ops: &mut Lowering, size: usize) -> usize {
+    let mut f = Formula(ops);
+    let [x, y] = f.position();
+    let x = f.binary(BinaryOp::Div, x, size);
+    let x = f.unary(UnaryOp::Floor, x);
+    let y = f.binary(BinaryOp::Div, y, size);
+    let y = f.unary(UnaryOp::Floor, y);
+    let cells = f.binary(BinaryOp::Add, x, y);
+    f.alternate(cells)
+}
+
+pub(super) fn stripes(ops: &mut Lowering, width: usize, angle: usize) -> usize {
+    let mut f = Formula(ops);
+    let t = f.along(angle);
+    let t = f.binary(BinaryOp::Div, t, width);
+    f.alternate(t)
+}
+
+pub(super) fn rings(ops: &mut Lowering, width: usize) -> usize {
+    let mut f = Formula(ops);
+    let [x, y] = f.position();
+    let distance = f.binary(BinaryOp::Hypot, x, y);
+    let t = f.binary(BinaryOp::Div, distance, width);
+    f.alternate(t)
+}
+
+pub(super) fn radial_gradient(ops: &mut Lowering, center: [usize; 2], radius: usize) -> usize {
+    let mut f = Formula(ops);
+    let one = f.constant(1.0);
+    let two = f.constant(2.0);
+    let [x, y] = f.relative(center);
+    let distance = f.binary(BinaryOp::Hypot, x, y);
+    let t = f.binary(BinaryOp::Div, distance, radius);
+    let t = f.unary(UnaryOp::Saturate, t);
+    let t = f.binary(BinaryOp::Mul, two, t);
+    f.binary(BinaryOp::Sub, one, t)
+}
+
+pub(super) fn linear_gradient(ops: &mut Lowering, angle: usize, length: usize) -> usize {
+    let mut f = Formula(ops);
+    let two = f.constant(2.0);
+    let minus_one = f.constant(-1.0);
+    let one = f.constant(1.0);
+    let t = f.along(angle);
+    let t = f.binary(BinaryOp::Mul, t, two);
+    let t = f.binary(BinaryOp::Div, t, length);
+    push(f.0, Op::Ternary(TernaryOp::Clamp, t, minus_one, one))
+}
+
+pub(super) fn circle_sdf(ops: &mut Lowering, center: [usize; 2], radius: usize) -> usize {
+    let mut f = Formula(ops);
+    let [x, y] = f.relative(center);
+    let distance = f.binary(BinaryOp::Hypot, x, y);
+    f.binary(BinaryOp::Sub, distance, radius)
+}
+
+pub(super) fn box_sdf(
+    ops: &mut Lowering,
+    center: [usize; 2],
+    [width, height]: [usize; 2],
+) -> usize {
+    let mut f = Formula(ops);
+    let zero = f.constant(0.0);
+    let half = f.constant(0.5);
+    let [x, y] = f.relative(center);
+
+    // the distances outside the sides, negative inside
+    let x = f.unary(UnaryOp::Abs, x);
+    let half_width = f.binary(BinaryOp::Mul, width, half);
+    let x = f.binary(BinaryOp::Sub, x, half_width);
+    let y = f.unary(UnaryOp::Abs, y);
+    let half_height = f.binary(BinaryOp::Mul, height, half);
+    let y = f.binary(BinaryOp::Sub, y, half_height);
+
+    let outside_x = f.binary(BinaryOp::Max, x, zero);
+    let outside_y = f.binary(BinaryOp::Max, y, zero);
+    let outside = f.binary(BinaryOp::Hypot, outside_x, outside_y);
+    let inside = f.binary(BinaryOp::Max, x, y);
+    let inside = f.binary(BinaryOp::Min, inside, zero);
+    f.binary(BinaryOp::Add, outside, inside)
+}
+
+pub(super) fn grid_lines(ops: &mut Lowering, spacing: usize, thickness: usize) -> usize {
+    let mut f = Formula(ops);
+    let position = f.position();
+
+    // the distances to the nearest vertical and horizontal line
+    let [x, y] = position.map(|p| {
+        let line = f.binary(BinaryOp::Div, p, spacing);
+        let line = f.unary(UnaryOp::Round, line);
+        let line = f.binary(BinaryOp::Mul, spacing, line);
+        let distance = f.binary(BinaryOp::Sub, p, line);
+        f.unary(UnaryOp::Abs, distance)
+    });
+
+    let distance = f.binary(BinaryOp::Min, x, y);
+    f.line(distance, thickness)
+}
+
+/// Hexagons with flat left and right sides whose centers are `size` apart.
+pub(super) fn hex_grid(ops: &mut Lowering, size: usize, thickness: usize) -> usize {
+    let mut f = Formula(ops);
+    let [x, y] = f.position();
+    let x = f.binary(BinaryOp::Div, x, size);
+    let y = f.binary(BinaryOp::Div, y, size);
+
+    // the centers lie on two rectangular grids, the second one offset by half a rectangle
+    let rect = [f.constant(1.0), f.constant(SQRT_3)];
+    let half_rect = [f.constant(0.5), f.constant(SQRT_3 * 0.5)];
+
+    let mut offsets = [[0; 2]; 2];
+
+    for (grid, offset) in offsets.iter_mut().enumerate() {
+        for axis in 0..2 {
+            let mut p = [x, y][axis];
+
+            if grid == 1 {
+                p = f.binary(BinaryOp::Sub, p, half_rect[axis]);
+            }
+
+            let p = f.modulo(p, rect[axis]);
+            offset[axis] = f.binary(BinaryOp::Sub, p, half_rect[axis]);
+        }
+    }
+
+    // the offset from the nearer center
+    let [a, b] = offsets.map(|[x, y]| {
+        let x2 = f.binary(BinaryOp::Mul, x, x);
+        let y2 = f.binary(BinaryOp::Mul, y, y);
+        f.binary(BinaryOp::Add, x2, y2)
+    });
+    let second = f.binary(BinaryOp::Step, b, a);
+    let [x, y] = [0, 1].map(|axis| {
+        let offset = push(
+            f.0,
+            Op::Ternary(TernaryOp::Lerp, offsets[0][axis], offsets[1][axis], second),
+        );
+        f.unary(UnaryOp::Abs, offset)
+    });
+
+    // the distance to the center in the hexagon's metric, 0.5 at the sides
+    let half = half_rect[0];
+    let slanted_x = f.binary(BinaryOp::Mul, x, half);
+    let slanted_y = f.binary(BinaryOp::Mul, y, half_rect[1]);
+    let slanted = f.binary(BinaryOp::Add, slanted_x, slanted_y);
+    let distance = f.binary(BinaryOp::Max, x, slanted);
+
+    let edge = f.binary(BinaryOp::Sub, half, distance);
+    let edge = f.binary(BinaryOp::Mul, edge, size);
+    f.line(edge, thickness)
+}
+
+pub(super) fn falloff(
+    ops: &mut Lowering,
+    shape: FalloffShape,
+    center: [usize; 2],
+    radius: usize,
+    exponent: usize,
+    invert: bool,
+) -> usize {
+    let mut f = Formula(ops);
+    let [x, y] = f.relative(center);
+    let x = f.binary(BinaryOp::Div, x, radius);
+    let x = f.unary(UnaryOp::Abs, x);
+    let y = f.binary(BinaryOp::Div, y, radius);
+    let y = f.unary(UnaryOp::Abs, y);
+
+    // the distance from the center in the shape's metric, 1 at its edge
+    let distance = match shape {
+        FalloffShape::Circle => f.binary(BinaryOp::Hypot, x, y),
+        FalloffShape::Square => f.binary(BinaryOp::Max, x, y),
+        FalloffShape::RoundedBox => {
+            let zero = f.constant(0.0);
+            let corner = f.constant(0.5);
+            let x = f.binary(BinaryOp::Sub, x, corner);
+            let y = f.binary(BinaryOp::Sub, y, corner);
+            let outside_x = f.binary(BinaryOp::Max, x, zero);
+            let outside_y = f.binary(BinaryOp::Max, y, zero);
+            let outside = f.binary(BinaryOp::Hypot, outside_x, outside_y);
+            let inside = f.binary(BinaryOp::Max, x, y);
+            let inside = f.binary(BinaryOp::Min, inside, zero);
+            let distance = f.binary(BinaryOp::Add, outside, inside);
+            f.binary(BinaryOp::Add, distance, corner)
+        }
+    };
+
+    let t = f.unary(UnaryOp::Saturate, distance);
+    let t = f.binary(BinaryOp::Pow, t, exponent);
+
+    if invert {
+        t
+    } else {
+        let one = f.constant(1.0);
+        f.binary(BinaryOp::Sub, one, t)
+    }
+}
@@ -0,0 +1,148 @@
+use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
+
+use crate::{
+    fragment::Fragment,
+    graph::{Group, GroupLibrary, Node},
+    nodes_snarl,
+};
+
+/// Replaces the nodes with a group node that contains them and adds the group to the library.
+///
+/// Wires going into the selection become group inputs, wires going out of it become group outputs.
+/// The first output of `preview` becomes the first group output if it's one of the nodes,
+/// so the group node previews the same noise.
+pub fn collapse(
+    snarl: &mut Snarl<Node>,
+    groups: &mut GroupLibrary,
+    node_ids: &[NodeId],
+    preview: Option<NodeId>,
+) -> Option<NodeId> {
+    const PIN_NODE_SPACING: egui::Vec2 = egui::vec2(250.0, 100.0);
+
+    let node_ids: Vec<NodeId> = node_ids
+        .iter()
+        .copied()
+        .filter(|&id| snarl.get_node(id).is_some())
+        .collect();
+
+    if node_ids.is_empty() {
+        return None;
+    }
+
+    let is_inside = |id: NodeId| node_ids.contains(&id);
+
+    // wires crossing the selection, grouped by the pin they come from
+    let mut incoming = Vec::<(OutPinId, Vec<InPinId>)>::new();
+    let mut outgoing = Vec::<(OutPinId, Vec<InPinId>)>::new();
+
+    if let Some(node) = preview.filter(|&node| is_inside(node)) {
+        outgoing.push((OutPinId { node, output: 0 }, Vec::new()));
+    }
+
+    for (out_pin, in_pin) in snarl.wires() {
+        let crossing = match (is_inside(out_pin.node), is_inside(in_pin.node)) {
+            (false, true) => &mut incoming,
+            (true, false) => &mut outgoing,
+            _ => continue,
+        };
+
+        match crossing.iter_mut().find(|(from, _)| *from == out_pin) {
+            Some((_, to)) => to.push(in_pin),
+            None => crossing.push((out_pin, vec![in_pin])),
+        }
+    }
+
+    let fragment = Fragment::new(snarl, &node_ids, groups);
+    let mut inner = Snarl::new();
+    let inner_ids = fragment.insert(&mut inner, groups, egui::Pos2::ZERO);
+    let inner_id = |id: NodeId| inner_ids[node_ids.iter().position(|&i| i == id).unwrap()];
+
+    let right = inner_ids
+        .iter()
+        .filter_map(|&id| inner.get_node_info(id))
+        .map(|info| info.pos.x)
+        .fold(0.0, f32::max)
+        + PIN_NODE_SPACING.x;
+
+    for (i, (_, to)) in incoming.iter().enumerate() {
+        let input = inner.insert_node(
+            egui::pos2(-PIN_NODE_SPACING.x, i as f32 * PIN_NODE_SPACING.y),
+            Node::GroupInput {
+                name: format!("Input {}", i + 1),
+                value: 0.0,
+            },
+        );
+
+        for in_pin in to {
+            inner.connect(
+                OutPinId {
+                    node: input,
+                    output: 0,
+                },
+                InPinId {
+                    node: inner_id(in_pin.node),
+                    input: in_pin.input,
+                },
+            );
+        }
+    }
+
+    for (i, (from, _)) in outgoing.iter().enumerate() {
+        let output = inner.insert_node(
+            egui::pos2(right, i as f32 * PIN_NODE_SPACING.y),
+            Node::GroupOutput {
+                name: format!("Output {}", i + 1),
+            },
+        );
+
+        inner.connect(
+            OutPinId {
+                node: inner_id(from.node),
+                output: from.output,
+            },
+            InPinId {
+                node: output,
+                input: 0,
+            },
+        );
+    }
+
+    for &id in &node_ids {
+        snarl.remove_node(id);
+    }
+
+    let graph = nodes_snarl::snarl_to_graph(&inner);
+    let group = groups.insert(Group::new("Group".into(), graph));
+
+    let group = snarl.insert_node(
+        fragment.origin(),
+        Node::Group {
+            group,
+            values: Vec::new(),
+        },
+    );
+
+    for (i, (from, _)) in incoming.iter().enumerate() {
+        snarl.connect(
+            *from,
+            InPinId {
+                node: group,
+                input: i,
+            },
+        );
+    }
+
+    for (i, (_, to)) in outgoing.iter().enumerate() {
+        for &in_pin in to {
+            snarl.connect(
+                OutPinId {
+                    node: group,
+                    output: i,
+                },
+                in_pin,
+            );
+        }
+    }
+
+    Some(group)
+}
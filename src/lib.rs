@@ -5,7 +5,31 @@
     clippy::collapsible_else_if
 )]
 
+#[cfg(feature = "gui")]
 mod app;
+#[cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+pub mod cli;
+#[cfg(feature = "gui")]
+mod codegen;
+#[cfg(feature = "gui")]
+mod curve_editor;
+#[cfg(feature = "gui")]
+mod fragment;
+#[cfg(feature = "core")]
+pub mod graph;
+#[cfg(feature = "gui")]
+mod group;
+#[cfg(feature = "gui")]
 mod message_box;
+#[cfg(feature = "gui")]
 mod nodes_snarl;
+#[cfg(feature = "gui")]
+mod palette;
+#[cfg(feature = "gui")]
+mod presets;
+#[cfg(any(feature = "gui", all(feature = "cli", not(target_arch = "wasm32"))))]
+mod render;
+#[cfg(feature = "gui")]
 pub use app::App;
+#[cfg(feature = "core")]
+pub use graph::{build_sampler, Graph};
@@ -28,7 +28,6 @@ impl MessageBox {
         });
     }
 
-    #[cfg_attr(not(target_arch = "wasm32"), expect(dead_code))]
     pub fn open(&mut self, title: impl Into<String>, message: impl Into<String>) {
         self.is_open = true;
         self.title = title.into();
@@ -1,142 +1,531 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
 use egui_snarl::{
     ui::{
-        BackgroundPattern, Grid, NodeLayout, PinInfo, PinPlacement, PinShape, SnarlStyle,
+        AnyPins, BackgroundPattern, Grid, NodeLayout, PinInfo, PinPlacement, PinShape, SnarlStyle,
         SnarlViewer,
     },
-    InPin, InPinId, NodeId, OutPinId, Snarl,
+    InPin, InPinId, NodeId, OutPin, OutPinId, Snarl,
 };
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Serialize, Deserialize)]
-pub enum Node {
-    Value,
-    ValueCubic,
-    Perlin,
-    Simplex,
-    OpenSimplex2,
-    OpenSimplex2s,
-    CellValue {
-        jitter: f32,
-    },
-    CellDistance {
-        jitter: f32,
-    },
-    CellDistanceSq {
-        jitter: f32,
-    },
-
-    // misc
-    Fractal {
-        octaves: u32,
-        gain: f32,
-        lacunarity: f32,
-        weighted_strength: f32,
-    },
-    Frequency {
-        frequency: f32,
-    },
-    TriangleWave {
-        frequency: f32,
-    },
-
-    // translate
-    TranslateXy {
-        x: f32,
-        y: f32,
-    },
-
-    // unary
-    Abs,
-    Neg,
-    Sqrt,
-    Floor,
-    Ceil,
-    Round,
-    Sin,
-    Cos,
-    Tan,
-
-    // binary
-    Add {
-        lhs: f32,
-        rhs: f32,
-    },
-    Sub {
-        lhs: f32,
-        rhs: f32,
-    },
-    Mul {
-        lhs: f32,
-        rhs: f32,
-    },
-    Div {
-        lhs: f32,
-        rhs: f32,
-    },
-    Rem {
-        lhs: f32,
-        rhs: f32,
-    },
-    Pow {
-        lhs: f32,
-        rhs: f32,
-    },
-    Min {
-        lhs: f32,
-        rhs: f32,
-    },
-    Max {
-        lhs: f32,
-        rhs: f32,
-    },
+use crate::{
+    curve_editor,
+    fragment::Fragment,
+    graph::{self, curve::Curve, CellMetric, FalloffShape, Graph, GroupId, GroupLibrary, Node},
+    group,
+    palette::{Palette, PaletteItem},
+    presets::PresetLibrary,
+};
 
-    // ternary
-    Lerp {
-        a: f32,
-        b: f32,
-        t: f32,
-    },
-    Clamp {
-        value: f32,
-        min: f32,
-        max: f32,
-    },
+impl Node {
+    /// Other names to find this node by in the palette.
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Node::Value | Node::ValueCubic => &["random", "white noise"],
+            Node::Perlin | Node::Simplex | Node::OpenSimplex2 | Node::OpenSimplex2s => {
+                &["gradient"]
+            }
+            Node::CellValue { .. } | Node::CellDistance { .. } | Node::CellDistanceSq { .. } => {
+                &["cellular", "voronoi", "worley"]
+            }
+            Node::Cellular { .. } => &["voronoi", "worley", "cell", "f1", "f2", "manhattan"],
+            Node::Checkerboard { .. } => &["checker", "chess", "tiles", "pattern"],
+            Node::Stripes { .. } => &["bands", "lines", "pattern"],
+            Node::Rings { .. } => &["circles", "concentric", "target", "pattern"],
+            Node::RadialGradient { .. } => &["circle", "falloff", "island", "ramp"],
+            Node::LinearGradient { .. } => &["ramp", "slope", "fade"],
+            Node::CircleSdf { .. } => &["signed distance", "disc", "shape", "mask"],
+            Node::BoxSdf { .. } => &["signed distance", "rectangle", "square", "shape", "mask"],
+            Node::GridLines { .. } => &["lines", "tiles", "pattern"],
+            Node::HexGrid { .. } => &["hexagon", "honeycomb", "tiles", "pattern"],
+            Node::Falloff { .. } => &["island", "vignette", "edge", "mask", "gradient"],
+            Node::Fractal { .. } => &["fbm", "octaves", "layers"],
+            Node::Frequency { .. } => &["scale", "zoom"],
+            Node::TriangleWave { .. } => &["ping pong", "ridge"],
+            Node::TranslateXy { .. } => &["offset", "move", "shift"],
+            Node::Abs => &["absolute"],
+            Node::Neg => &["negate", "invert", "minus"],
+            Node::Sqrt => &["square root", "root"],
+            Node::Floor | Node::Ceil | Node::Round => &["integer", "snap"],
+            Node::Sin | Node::Cos | Node::Tan => &["trigonometry", "wave"],
+            Node::Exp => &["exponential", "e"],
+            Node::Ln | Node::Log2 => &["logarithm", "log"],
+            Node::Sign => &["signum", "sgn"],
+            Node::Fract => &["fraction", "fractional", "repeat"],
+            Node::Reciprocal => &["inverse", "one over"],
+            Node::OneMinus => &["invert", "complement"],
+            Node::Saturate => &["clamp", "limit"],
+            Node::Add { .. } => &["plus", "sum"],
+            Node::Sub { .. } => &["subtract", "minus", "difference"],
+            Node::Mul { .. } => &["multiply", "times", "product"],
+            Node::Div { .. } => &["divide", "quotient"],
+            Node::Rem { .. } => &["modulo", "remainder"],
+            Node::Pow { .. } => &["power", "exponent"],
+            Node::Min { .. } => &["minimum", "smaller"],
+            Node::Max { .. } => &["maximum", "larger"],
+            Node::Step { .. } => &["threshold", "heaviside", "mask"],
+            Node::Atan2 { .. } => &["angle", "arctangent", "polar"],
+            Node::Hypot { .. } => &["length", "distance", "magnitude"],
+            Node::Lerp { .. } => &["mix", "interpolate", "blend"],
+            Node::InverseLerp { .. } => &["unlerp", "fraction", "normalize"],
+            Node::Smoothstep { .. } | Node::Smootherstep { .. } => &["ease", "fade", "hermite"],
+            Node::Clamp { .. } => &["limit", "saturate"],
+            Node::SmoothMin { .. } | Node::SmoothMax { .. } => &["soft", "union", "blend"],
+            Node::Remap { .. } => &["range", "map", "rescale", "fit"],
+            Node::Normalize { .. } => &["range", "rescale", "auto", "fit"],
+            Node::Expression { .. } => &["formula", "equation", "math", "code"],
+            Node::Curve(_) => &["spline", "ramp", "response", "shape", "gradient map"],
+            Node::Terrace { .. } => &["steps", "stairs", "plateau", "mesa"],
+            Node::Quantize { .. } => &["posterize", "levels", "steps", "snap"],
+            Node::Select { .. } => &["switch", "if", "threshold", "mask", "biome"],
+            Node::Blend { .. } => &["mix", "mask", "lerp", "biome"],
+            Node::Sum { .. } => &["add", "plus", "weighted", "layers", "many"],
+            Node::Product { .. } => &["multiply", "times", "many"],
+            Node::Minimum { .. } => &["min", "smallest", "many"],
+            Node::Maximum { .. } => &["max", "largest", "many"],
+            Node::Average { .. } => &["mean", "mix", "many"],
+            Node::Seed { .. } | Node::AddSeed { .. } | Node::MulSeed { .. } => &["random"],
+            Node::Position => &["coordinates", "x", "y", "uv"],
+            Node::Number { .. } => &["constant", "float", "scalar"],
+            Node::Group { .. } => &["subgraph"],
+            Node::GroupInput { .. } => &["parameter"],
+            Node::GroupOutput { .. } => &["result"],
+        }
+    }
+}
 
-    // seed
-    Seed {
-        seed: i32,
-    },
-    AddSeed {
-        add: i32,
-    },
-    MulSeed {
-        mul: i32,
-    },
+/// The name of a category and its nodes with their names.
+pub type NodeCategory = (&'static str, Vec<(&'static str, Node)>);
 
-    // input
-    Position,
-    Number {
-        value: f32,
-    },
-}
+/// Node types that can be added from the graph menu and the palette.
+pub static NODES_BY_CATEGORY: LazyLock<Vec<NodeCategory>> = LazyLock::new(|| {
+    vec![
+        (
+            "Noise",
+            vec![
+                ("Value", Node::Value),
+                ("Value Cubic", Node::ValueCubic),
+                ("Perlin", Node::Perlin),
+                ("Simplex", Node::Simplex),
+                ("OpenSimplex2", Node::OpenSimplex2),
+                ("OpenSimplex2s", Node::OpenSimplex2s),
+                ("Cell Value", Node::CellValue { jitter: 1.0 }),
+                ("Cell Distance", Node::CellDistance { jitter: 1.0 }),
+                (
+                    "Cell Distance Squared",
+                    Node::CellDistanceSq { jitter: 1.0 },
+                ),
+                (
+                    "Cellular",
+                    Node::Cellular {
+                        metric: CellMetric::Euclidean,
+                        jitter: 1.0,
+                        exponent: 3.0,
+                    },
+                ),
+            ],
+        ),
+        (
+            "Pattern",
+            vec![
+                ("Checkerboard", Node::Checkerboard { size: 0.5 }),
+                (
+                    "Stripes",
+                    Node::Stripes {
+                        width: 0.25,
+                        angle: 0.0,
+                    },
+                ),
+                ("Rings", Node::Rings { width: 0.25 }),
+                (
+                    "Radial Gradient",
+                    Node::RadialGradient {
+                        x: 0.0,
+                        y: 0.0,
+                        radius: 1.0,
+                    },
+                ),
+                (
+                    "Linear Gradient",
+                    Node::LinearGradient {
+                        angle: 0.0,
+                        length: 2.0,
+                    },
+                ),
+                (
+                    "Circle SDF",
+                    Node::CircleSdf {
+                        x: 0.0,
+                        y: 0.0,
+                        radius: 0.5,
+                    },
+                ),
+                (
+                    "Box SDF",
+                    Node::BoxSdf {
+                        x: 0.0,
+                        y: 0.0,
+                        width: 1.0,
+                        height: 1.0,
+                    },
+                ),
+                (
+                    "Grid Lines",
+                    Node::GridLines {
+                        spacing: 0.5,
+                        thickness: 0.05,
+                    },
+                ),
+                (
+                    "Hex Grid",
+                    Node::HexGrid {
+                        size: 0.5,
+                        thickness: 0.05,
+                    },
+                ),
+                (
+                    "Falloff",
+                    Node::Falloff {
+                        shape: FalloffShape::Circle,
+                        x: 0.0,
+                        y: 0.0,
+                        radius: 1.0,
+                        exponent: 2.0,
+                        invert: false,
+                    },
+                ),
+            ],
+        ),
+        (
+            "Transform",
+            vec![
+                (
+                    "Fractal",
+                    Node::Fractal {
+                        octaves: 3,
+                        gain: 0.5,
+                        lacunarity: 2.0,
+                        weighted_strength: 0.0,
+                    },
+                ),
+                ("Frequency", Node::Frequency { frequency: 1.0 }),
+                ("Translate", Node::TranslateXy { x: 0.0, y: 0.0 }),
+            ],
+        ),
+        (
+            "Math",
+            vec![
+                ("Abs", Node::Abs),
+                ("Neg", Node::Neg),
+                ("Sqrt", Node::Sqrt),
+                ("Floor", Node::Floor),
+                ("Ceil", Node::Ceil),
+                ("Round", Node::Round),
+                ("Sin", Node::Sin),
+                ("Cos", Node::Cos),
+                ("Tan", Node::Tan),
+                ("Exp", Node::Exp),
+                ("Ln", Node::Ln),
+                ("Log2", Node::Log2),
+                ("Sign", Node::Sign),
+                ("Fract", Node::Fract),
+                ("Reciprocal", Node::Reciprocal),
+                ("One Minus", Node::OneMinus),
+                ("Saturate", Node::Saturate),
+                ("Add", Node::Add { lhs: 0.0, rhs: 0.0 }),
+                ("Sub", Node::Sub { lhs: 0.0, rhs: 0.0 }),
+                ("Mul", Node::Mul { lhs: 1.0, rhs: 1.0 }),
+                ("Div", Node::Div { lhs: 1.0, rhs: 1.0 }),
+                ("Rem", Node::Rem { lhs: 1.0, rhs: 1.0 }),
+                ("Pow", Node::Pow { lhs: 1.0, rhs: 1.0 }),
+                ("Min", Node::Min { lhs: 0.0, rhs: 0.0 }),
+                ("Max", Node::Max { lhs: 0.0, rhs: 0.0 }),
+                (
+                    "Step",
+                    Node::Step {
+                        edge: 0.0,
+                        value: 0.0,
+                    },
+                ),
+                ("Atan2", Node::Atan2 { y: 0.0, x: 1.0 }),
+                ("Hypot", Node::Hypot { x: 0.0, y: 0.0 }),
+                (
+                    "Lerp",
+                    Node::Lerp {
+                        a: 0.0,
+                        b: 1.0,
+                        t: 0.5,
+                    },
+                ),
+                (
+                    "Inverse Lerp",
+                    Node::InverseLerp {
+                        a: 0.0,
+                        b: 1.0,
+                        value: 0.5,
+                    },
+                ),
+                (
+                    "Smoothstep",
+                    Node::Smoothstep {
+                        edge0: 0.0,
+                        edge1: 1.0,
+                        value: 0.5,
+                    },
+                ),
+                (
+                    "Smootherstep",
+                    Node::Smootherstep {
+                        edge0: 0.0,
+                        edge1: 1.0,
+                        value: 0.5,
+                    },
+                ),
+                (
+                    "Clamp",
+                    Node::Clamp {
+                        value: 0.5,
+                        min: 0.0,
+                        max: 1.0,
+                    },
+                ),
+                (
+                    "Smooth Min",
+                    Node::SmoothMin {
+                        lhs: 0.0,
+                        rhs: 0.0,
+                        k: 0.25,
+                    },
+                ),
+                (
+                    "Smooth Max",
+                    Node::SmoothMax {
+                        lhs: 0.0,
+                        rhs: 0.0,
+                        k: 0.25,
+                    },
+                ),
+                (
+                    "Remap",
+                    Node::Remap {
+                        value: 0.0,
+                        from_min: -1.0,
+                        from_max: 1.0,
+                        to_min: 0.0,
+                        to_max: 1.0,
+                        clamp: false,
+                    },
+                ),
+                (
+                    "Normalize",
+                    Node::Normalize {
+                        min: -1.0,
+                        max: 1.0,
+                    },
+                ),
+                ("Triangle Wave", Node::TriangleWave { frequency: 1.0 }),
+                (
+                    "Expression",
+                    Node::Expression {
+                        expression: String::new(),
+                        a: 0.0,
+                        b: 0.0,
+                        c: 0.0,
+                    },
+                ),
+                ("Curve", Node::Curve(Curve::default())),
+                (
+                    "Terrace",
+                    Node::Terrace {
+                        steps: 4,
+                        heights: Vec::new(),
+                        smoothness: 0.5,
+                        invert: false,
+                    },
+                ),
+                (
+                    "Quantize",
+                    Node::Quantize {
+                        levels: 4,
+                        min: -1.0,
+                        max: 1.0,
+                    },
+                ),
+            ],
+        ),
+        (
+            "Combine",
+            vec![
+                (
+                    "Select",
+                    Node::Select {
+                        a: -1.0,
+                        b: 1.0,
+                        control: 0.0,
+                        threshold: 0.0,
+                        falloff: 0.0,
+                    },
+                ),
+                (
+                    "Blend",
+                    Node::Blend {
+                        a: 0.0,
+                        b: 0.0,
+                        weight: 0.5,
+                    },
+                ),
+                (
+                    "Sum",
+                    Node::Sum {
+                        weights: vec![1.0, 1.0],
+                    },
+                ),
+                ("Product", Node::Product { inputs: 2 }),
+                ("Minimum", Node::Minimum { inputs: 2 }),
+                ("Maximum", Node::Maximum { inputs: 2 }),
+                ("Average", Node::Average { inputs: 2 }),
+            ],
+        ),
+        (
+            "Seed",
+            vec![
+                ("Seed", Node::Seed { seed: 0 }),
+                ("Add Seed", Node::AddSeed { add: 1 }),
+                ("Mul Seed", Node::MulSeed { mul: 1 }),
+            ],
+        ),
+        (
+            "Input",
+            vec![
+                ("Position", Node::Position),
+                ("Number", Node::Number { value: 0.0 }),
+            ],
+        ),
+        (
+            "Group",
+            vec![
+                (
+                    "Group Input",
+                    Node::GroupInput {
+                        name: String::new(),
+                        value: 0.0,
+                    },
+                ),
+                (
+                    "Group Output",
+                    Node::GroupOutput {
+                        name: String::new(),
+                    },
+                ),
+            ],
+        ),
+    ]
+});
 
 #[derive(Default, Serialize, Deserialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct Viewer {
     pub active_node: Option<NodeId>,
 
+    /// The groups that the group nodes of the root graph and of the groups refer to.
+    groups: GroupLibrary,
+
+    /// The group nodes that were entered to edit their graph, outermost first.
+    group_path: Vec<NodeId>,
+
+    /// The graphs of the groups in `group_path` while they are being edited.
+    #[serde(skip)]
+    group_snarls: Vec<Snarl<Node>>,
+
+    /// The app loads and saves these separately from the graph.
+    #[serde(skip)]
+    pub presets: PresetLibrary,
+
+    #[serde(skip)]
+    selected_nodes: Vec<NodeId>,
+
+    /// The nodes that were pasted or duplicated last. Snarl can't be told to select them, so they
+    /// are selected instead of its selection until that changes or the user clicks.
+    #[serde(skip)]
+    inserted_nodes: Vec<NodeId>,
+
+    /// Snarl's selection when the nodes were inserted.
+    #[serde(skip)]
+    snarl_selection: Vec<NodeId>,
+
+    #[serde(skip)]
+    selection_stroke: egui::Stroke,
+
+    /// The center of the graph view in screen coordinates.
+    #[serde(skip)]
+    view_center: egui::Pos2,
+
     #[serde(skip)]
     changed_nodes: egui::ahash::HashSet<NodeId>,
 
+    /// The Normalize nodes whose input range should be measured, see [`Viewer::take_measure_requests`].
+    #[serde(skip)]
+    measure_requests: Vec<NodeId>,
+
     #[serde(skip)]
     prev_active_node: Option<NodeId>,
+
+    #[serde(skip)]
+    palette: Palette,
+
+    /// Where the palette popup was opened, in screen and graph coordinates.
+    #[serde(skip)]
+    palette_popup: Option<(egui::Pos2, egui::Pos2)>,
+
+    /// The fragment that was pasted or duplicated last, where it was asked for and where it went,
+    /// so that copies of the same nodes don't stack up on each other.
+    #[serde(skip)]
+    last_insert: Option<(String, Option<egui::Pos2>, egui::Pos2)>,
+
+    /// Maps graph coordinates to screen coordinates, from the last nodes snarl has drawn.
+    #[serde(skip)]
+    graph_to_screen: Option<egui::emath::TSTransform>,
 }
 
 impl Viewer {
-    pub fn show(&mut self, snarl: &mut Snarl<Node>, ui: &mut egui::Ui) {
+    /// The root graph with the groups it uses.
+    pub fn graph(&self, root: &Snarl<Node>) -> Graph {
+        let mut graph = snarl_to_graph(root);
+        *graph.groups_mut() = self.groups.used_by(root.node_ids().map(|(_, node)| node));
+        graph
+    }
+
+    /// Starts editing the graph and returns its root graph.
+    pub fn open(&mut self, graph: &Graph) -> Snarl<Node> {
+        self.groups = graph.groups().clone();
+        self.active_node = None;
+        self.exit_groups();
+        graph_to_snarl(graph).0
+    }
+
+    pub fn show(&mut self, root: &mut Snarl<Node>, ui: &mut egui::Ui) {
         self.changed_nodes.clear();
         self.prev_active_node = self.active_node;
 
+        self.enter_groups(root);
+        let depth = self.group_path.len();
+
+        if !self.group_path.is_empty() {
+            self.show_group_path(root, ui);
+        }
+
+        let id_salt = ("snarl", self.group_path.clone());
+
+        // taken out so the viewer can be borrowed while showing the graph
+        let mut group_snarls = std::mem::take(&mut self.group_snarls);
+        let snarl = group_snarls.last_mut().unwrap_or(&mut *root);
+
+        self.view_center = ui.available_rect_before_wrap().center();
+        self.selection_stroke = ui.visuals().selection.stroke;
+        // set again by every node that is drawn
+        self.graph_to_screen = None;
+
         snarl.show(
             self,
             &SnarlStyle {
@@ -156,174 +545,509 @@ impl Viewer {
                 header_drag_space: Some(egui::Vec2::ZERO),
                 ..Default::default()
             },
-            "snarl",
+            &id_salt,
             ui,
         );
+
+        self.update_selection(snarl, &id_salt, ui);
+        self.handle_shortcuts(snarl, ui);
+        self.show_palette_popup(snarl, ui);
+
+        self.group_snarls = group_snarls;
+
+        // converting the graphs every frame is slow, leaving a group saves them on its own
+        if !self.changed_nodes.is_empty() || self.group_path.len() > depth {
+            self.save_groups(root);
+        }
+    }
+
+    /// Leaves all groups and edits the root graph.
+    pub fn exit_groups(&mut self) {
+        self.group_path.clear();
+        self.group_snarls.clear();
+    }
+
+    /// Converts the graphs of entered groups for editing, leaving groups that no longer exist.
+    fn enter_groups(&mut self, root: &Snarl<Node>) {
+        self.group_snarls.truncate(self.group_path.len());
+
+        while self.group_snarls.len() < self.group_path.len() {
+            let depth = self.group_snarls.len();
+            let parent = self.group_snarls.last().unwrap_or(root);
+
+            let group = parent
+                .get_node(self.group_path[depth])
+                .and_then(Node::group)
+                .and_then(|id| self.groups.get_mut(id));
+
+            let Some(group) = group else {
+                self.group_path.truncate(depth);
+                break;
+            };
+
+            let (snarl, ids) = graph_to_snarl(&group.graph);
+
+            // the group takes over the ids of the edited nodes, which it keeps when it's saved
+            group.graph = snarl_to_graph(&snarl);
+            group.replace_pin_ids(&ids.iter().map(|(&old, &new)| (old, new.into())).collect());
+
+            if let Some(node) = self.group_path.get_mut(depth + 1) {
+                if let Some(&id) = ids.get(&(*node).into()) {
+                    *node = id;
+                }
+            }
+
+            self.group_snarls.push(snarl);
+        }
+    }
+
+    /// The groups whose graphs are being edited, outermost first.
+    fn entered_groups(&self, root: &Snarl<Node>) -> Vec<GroupId> {
+        std::iter::once(root)
+            .chain(&self.group_snarls)
+            .zip(&self.group_path)
+            .filter_map(|(parent, &node)| parent.get_node(node)?.group())
+            .collect()
+    }
+
+    /// Stores the graphs of the entered groups in the library.
+    ///
+    /// [`Viewer::show`] only does so when nodes change or groups are entered or left,
+    /// so the app calls this before storing the viewer to keep nodes that were only moved.
+    pub fn save_groups(&mut self, root: &Snarl<Node>) {
+        for depth in 0..self.group_snarls.len() {
+            let parent = match depth {
+                0 => root,
+                _ => &self.group_snarls[depth - 1],
+            };
+
+            let Some(id) = parent
+                .get_node(self.group_path[depth])
+                .and_then(Node::group)
+            else {
+                continue;
+            };
+
+            if let Some(group) = self.groups.get_mut(id) {
+                group.graph = snarl_to_graph(&self.group_snarls[depth]);
+                group.update_pins();
+            }
+        }
+    }
+
+    /// Returns the selected nodes of the graph that is being edited.
+    pub fn selection(&self, root: &Snarl<Node>) -> Fragment {
+        let snarl = self.group_snarls.last().unwrap_or(root);
+        Fragment::new(snarl, &self.selected_nodes, &self.groups)
     }
 
-    pub fn changed(&self) -> Option<NodeId> {
-        if self.active_node != self.prev_active_node || !self.changed_nodes.is_empty() {
-            self.active_node
+    /// Inserts the fragment in the middle of the view of the graph that is being edited.
+    pub fn insert_fragment(&mut self, root: &mut Snarl<Node>, fragment: &Fragment) {
+        let screen_to_graph = self.screen_to_graph();
+        let pos = screen_to_graph * self.view_center;
+
+        // taken out so the viewer can be borrowed while inserting
+        let mut group_snarls = std::mem::take(&mut self.group_snarls);
+        let snarl = group_snarls.last_mut().unwrap_or(&mut *root);
+        self.insert_preset(snarl, fragment, pos);
+
+        self.group_snarls = group_snarls;
+        self.save_groups(root);
+    }
+
+    /// Inserts the nodes of a preset, which may have been imported from any file.
+    fn insert_preset(&mut self, snarl: &mut Snarl<Node>, fragment: &Fragment, pos: egui::Pos2) {
+        let mut fragment = fragment.clone();
+        fragment.remove_invalid_wires(self);
+
+        let nodes = fragment.insert(snarl, &mut self.groups, pos);
+        self.changed_nodes.extend(nodes);
+    }
+
+    /// Shows which group is being edited and a button to go back to the outer graph.
+    fn show_group_path(&mut self, root: &mut Snarl<Node>, ui: &mut egui::Ui) {
+        let mut names = vec!["Graph".to_string()];
+
+        for (depth, &id) in self.group_path.iter().enumerate() {
+            let parent = match depth {
+                0 => &*root,
+                _ => &self.group_snarls[depth - 1],
+            };
+
+            if let Some(group) = parent
+                .get_node(id)
+                .and_then(Node::group)
+                .and_then(|id| self.groups.get(id))
+            {
+                names.push(group.name.clone());
+            }
+        }
+
+        let exit = ui
+            .horizontal(|ui| {
+                let exit = ui.button("⬅ Exit Group").clicked();
+                ui.label(names.join(" › "));
+                exit
+            })
+            .inner;
+
+        if exit {
+            self.save_groups(root);
+            self.group_snarls.pop();
+
+            if let Some(group_node) = self.group_path.pop() {
+                let parent = self.group_snarls.last().unwrap_or(&*root);
+
+                if let Some(group) = parent.get_node(group_node).and_then(Node::group) {
+                    self.drop_removed_pins(root, group);
+                }
+
+                self.changed_nodes.insert(group_node);
+            }
+        }
+    }
+
+    /// Removes the wires of pins that the nodes of the group no longer have
+    /// because its group inputs or outputs were removed, in every graph that uses it.
+    fn drop_removed_pins(&mut self, root: &mut Snarl<Node>, group: GroupId) {
+        let Some(definition) = self.groups.get(group) else {
+            return;
+        };
+
+        let inputs = definition.inputs().count();
+        let outputs = definition.outputs().count();
+
+        // whether a wire is connected to a pin that the group's nodes no longer have
+        let is_removed = |from: Option<&Node>, output: usize, to: Option<&Node>, input: usize| {
+            let is_group = |node: Option<&Node>| node.and_then(Node::group) == Some(group);
+            (is_group(from) && output >= outputs) || (is_group(to) && input >= inputs)
+        };
+
+        for snarl in std::iter::once(&mut *root).chain(&mut self.group_snarls) {
+            let removed: Vec<(OutPinId, InPinId)> = snarl
+                .wires()
+                .filter(|(out_pin, in_pin)| {
+                    is_removed(
+                        snarl.get_node(out_pin.node),
+                        out_pin.output,
+                        snarl.get_node(in_pin.node),
+                        in_pin.input,
+                    )
+                })
+                .collect();
+
+            for (out_pin, in_pin) in removed {
+                snarl.disconnect(out_pin, in_pin);
+            }
+        }
+
+        for (_, definition) in self.groups.iter_mut() {
+            let graph = &mut definition.graph;
+
+            let removed: Vec<(graph::OutPinId, graph::InPinId)> = graph
+                .wires()
+                .filter(|(from, to)| {
+                    is_removed(
+                        graph.node(from.node),
+                        from.output,
+                        graph.node(to.node),
+                        to.input,
+                    )
+                })
+                .collect();
+
+            for (from, to) in removed {
+                graph.disconnect(from, to);
+            }
+        }
+    }
+
+    /// Opens the palette at the pointer on Space or Tab and inserts the picked item there.
+    fn show_palette_popup(&mut self, snarl: &mut Snarl<Node>, ui: &mut egui::Ui) {
+        if self.palette_popup.is_none() && !ui.ctx().wants_keyboard_input() {
+            let pointer = ui
+                .ctx()
+                .pointer_hover_pos()
+                .filter(|&pos| ui.max_rect().contains(pos));
+
+            if let Some(screen_pos) = pointer {
+                let open = ui.input_mut(|i| {
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Space)
+                        || i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)
+                });
+
+                if open {
+                    let screen_to_graph = self.screen_to_graph();
+                    self.palette_popup = Some((screen_pos, screen_to_graph * screen_pos));
+                }
+            }
+        }
+
+        let Some((screen_pos, graph_pos)) = self.palette_popup else {
+            return;
+        };
+
+        let area = egui::Area::new(egui::Id::new("palette"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(screen_pos)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style())
+                    .show(ui, |ui| self.palette.show(ui, &self.presets))
+                    .inner
+            });
+
+        if let Some(item) = area.inner {
+            match item {
+                PaletteItem::Node(node) => {
+                    snarl.insert_node(graph_pos, node);
+                }
+                PaletteItem::Preset(fragment) => {
+                    self.insert_preset(snarl, &fragment, graph_pos);
+                }
+            }
+
+            self.palette_popup = None;
+        } else if area.response.clicked_elsewhere()
+            || ui.input(|i| i.key_pressed(egui::Key::Escape))
+        {
+            self.palette_popup = None;
+        }
+    }
+
+    fn update_selection(
+        &mut self,
+        snarl: &Snarl<Node>,
+        id_salt: &impl std::hash::Hash,
+        ui: &mut egui::Ui,
+    ) {
+        let snarl_selection = Snarl::<Node>::get_selected_nodes(id_salt, ui);
+
+        if snarl_selection != self.snarl_selection || ui.input(|i| i.pointer.any_click()) {
+            self.inserted_nodes.clear();
+            self.snarl_selection = snarl_selection;
+        }
+
+        self.inserted_nodes
+            .retain(|&node| snarl.get_node(node).is_some());
+
+        self.selected_nodes = if self.inserted_nodes.is_empty() {
+            self.snarl_selection.clone()
         } else {
-            None
+            self.inserted_nodes.clone()
+        };
+    }
+
+    /// Connects a node that was created from a dropped wire to the pins the wire was dragged from.
+    fn connect_new_node(&mut self, node: NodeId, src_pins: AnyPins, snarl: &mut Snarl<Node>) {
+        match src_pins {
+            AnyPins::Out(out_pins) => {
+                let Some(&out_pin) = out_pins.first() else {
+                    return;
+                };
+
+                let inputs = self.inputs(&snarl[node]);
+
+                let Some(input) = (0..inputs).find(|&i| snarl[node].is_input_connectable(i)) else {
+                    return;
+                };
+
+                snarl.connect(out_pin, InPinId { node, input });
+                fit_variadic_inputs(snarl, node);
+                self.changed_nodes.insert(node);
+            }
+            AnyPins::In(in_pins) => {
+                if self.outputs(&snarl[node]) == 0 {
+                    return;
+                }
+
+                let out_pin = OutPinId { node, output: 0 };
+
+                for &in_pin in in_pins {
+                    if snarl[in_pin.node].is_input_connectable(in_pin.input) {
+                        snarl.drop_inputs(in_pin);
+                        snarl.connect(out_pin, in_pin);
+                        fit_variadic_inputs(snarl, in_pin.node);
+                        self.changed_nodes.insert(in_pin.node);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the selected nodes on Ctrl+C, pastes on Ctrl+V, duplicates them on Ctrl+D
+    /// and collapses them into a group on Ctrl+G.
+    fn handle_shortcuts(&mut self, snarl: &mut Snarl<Node>, ui: &mut egui::Ui) {
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+
+        let pointer = ui
+            .ctx()
+            .pointer_hover_pos()
+            .filter(|&pos| ui.max_rect().contains(pos));
+
+        let (copy, paste, duplicate, collapse) = ui.input_mut(|i| {
+            let copy = i.events.iter().any(|e| matches!(e, egui::Event::Copy));
+
+            let paste = i.events.iter().find_map(|e| match e {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            });
+
+            let duplicate = i.consume_key(egui::Modifiers::COMMAND, egui::Key::D);
+            let collapse = i.consume_key(egui::Modifiers::COMMAND, egui::Key::G);
+
+            (copy, paste, duplicate, collapse)
+        });
+
+        if collapse {
+            let selected = &self.selected_nodes;
+
+            // the group keeps showing the previewed noise as its first output
+            let preview = self
+                .active_node
+                .filter(|node| self.group_path.is_empty() && selected.contains(node));
+
+            if let Some(group) = group::collapse(snarl, &mut self.groups, selected, preview) {
+                if preview.is_some() {
+                    self.active_node = Some(group);
+                }
+
+                self.changed_nodes.insert(group);
+            }
+        }
+
+        if copy || duplicate {
+            let fragment = Fragment::new(snarl, &self.selected_nodes, &self.groups);
+
+            if !fragment.is_empty() {
+                if copy {
+                    ui.ctx().copy_text(fragment.to_text());
+                }
+
+                if duplicate {
+                    let pos = self.insert_pos(&fragment, None);
+                    self.inserted_nodes = fragment.insert(snarl, &mut self.groups, pos);
+                    self.changed_nodes.extend(&self.inserted_nodes);
+                }
+            }
+        }
+
+        if let Some(mut fragment) = paste.as_deref().and_then(Fragment::from_text) {
+            fragment.remove_invalid_wires(self);
+
+            let pos = self.insert_pos(&fragment, pointer.map(|pos| self.screen_to_graph() * pos));
+            self.inserted_nodes = fragment.insert(snarl, &mut self.groups, pos);
+            self.changed_nodes.extend(&self.inserted_nodes);
         }
     }
 
+    /// Where to insert a copy of the fragment: at `pos` if there is one, otherwise next to the
+    /// nodes it was copied from, and further along for each copy of the same nodes in a row.
+    fn insert_pos(&mut self, fragment: &Fragment, pos: Option<egui::Pos2>) -> egui::Pos2 {
+        const OFFSET: egui::Vec2 = egui::vec2(40.0, 40.0);
+
+        let text = fragment.to_text();
+        let insert_pos = match &self.last_insert {
+            Some((last_text, last_pos, last_insert_pos))
+                if *last_text == text && *last_pos == pos =>
+            {
+                *last_insert_pos + OFFSET
+            }
+            _ => pos.unwrap_or(fragment.origin() + OFFSET),
+        };
+
+        self.last_insert = Some((text, pos, insert_pos));
+        insert_pos
+    }
+
+    /// Maps screen coordinates to graph coordinates.
+    fn screen_to_graph(&self) -> egui::emath::TSTransform {
+        // without nodes the graph is centered on its origin
+        self.graph_to_screen
+            .unwrap_or(egui::emath::TSTransform::from_translation(
+                self.view_center.to_vec2(),
+            ))
+            .inverse()
+    }
+
+    /// Returns the Normalize nodes of the root graph whose Measure button was clicked since the last call.
+    ///
+    /// The viewer doesn't know the preview region, so the app measures their inputs.
+    pub fn take_measure_requests(&mut self) -> Vec<NodeId> {
+        std::mem::take(&mut self.measure_requests)
+    }
+
+    /// Returns the active node if it or a node it depends on changed since the last frame.
+    pub fn changed(&self, root: &Snarl<Node>) -> Option<NodeId> {
+        let active_node = self.active_node?;
+
+        if self.active_node != self.prev_active_node {
+            return Some(active_node);
+        }
+
+        // changes in a group's graph change the nodes of the root graph that use the group
+        // or a group around it
+        let changed_nodes: Vec<NodeId> = match self.group_path.first() {
+            Some(_) if !self.changed_nodes.is_empty() => {
+                let groups = self.entered_groups(root);
+
+                root.node_ids()
+                    .filter(|(_, node)| node.group().is_some_and(|id| groups.contains(&id)))
+                    .map(|(id, _)| id)
+                    .collect()
+            }
+            _ => self.changed_nodes.iter().copied().collect(),
+        };
+
+        if changed_nodes.is_empty() {
+            return None;
+        }
+
+        let upstream = upstream_nodes(root, active_node);
+
+        changed_nodes
+            .iter()
+            .any(|node| upstream.contains(node))
+            .then_some(active_node)
+    }
+
     pub fn show_menu(
         &mut self,
         pos: egui::Pos2,
         ui: &mut egui::Ui,
         _scale: f32,
-        _src_pins: Option<egui_snarl::ui::AnyPins>,
+        src_pins: Option<egui_snarl::ui::AnyPins>,
         snarl: &mut egui_snarl::Snarl<Node>,
     ) {
-        const NODES_BY_CATEGORY: &[(&str, &[(&str, Node)])] = &[
-            (
-                "Noise",
-                &[
-                    ("Value", Node::Value),
-                    ("Value Cubic", Node::ValueCubic),
-                    ("Perlin", Node::Perlin),
-                    ("Simplex", Node::Simplex),
-                    ("OpenSimplex2", Node::OpenSimplex2),
-                    ("OpenSimplex2s", Node::OpenSimplex2s),
-                    ("Cell Value", Node::CellValue { jitter: 1.0 }),
-                    ("Cell Distance", Node::CellDistance { jitter: 1.0 }),
-                    (
-                        "Cell Distance Squared",
-                        Node::CellDistanceSq { jitter: 1.0 },
-                    ),
-                ],
-            ),
-            (
-                "Transform",
-                &[
-                    (
-                        "Fractal",
-                        Node::Fractal {
-                            octaves: 3,
-                            gain: 0.5,
-                            lacunarity: 2.0,
-                            weighted_strength: 0.0,
-                        },
-                    ),
-                    ("Frequency", Node::Frequency { frequency: 1.0 }),
-                    ("Translate", Node::TranslateXy { x: 0.0, y: 0.0 }),
-                ],
-            ),
-            (
-                "Math",
-                &[
-                    ("Abs", Node::Abs),
-                    ("Neg", Node::Neg),
-                    ("Sqrt", Node::Sqrt),
-                    ("Floor", Node::Floor),
-                    ("Ceil", Node::Ceil),
-                    ("Round", Node::Round),
-                    ("Sin", Node::Sin),
-                    ("Cos", Node::Cos),
-                    ("Tan", Node::Tan),
-                    ("Add", Node::Add { lhs: 0.0, rhs: 0.0 }),
-                    ("Sub", Node::Sub { lhs: 0.0, rhs: 0.0 }),
-                    ("Mul", Node::Mul { lhs: 1.0, rhs: 1.0 }),
-                    ("Div", Node::Div { lhs: 1.0, rhs: 1.0 }),
-                    ("Rem", Node::Rem { lhs: 1.0, rhs: 1.0 }),
-                    ("Pow", Node::Pow { lhs: 1.0, rhs: 1.0 }),
-                    ("Min", Node::Min { lhs: 0.0, rhs: 0.0 }),
-                    ("Max", Node::Max { lhs: 0.0, rhs: 0.0 }),
-                    (
-                        "Lerp",
-                        Node::Lerp {
-                            a: 0.0,
-                            b: 1.0,
-                            t: 0.5,
-                        },
-                    ),
-                    (
-                        "Clamp",
-                        Node::Clamp {
-                            value: 0.5,
-                            min: 0.0,
-                            max: 1.0,
-                        },
-                    ),
-                    ("Triangle Wave", Node::TriangleWave { frequency: 1.0 }),
-                ],
-            ),
-            (
-                "Seed",
-                &[
-                    ("Seed", Node::Seed { seed: 0 }),
-                    ("Add Seed", Node::AddSeed { add: 1 }),
-                    ("Mul Seed", Node::MulSeed { mul: 1 }),
-                ],
-            ),
-            (
-                "Input",
-                &[
-                    ("Position", Node::Position),
-                    ("Number", Node::Number { value: 0.0 }),
-                ],
-            ),
-        ];
-
         ui.label("Add node");
 
-        for &(category, nodes) in NODES_BY_CATEGORY {
-            ui.menu_button(category, |ui| {
-                for &(name, node) in nodes {
-                    if ui.button(name).clicked() {
-                        snarl.insert_node(pos, node);
+        let mut picked = None;
+
+        for (category, nodes) in NODES_BY_CATEGORY.iter() {
+            ui.menu_button(*category, |ui| {
+                for (name, node) in nodes.iter() {
+                    if ui.button(*name).clicked() {
+                        picked = Some(node.clone());
                         ui.close_menu();
                     }
                 }
             });
         }
+
+        if let Some(node) = picked {
+            let node = snarl.insert_node(pos, node);
+
+            if let Some(src_pins) = src_pins {
+                self.connect_new_node(node, src_pins, snarl);
+            }
+        }
     }
 }
 
 impl SnarlViewer<Node> for Viewer {
     fn title(&mut self, node: &Node) -> String {
-        match node {
-            Node::Value => "Value",
-            Node::ValueCubic => "Value Cubic",
-            Node::Perlin => "Perlin",
-            Node::Simplex => "Simplex",
-            Node::OpenSimplex2 => "OpenSimplex2",
-            Node::OpenSimplex2s => "OpenSimplex2s",
-            Node::CellValue { .. } => "Cell Value",
-            Node::CellDistance { .. } => "Cell Distance",
-            Node::CellDistanceSq { .. } => "Cell DistanceSq",
-            Node::Fractal { .. } => "Fractal",
-            Node::Frequency { .. } => "Frequency",
-            Node::TriangleWave { .. } => "TriangleWave",
-            Node::TranslateXy { .. } => "Translate Xy",
-            Node::Abs => "Abs",
-            Node::Neg => "Neg",
-            Node::Sqrt => "Sqrt",
-            Node::Floor => "Floor",
-            Node::Ceil => "Ceil",
-            Node::Round => "Round",
-            Node::Sin => "Sin",
-            Node::Cos => "Cos",
-            Node::Tan => "Tan",
-            Node::Add { .. } => "Add",
-            Node::Sub { .. } => "Subtract",
-            Node::Mul { .. } => "Multiply",
-            Node::Div { .. } => "Divide",
-            Node::Rem { .. } => "Modulo",
-            Node::Pow { .. } => "Power",
-            Node::Min { .. } => "Min",
-            Node::Max { .. } => "Max",
-            Node::Lerp { .. } => "Lerp",
-            Node::Clamp { .. } => "Clamp",
-            Node::Seed { .. } => "Seed",
-            Node::AddSeed { .. } => "Add Seed",
-            Node::MulSeed { .. } => "Multiply Seed",
-            Node::Position => "Position",
-            Node::Number { .. } => "Number",
-        }
-        .into()
+        node.name().into()
     }
 
     fn show_header(
@@ -335,17 +1059,261 @@ impl SnarlViewer<Node> for Viewer {
         _scale: f32,
         snarl: &mut egui_snarl::Snarl<Node>,
     ) {
-        let mut active = self.active_node == Some(node);
+        // only nodes of the outermost graph can be previewed
+        if self.group_path.is_empty() {
+            let mut active = self.active_node == Some(node);
 
-        if ui.add(egui::Checkbox::without_text(&mut active)).changed() {
-            if active {
-                self.active_node = Some(node);
-            } else {
-                self.active_node = None;
+            if ui.add(egui::Checkbox::without_text(&mut active)).changed() {
+                if active {
+                    self.active_node = Some(node);
+                } else {
+                    self.active_node = None;
+                }
             }
         }
 
-        ui.add(egui::Label::new(self.title(&snarl[node])).selectable(false));
+        let changed = match &mut snarl[node] {
+            Node::Group { group, .. } => match self.groups.get_mut(*group) {
+                Some(group) => ui
+                    .add(egui::TextEdit::singleline(&mut group.name).desired_width(120.0))
+                    .changed(),
+                None => false,
+            },
+            Node::GroupInput { name, .. } | Node::GroupOutput { name } => ui
+                .add(
+                    egui::TextEdit::singleline(name)
+                        .hint_text("Name")
+                        .desired_width(120.0),
+                )
+                .changed(),
+            node => {
+                ui.add(egui::Label::new(self.title(node)).selectable(false));
+                false
+            }
+        };
+
+        if changed {
+            self.changed_nodes.insert(node);
+        }
+    }
+
+    fn node_frame(
+        &mut self,
+        default: egui::Frame,
+        node: NodeId,
+        _inputs: &[InPin],
+        _outputs: &[OutPin],
+        _snarl: &Snarl<Node>,
+    ) -> egui::Frame {
+        if self.inserted_nodes.contains(&node) {
+            default.stroke(self.selection_stroke)
+        } else {
+            default
+        }
+    }
+
+    fn final_node_rect(
+        &mut self,
+        _node: egui_snarl::NodeId,
+        ui_rect: egui::Rect,
+        graph_rect: egui::Rect,
+        _ui: &mut egui::Ui,
+        scale: f32,
+        _snarl: &mut egui_snarl::Snarl<Node>,
+    ) {
+        // the same node in both coordinates gives the transform of the view
+        let translation = ui_rect.min.to_vec2() - graph_rect.min.to_vec2() * scale;
+        self.graph_to_screen = Some(egui::emath::TSTransform::new(translation, scale));
+    }
+
+    fn has_body(&mut self, node: &Node) -> bool {
+        matches!(
+            node,
+            Node::Expression { .. }
+                | Node::Remap { .. }
+                | Node::Normalize { .. }
+                | Node::Curve(_)
+                | Node::Terrace { .. }
+                | Node::Quantize { .. }
+                | Node::Cellular { .. }
+                | Node::Falloff { .. }
+        )
+    }
+
+    fn show_body(
+        &mut self,
+        node: egui_snarl::NodeId,
+        _inputs: &[InPin],
+        _outputs: &[egui_snarl::OutPin],
+        ui: &mut egui::Ui,
+        _scale: f32,
+        snarl: &mut egui_snarl::Snarl<Node>,
+    ) {
+        let expression = match &mut snarl[node] {
+            Node::Expression { expression, .. } => expression,
+            Node::Remap { clamp, .. } => {
+                if ui.checkbox(clamp, "Clamp").changed() {
+                    self.changed_nodes.insert(node);
+                }
+
+                return;
+            }
+            Node::Normalize { min, max } => {
+                let mut changed = false;
+
+                ui.vertical(|ui| {
+                    ui.add(egui::Label::new("Manual Range").selectable(false));
+
+                    ui.horizontal(|ui| {
+                        for (name, value) in [("Min", min), ("Max", max)] {
+                            ui.add(egui::Label::new(name).selectable(false));
+                            changed |= ui.add(egui::DragValue::new(value).speed(0.05)).changed();
+                        }
+                    });
+
+                    // the inputs of a group's graph are only known in the outermost graph
+                    let response = ui
+                        .add_enabled(self.group_path.is_empty(), egui::Button::new("Measure"))
+                        .on_hover_text(
+                            "Set the range to that of the input over the preview region. \
+                            It stays fixed when the input changes, measure again to update it.",
+                        )
+                        .on_disabled_hover_text(
+                            "Only nodes of the outermost graph can be measured",
+                        );
+
+                    if response.clicked() {
+                        self.measure_requests.push(node);
+                        changed = true;
+                    }
+                });
+
+                if changed {
+                    self.changed_nodes.insert(node);
+                }
+
+                return;
+            }
+            Node::Curve(curve) => {
+                ui.vertical(|ui| {
+                    if curve_editor::show(ui, egui::Id::new(("curve", node)), curve) {
+                        self.changed_nodes.insert(node);
+                    }
+                });
+
+                return;
+            }
+            Node::Terrace {
+                steps,
+                heights,
+                smoothness,
+                invert,
+            } => {
+                let mut changed = false;
+
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Steps").selectable(false));
+                        let steps = egui::DragValue::new(steps).range(1..=64);
+                        changed |= ui
+                            .add_enabled(heights.is_empty(), steps)
+                            .on_disabled_hover_text("The custom heights are used instead")
+                            .changed();
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Smoothness").selectable(false));
+                        let smoothness = egui::DragValue::new(smoothness)
+                            .speed(0.01)
+                            .range(0.0..=1.0);
+                        changed |= ui.add(smoothness).changed();
+                    });
+
+                    changed |= ui.checkbox(invert, "Invert").changed();
+                    changed |= show_terrace_heights(ui, *steps, heights);
+                });
+
+                if changed {
+                    self.changed_nodes.insert(node);
+                }
+
+                return;
+            }
+            Node::Quantize { levels, .. } => {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Label::new("Levels").selectable(false));
+
+                    if ui
+                        .add(egui::DragValue::new(levels).range(2..=256))
+                        .changed()
+                    {
+                        self.changed_nodes.insert(node);
+                    }
+                });
+
+                return;
+            }
+            Node::Cellular { metric, .. } => {
+                egui::ComboBox::from_id_salt(("cell metric", node))
+                    .selected_text(metric.name())
+                    .show_ui(ui, |ui| {
+                        for option in CellMetric::ALL {
+                            if ui.selectable_value(metric, option, option.name()).changed() {
+                                self.changed_nodes.insert(node);
+                            }
+                        }
+                    });
+
+                return;
+            }
+            Node::Falloff { shape, invert, .. } => {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt(("falloff shape", node))
+                        .selected_text(shape.name())
+                        .show_ui(ui, |ui| {
+                            for option in FalloffShape::ALL {
+                                if ui.selectable_value(shape, option, option.name()).changed() {
+                                    self.changed_nodes.insert(node);
+                                }
+                            }
+                        });
+
+                    if ui.checkbox(invert, "Invert").changed() {
+                        self.changed_nodes.insert(node);
+                    }
+                });
+
+                return;
+            }
+            _ => return,
+        };
+
+        ui.vertical(|ui| {
+            let functions: Vec<&str> = graph::expression::FUNCTIONS
+                .iter()
+                .map(|(name, _)| *name)
+                .collect();
+
+            let response = ui
+                .add(
+                    egui::TextEdit::singleline(expression)
+                        .code_editor()
+                        .hint_text("sin(x * 3) * 0.5 + a")
+                        .desired_width(200.0),
+                )
+                .on_hover_text(format!(
+                    "Variables: a, b, c, x, y, seed\nConstants: pi, tau, e\nOperators: + - * / % ^\nFunctions: {}",
+                    functions.join(", ")
+                ));
+
+            if response.changed() {
+                self.changed_nodes.insert(node);
+            }
+
+            if let Err(error) = graph::expression::parse(expression) {
+                ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+            }
+        });
     }
 
     fn inputs(&mut self, node: &Node) -> usize {
@@ -359,6 +1327,17 @@ impl SnarlViewer<Node> for Viewer {
             Node::CellValue { .. } => 1,
             Node::CellDistance { .. } => 1,
             Node::CellDistanceSq { .. } => 1,
+            Node::Cellular { .. } => 2,
+            Node::Checkerboard { .. } => 1,
+            Node::Stripes { .. } => 2,
+            Node::Rings { .. } => 1,
+            Node::RadialGradient { .. } => 3,
+            Node::LinearGradient { .. } => 2,
+            Node::CircleSdf { .. } => 3,
+            Node::BoxSdf { .. } => 4,
+            Node::GridLines { .. } => 2,
+            Node::HexGrid { .. } => 2,
+            Node::Falloff { .. } => 4,
             Node::Fractal { .. } => 5,
             Node::Frequency { .. } => 2,
             Node::TriangleWave { .. } => 2,
@@ -372,6 +1351,14 @@ impl SnarlViewer<Node> for Viewer {
             Node::Sin => 1,
             Node::Cos => 1,
             Node::Tan => 1,
+            Node::Exp => 1,
+            Node::Ln => 1,
+            Node::Log2 => 1,
+            Node::Sign => 1,
+            Node::Fract => 1,
+            Node::Reciprocal => 1,
+            Node::OneMinus => 1,
+            Node::Saturate => 1,
             Node::Add { .. } => 2,
             Node::Sub { .. } => 2,
             Node::Mul { .. } => 2,
@@ -380,13 +1367,40 @@ impl SnarlViewer<Node> for Viewer {
             Node::Pow { .. } => 2,
             Node::Min { .. } => 2,
             Node::Max { .. } => 2,
+            Node::Step { .. } => 2,
+            Node::Atan2 { .. } => 2,
+            Node::Hypot { .. } => 2,
             Node::Lerp { .. } => 3,
+            Node::InverseLerp { .. } => 3,
+            Node::Smoothstep { .. } => 3,
+            Node::Smootherstep { .. } => 3,
             Node::Clamp { .. } => 3,
+            Node::SmoothMin { .. } => 3,
+            Node::SmoothMax { .. } => 3,
+            Node::Remap { .. } => 5,
+            Node::Normalize { .. } => 1,
+            Node::Expression { .. } => 3,
+            Node::Curve(_) => 1,
+            Node::Terrace { .. } => 1,
+            Node::Quantize { .. } => 3,
+            Node::Select { .. } => 5,
+            Node::Blend { .. } => 3,
+            Node::Sum { weights } => weights.len(),
+            Node::Product { inputs }
+            | Node::Minimum { inputs }
+            | Node::Maximum { inputs }
+            | Node::Average { inputs } => *inputs,
             Node::Seed { .. } => 2,
             Node::AddSeed { .. } => 2,
             Node::MulSeed { .. } => 2,
             Node::Position => 0,
             Node::Number { .. } => 1,
+            Node::Group { group, .. } => self
+                .groups
+                .get(*group)
+                .map_or(0, |group| group.inputs().count()),
+            Node::GroupInput { .. } => 1,
+            Node::GroupOutput { .. } => 1,
         }
     }
 
@@ -478,10 +1492,164 @@ impl SnarlViewer<Node> for Viewer {
             | Node::Round
             | Node::Sin
             | Node::Cos
-            | Node::Tan => noise(ui),
+            | Node::Tan
+            | Node::Exp
+            | Node::Ln
+            | Node::Log2
+            | Node::Sign
+            | Node::Fract
+            | Node::Reciprocal
+            | Node::OneMinus
+            | Node::Saturate
+            | Node::Curve(_) => noise(ui),
             Node::CellValue { jitter }
             | Node::CellDistance { jitter }
             | Node::CellDistanceSq { jitter } => input_jitter(self, ui, jitter),
+            Node::Cellular {
+                jitter, exponent, ..
+            } => {
+                match pin.id.input {
+                    0 => {
+                        input_jitter(self, ui, jitter);
+                    }
+                    1 => {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::Label::new("Exponent").selectable(false));
+
+                            if !has_remote {
+                                let exponent = egui::DragValue::new(exponent)
+                                    .speed(0.05)
+                                    .range(CellMetric::MIN_EXPONENT..=f32::INFINITY);
+                                if ui.add(exponent).changed() {
+                                    self.changed_nodes.insert(pin.id.node);
+                                }
+                            }
+                        });
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Checkerboard { size } => drag_value(self, ui, "Size", size, 0.05),
+            Node::Stripes { width, angle } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Width", width, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Angle", angle, 1.0);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Rings { width } => drag_value(self, ui, "Width", width, 0.05),
+            Node::RadialGradient { x, y, radius } | Node::CircleSdf { x, y, radius } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "X", x, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Y", y, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Radius", radius, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::LinearGradient { angle, length } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Angle", angle, 1.0);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Length", length, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::BoxSdf {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "X", x, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Y", y, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Width", width, 0.05);
+                    }
+                    3 => {
+                        drag_value(self, ui, "Height", height, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::GridLines { spacing, thickness } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Spacing", spacing, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Thickness", thickness, 0.01);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::HexGrid { size, thickness } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Size", size, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Thickness", thickness, 0.01);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Falloff {
+                x,
+                y,
+                radius,
+                exponent,
+                ..
+            } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "X", x, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Y", y, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Radius", radius, 0.05);
+                    }
+                    3 => {
+                        drag_value(self, ui, "Exponent", exponent, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
             Node::Fractal {
                 octaves,
                 gain,
@@ -572,6 +1740,45 @@ impl SnarlViewer<Node> for Viewer {
             Node::Pow { lhs, rhs } => input_binary(self, ui, lhs, rhs),
             Node::Min { lhs, rhs } => input_binary(self, ui, lhs, rhs),
             Node::Max { lhs, rhs } => input_binary(self, ui, lhs, rhs),
+            Node::Step { edge, value } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Edge", edge, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Value", value, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Atan2 { y, x } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Y", y, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "X", x, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Hypot { x, y } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "X", x, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Y", y, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
             Node::Lerp { a, b, t } => {
                 match pin.id.input {
                     0 => {
@@ -588,6 +1795,47 @@ impl SnarlViewer<Node> for Viewer {
 
                 PinInfo::default()
             }
+            Node::InverseLerp { a, b, value } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "A", a, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "B", b, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Value", value, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Smoothstep {
+                edge0,
+                edge1,
+                value,
+            }
+            | Node::Smootherstep {
+                edge0,
+                edge1,
+                value,
+            } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Edge 0", edge0, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Edge 1", edge1, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Value", value, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
             Node::Clamp { value, min, max } => {
                 match pin.id.input {
                     0 => {
@@ -604,6 +1852,146 @@ impl SnarlViewer<Node> for Viewer {
 
                 PinInfo::default()
             }
+            Node::SmoothMin { lhs, rhs, k } | Node::SmoothMax { lhs, rhs, k } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "A", lhs, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "B", rhs, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "K", k, 0.01);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Remap {
+                value,
+                from_min,
+                from_max,
+                to_min,
+                to_max,
+                ..
+            } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "Value", value, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "From Min", from_min, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "From Max", from_max, 0.05);
+                    }
+                    3 => {
+                        drag_value(self, ui, "To Min", to_min, 0.05);
+                    }
+                    4 => {
+                        drag_value(self, ui, "To Max", to_max, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Normalize { .. } => noise(ui),
+            Node::Expression { a, b, c, .. } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "A", a, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "B", b, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "C", c, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Terrace { .. } => noise(ui),
+            Node::Quantize { min, max, .. } => {
+                match pin.id.input {
+                    0 => {
+                        noise(ui);
+                    }
+                    1 => {
+                        drag_value(self, ui, "Min", min, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Max", max, 0.05);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Select {
+                a,
+                b,
+                control,
+                threshold,
+                falloff,
+            } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "A", a, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "B", b, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Control", control, 0.05);
+                    }
+                    3 => {
+                        drag_value(self, ui, "Threshold", threshold, 0.05);
+                    }
+                    4 => {
+                        drag_value(self, ui, "Falloff", falloff, 0.01);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Blend { a, b, weight } => {
+                match pin.id.input {
+                    0 => {
+                        drag_value(self, ui, "A", a, 0.05);
+                    }
+                    1 => {
+                        drag_value(self, ui, "B", b, 0.05);
+                    }
+                    2 => {
+                        drag_value(self, ui, "Weight", weight, 0.01);
+                    }
+                    _ => (),
+                }
+
+                PinInfo::default()
+            }
+            Node::Sum { weights } => {
+                // the weight applies to the connected noise, so it stays editable
+                ui.horizontal(|ui| {
+                    ui.add(egui::Label::new("Weight").selectable(false));
+
+                    let weight = &mut weights[pin.id.input];
+                    if ui.add(egui::DragValue::new(weight).speed(0.01)).changed() {
+                        self.changed_nodes.insert(pin.id.node);
+                    }
+                });
+
+                PinInfo::default()
+            }
+            Node::Product { .. }
+            | Node::Minimum { .. }
+            | Node::Maximum { .. }
+            | Node::Average { .. } => noise(ui),
             Node::Seed { seed: value }
             | Node::AddSeed { add: value }
             | Node::MulSeed { mul: value } => {
@@ -624,6 +2012,29 @@ impl SnarlViewer<Node> for Viewer {
 
                 PinInfo::default()
             }
+            Node::Group { group, values } => {
+                let Some(group) = self.groups.get(*group) else {
+                    return PinInfo::default();
+                };
+
+                // the node starts with the values of the group inputs and keeps its own from then on
+                values.extend(group.input_values().skip(values.len()));
+
+                let input = group.inputs().nth(pin.id.input);
+
+                let name = match input.map(|input| &group.graph[input]) {
+                    Some(Node::GroupInput { name, .. }) if !name.is_empty() => name.clone(),
+                    _ => "Input".to_string(),
+                };
+
+                if let Some(value) = values.get_mut(pin.id.input) {
+                    drag_value(self, ui, &name, value, 0.05);
+                }
+
+                PinInfo::default()
+            }
+            Node::GroupInput { value, .. } => drag_value(self, ui, "Value", value, 0.05),
+            Node::GroupOutput { .. } => noise(ui),
         }
     }
 
@@ -638,6 +2049,16 @@ impl SnarlViewer<Node> for Viewer {
             | Node::CellValue { .. }
             | Node::CellDistance { .. }
             | Node::CellDistanceSq { .. }
+            | Node::Checkerboard { .. }
+            | Node::Stripes { .. }
+            | Node::Rings { .. }
+            | Node::RadialGradient { .. }
+            | Node::LinearGradient { .. }
+            | Node::CircleSdf { .. }
+            | Node::BoxSdf { .. }
+            | Node::GridLines { .. }
+            | Node::HexGrid { .. }
+            | Node::Falloff { .. }
             | Node::Fractal { .. }
             | Node::Frequency { .. }
             | Node::TriangleWave { .. }
@@ -651,6 +2072,14 @@ impl SnarlViewer<Node> for Viewer {
             | Node::Sin
             | Node::Cos
             | Node::Tan
+            | Node::Exp
+            | Node::Ln
+            | Node::Log2
+            | Node::Sign
+            | Node::Fract
+            | Node::Reciprocal
+            | Node::OneMinus
+            | Node::Saturate
             | Node::Add { .. }
             | Node::Sub { .. }
             | Node::Mul { .. }
@@ -659,13 +2088,41 @@ impl SnarlViewer<Node> for Viewer {
             | Node::Pow { .. }
             | Node::Min { .. }
             | Node::Max { .. }
+            | Node::Step { .. }
+            | Node::Atan2 { .. }
+            | Node::Hypot { .. }
             | Node::Lerp { .. }
+            | Node::InverseLerp { .. }
+            | Node::Smoothstep { .. }
+            | Node::Smootherstep { .. }
             | Node::Clamp { .. }
+            | Node::SmoothMin { .. }
+            | Node::SmoothMax { .. }
+            | Node::Remap { .. }
+            | Node::Normalize { .. }
+            | Node::Expression { .. }
+            | Node::Curve(_)
+            | Node::Terrace { .. }
+            | Node::Quantize { .. }
+            | Node::Select { .. }
+            | Node::Blend { .. }
+            | Node::Sum { .. }
+            | Node::Product { .. }
+            | Node::Minimum { .. }
+            | Node::Maximum { .. }
+            | Node::Average { .. }
             | Node::Seed { .. }
             | Node::AddSeed { .. }
             | Node::MulSeed { .. }
-            | Node::Number { .. } => 1,
+            | Node::Number { .. }
+            | Node::GroupInput { .. } => 1,
             Node::Position => 2,
+            Node::Cellular { .. } => 5,
+            Node::Group { group, .. } => self
+                .groups
+                .get(*group)
+                .map_or(0, |group| group.outputs().count()),
+            Node::GroupOutput { .. } => 0,
         }
     }
 
@@ -676,7 +2133,7 @@ impl SnarlViewer<Node> for Viewer {
         _scale: f32,
         snarl: &mut egui_snarl::Snarl<Node>,
     ) -> PinInfo {
-        match snarl[pin.id.node] {
+        match &snarl[pin.id.node] {
             Node::Value
             | Node::ValueCubic
             | Node::Perlin
@@ -686,6 +2143,16 @@ impl SnarlViewer<Node> for Viewer {
             | Node::CellValue { .. }
             | Node::CellDistance { .. }
             | Node::CellDistanceSq { .. }
+            | Node::Checkerboard { .. }
+            | Node::Stripes { .. }
+            | Node::Rings { .. }
+            | Node::RadialGradient { .. }
+            | Node::LinearGradient { .. }
+            | Node::CircleSdf { .. }
+            | Node::BoxSdf { .. }
+            | Node::GridLines { .. }
+            | Node::HexGrid { .. }
+            | Node::Falloff { .. }
             | Node::Fractal { .. }
             | Node::Frequency { .. }
             | Node::TriangleWave { .. }
@@ -699,6 +2166,14 @@ impl SnarlViewer<Node> for Viewer {
             | Node::Sin
             | Node::Cos
             | Node::Tan
+            | Node::Exp
+            | Node::Ln
+            | Node::Log2
+            | Node::Sign
+            | Node::Fract
+            | Node::Reciprocal
+            | Node::OneMinus
+            | Node::Saturate
             | Node::Add { .. }
             | Node::Sub { .. }
             | Node::Mul { .. }
@@ -707,15 +2182,51 @@ impl SnarlViewer<Node> for Viewer {
             | Node::Pow { .. }
             | Node::Min { .. }
             | Node::Max { .. }
+            | Node::Step { .. }
+            | Node::Atan2 { .. }
+            | Node::Hypot { .. }
             | Node::Lerp { .. }
+            | Node::InverseLerp { .. }
+            | Node::Smoothstep { .. }
+            | Node::Smootherstep { .. }
             | Node::Clamp { .. }
+            | Node::SmoothMin { .. }
+            | Node::SmoothMax { .. }
+            | Node::Remap { .. }
+            | Node::Normalize { .. }
+            | Node::Expression { .. }
+            | Node::Curve(_)
+            | Node::Terrace { .. }
+            | Node::Quantize { .. }
+            | Node::Select { .. }
+            | Node::Blend { .. }
+            | Node::Sum { .. }
+            | Node::Product { .. }
+            | Node::Minimum { .. }
+            | Node::Maximum { .. }
+            | Node::Average { .. }
             | Node::Seed { .. }
             | Node::AddSeed { .. }
             | Node::MulSeed { .. }
-            | Node::Number { .. } => {
+            | Node::Number { .. }
+            | Node::GroupInput { .. } => {
                 ui.add(egui::Label::new("Output").selectable(false));
                 PinInfo::default()
             }
+            Node::GroupOutput { .. } => unreachable!(),
+            Node::Group { group, .. } => {
+                let group = self.groups.get(*group);
+                let output =
+                    group.and_then(|group| Some(&group.graph[group.outputs().nth(pin.id.output)?]));
+
+                let name = match output {
+                    Some(Node::GroupOutput { name }) if !name.is_empty() => name.as_str(),
+                    _ => "Output",
+                };
+
+                ui.add(egui::Label::new(name).selectable(false));
+                PinInfo::default()
+            }
             Node::Position => {
                 match pin.id.output {
                     0 => {
@@ -729,19 +2240,34 @@ impl SnarlViewer<Node> for Viewer {
 
                 PinInfo::default()
             }
+            Node::Cellular { .. } => {
+                let name = ["F1", "F2", "F2 - F1", "F1 · F2", "Cell Value"]
+                    .get(pin.id.output)
+                    .copied()
+                    .unwrap_or_default();
+
+                ui.add(egui::Label::new(name).selectable(false));
+                PinInfo::default()
+            }
         }
     }
 
     fn connect(&mut self, from: &egui_snarl::OutPin, to: &InPin, snarl: &mut Snarl<Node>) {
+        if !snarl[to.id.node].is_input_connectable(to.id.input) {
+            return;
+        }
+
         self.changed_nodes.insert(to.id.node);
 
         snarl.drop_inputs(to.id);
         snarl.connect(from.id, to.id);
+        fit_variadic_inputs(snarl, to.id.node);
     }
 
     fn disconnect(&mut self, from: &egui_snarl::OutPin, to: &InPin, snarl: &mut Snarl<Node>) {
         self.changed_nodes.insert(to.id.node);
         snarl.disconnect(from.id, to.id);
+        fit_variadic_inputs(snarl, to.id.node);
     }
 
     fn has_graph_menu(&mut self, _pos: egui::Pos2, _snarl: &mut egui_snarl::Snarl<Node>) -> bool {
@@ -774,6 +2300,23 @@ impl SnarlViewer<Node> for Viewer {
         src_pins: egui_snarl::ui::AnyPins,
         snarl: &mut egui_snarl::Snarl<Node>,
     ) {
+        if let Some(item) = self.palette.show(ui, &self.presets) {
+            match item {
+                PaletteItem::Node(node) => {
+                    let node = snarl.insert_node(pos, node);
+                    self.connect_new_node(node, src_pins, snarl);
+                }
+                PaletteItem::Preset(fragment) => {
+                    self.insert_preset(snarl, &fragment, pos);
+                }
+            }
+
+            ui.close_menu();
+            return;
+        }
+
+        ui.separator();
+
         self.show_menu(pos, ui, scale, Some(src_pins), snarl);
     }
 
@@ -792,125 +2335,171 @@ impl SnarlViewer<Node> for Viewer {
     ) {
         ui.label("Node menu");
 
+        if let Node::Group { .. } = snarl[node] {
+            if ui.button("Enter Group").clicked() {
+                self.group_path.push(node);
+                ui.close_menu();
+            }
+        }
+
         if ui.button("Remove").clicked() {
+            let downstream: Vec<NodeId> = snarl
+                .wires()
+                .filter(|(out_pin, _)| out_pin.node == node)
+                .map(|(_, in_pin)| in_pin.node)
+                .collect();
+
             snarl.remove_node(node);
+            self.changed_nodes.insert(node);
+
+            for downstream in downstream {
+                fit_variadic_inputs(snarl, downstream);
+                self.changed_nodes.insert(downstream);
+            }
+
             ui.close_menu();
         }
     }
 }
 
-pub fn node_to_noise(
-    snarl: &Snarl<Node>,
-    pin: OutPinId,
-) -> Option<Box<dyn noise_functions::Sample<2>>> {
-    use noise_functions::{Constant, Noise, NoiseFn, Sample};
+impl From<NodeId> for graph::NodeId {
+    fn from(id: NodeId) -> Self {
+        graph::NodeId(id.0)
+    }
+}
+
+impl From<OutPinId> for graph::OutPinId {
+    fn from(pin: OutPinId) -> Self {
+        graph::OutPinId {
+            node: pin.node.into(),
+            output: pin.output,
+        }
+    }
+}
+
+impl From<InPinId> for graph::InPinId {
+    fn from(pin: InPinId) -> Self {
+        graph::InPinId {
+            node: pin.node.into(),
+            input: pin.input,
+        }
+    }
+}
+
+/// The node and the nodes whose outputs it depends on.
+fn upstream_nodes(snarl: &Snarl<Node>, node: NodeId) -> egui::ahash::HashSet<NodeId> {
+    let mut upstream = egui::ahash::HashSet::default();
+    let mut stack = vec![node];
 
-    fn input_or(
-        snarl: &Snarl<Node>,
-        pin: InPinId,
-        default: f32,
-    ) -> Option<Box<dyn noise_functions::Sample<2>>> {
-        if let Some(&pin) = snarl.in_pin(pin).remotes.first() {
-            node_to_noise(snarl, pin)
-        } else {
-            Some(Box::new(Constant(default)))
+    while let Some(node) = stack.pop() {
+        if upstream.insert(node) {
+            stack.extend(
+                snarl
+                    .wires()
+                    .filter(|(_, in_pin)| in_pin.node == node)
+                    .map(|(out_pin, _)| out_pin.node),
+            );
         }
     }
 
-    fn cell_noise<N: Sample<2>>(
-        snarl: &Snarl<Node>,
-        node: NodeId,
-        default_jitter: f32,
-        f: impl Fn(f32) -> N + 'static,
-    ) -> Option<Box<dyn noise_functions::Sample<2>>> {
-        let pin = InPinId { node, input: 0 };
-        let jitter = input_or(snarl, pin, default_jitter)?;
-
-        Some(Box::new(NoiseFn(move |point: [f32; 2], seed: i32| {
-            let jitter = jitter.sample_with_seed(point, seed);
-            f(jitter).sample_with_seed(point, seed)
-        })))
-    }
-
-    let input_or = |i: usize, default: f32| -> Option<Box<dyn noise_functions::Sample<2>>> {
-        let pin = InPinId {
-            node: pin.node,
-            input: i,
+    upstream
+}
+
+/// Leaves a variadic node like [`Node::Sum`] one unconnected input after its last connected one.
+fn fit_variadic_inputs(snarl: &mut Snarl<Node>, node: NodeId) {
+    if snarl[node].variadic_inputs().is_none() {
+        return;
+    }
+
+    let used = snarl
+        .wires()
+        .filter(|(_, in_pin)| in_pin.node == node)
+        .map(|(_, in_pin)| in_pin.input + 1)
+        .max()
+        .unwrap_or(0);
+
+    snarl[node].set_variadic_inputs(used + 1);
+}
+
+/// Converts the edited graph to the graph model, keeping the node ids.
+pub fn snarl_to_graph(snarl: &Snarl<Node>) -> Graph {
+    let mut graph = Graph::new();
+
+    for (id, _) in snarl.node_ids() {
+        let info = snarl.get_node_info(id).expect("node exists");
+        graph.insert_node_with_id(id.into(), info.pos.into(), info.value.clone());
+    }
+
+    for (out_pin, in_pin) in snarl.wires() {
+        graph.connect(out_pin.into(), in_pin.into());
+    }
+
+    graph
+}
+
+/// Converts the graph model to a graph for editing and returns the ids that its nodes got there.
+pub fn graph_to_snarl(graph: &Graph) -> (Snarl<Node>, BTreeMap<graph::NodeId, NodeId>) {
+    let mut snarl = Snarl::new();
+    let mut ids = BTreeMap::new();
+
+    for (id, node) in graph.nodes() {
+        let pos = graph.node_pos(id).expect("node exists");
+        ids.insert(id, snarl.insert_node(egui::Pos2::from(pos), node.clone()));
+    }
+
+    for (out_pin, in_pin) in graph.wires() {
+        let (Some(&from), Some(&to)) = (ids.get(&out_pin.node), ids.get(&in_pin.node)) else {
+            continue;
         };
 
-        if let Some(&pin) = snarl.in_pin(pin).remotes.first() {
-            node_to_noise(snarl, pin)
-        } else {
-            Some(Box::new(Constant(default)))
-        }
-    };
-
-    match snarl[pin.node] {
-        Node::Value => Some(Box::new(noise_functions::Value)),
-        Node::ValueCubic => Some(Box::new(noise_functions::ValueCubic)),
-        Node::Perlin => Some(Box::new(noise_functions::Perlin)),
-        Node::Simplex => Some(Box::new(noise_functions::Simplex)),
-        Node::OpenSimplex2 => Some(Box::new(noise_functions::OpenSimplex2)),
-        Node::OpenSimplex2s => Some(Box::new(noise_functions::OpenSimplex2s)),
-        Node::CellValue { jitter } => cell_noise(snarl, pin.node, jitter, |jitter| {
-            noise_functions::CellValue { jitter }
-        }),
-        Node::CellDistance { jitter } => cell_noise(snarl, pin.node, jitter, |jitter| {
-            noise_functions::CellDistance { jitter }
-        }),
-        Node::CellDistanceSq { jitter } => cell_noise(snarl, pin.node, jitter, |jitter| {
-            noise_functions::CellDistanceSq { jitter }
-        }),
-        Node::Fractal {
-            octaves,
-            gain,
-            lacunarity,
-            weighted_strength,
-        } => Some(Box::new(
-            input_or(0, 0.0)?
-                .fbm(octaves, gain, lacunarity)
-                .weighted(weighted_strength),
-        )),
-        Node::Frequency { frequency } => Some(Box::new(
-            input_or(0, 0.0)?.frequency(input_or(1, frequency)?),
-        )),
-        Node::TriangleWave { frequency } => Some(Box::new(
-            input_or(0, 0.0)?.triangle_wave(input_or(1, frequency)?),
-        )),
-        Node::TranslateXy { x, y } => Some(Box::new(
-            input_or(0, 0.0)?.translate_xy(input_or(1, x)?, input_or(2, y)?),
-        )),
-        Node::Abs => Some(Box::new(input_or(0, 0.0)?.abs())),
-        Node::Neg => Some(Box::new(input_or(0, 0.0)?.neg())),
-        Node::Sqrt => Some(Box::new(input_or(0, 0.0)?.sqrt())),
-        Node::Floor => Some(Box::new(input_or(0, 0.0)?.floor())),
-        Node::Ceil => Some(Box::new(input_or(0, 0.0)?.ceil())),
-        Node::Round => Some(Box::new(input_or(0, 0.0)?.round())),
-        Node::Sin => Some(Box::new(input_or(0, 0.0)?.sin())),
-        Node::Cos => Some(Box::new(input_or(0, 0.0)?.cos())),
-        Node::Tan => Some(Box::new(input_or(0, 0.0)?.tan())),
-        Node::Add { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.add(input_or(1, rhs)?))),
-        Node::Sub { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.sub(input_or(1, rhs)?))),
-        Node::Mul { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.mul(input_or(1, rhs)?))),
-        Node::Div { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.div(input_or(1, rhs)?))),
-        Node::Rem { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.rem(input_or(1, rhs)?))),
-        Node::Pow { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.pow(input_or(1, rhs)?))),
-        Node::Min { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.min(input_or(1, rhs)?))),
-        Node::Max { lhs, rhs } => Some(Box::new(input_or(0, lhs)?.max(input_or(1, rhs)?))),
-        Node::Lerp { a, b, t } => Some(Box::new(
-            input_or(0, a)?.clamp(input_or(1, b)?, input_or(2, t)?),
-        )),
-        Node::Clamp { value, min, max } => Some(Box::new(
-            input_or(0, value)?.clamp(input_or(1, min)?, input_or(2, max)?),
-        )),
-        Node::Seed { seed } => Some(Box::new(input_or(0, 0.0)?.seed(seed))),
-        Node::AddSeed { add } => Some(Box::new(input_or(0, 0.0)?.add_seed(add))),
-        Node::MulSeed { mul } => Some(Box::new(input_or(0, 0.0)?.mul_seed(mul))),
-        Node::Position => match pin.output {
-            0 => Some(Box::new(NoiseFn(move |point: [f32; 2]| point[0]))),
-            1 => Some(Box::new(NoiseFn(move |point: [f32; 2]| point[1]))),
-            _ => None,
-        },
-        Node::Number { value } => Some(Box::new(input_or(0, value)?)),
+        snarl.connect(
+            OutPinId {
+                node: from,
+                output: out_pin.output,
+            },
+            InPinId {
+                node: to,
+                input: in_pin.input,
+            },
+        );
+    }
+
+    (snarl, ids)
+}
+
+/// Shows the custom heights of a Terrace node, returns whether they changed.
+fn show_terrace_heights(ui: &mut egui::Ui, steps: u32, heights: &mut Vec<f32>) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+
+    for (i, height) in heights.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(height).speed(0.01)).changed();
+
+            if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                removed = Some(i);
+            }
+        });
+    }
+
+    if let Some(i) = removed {
+        heights.remove(i);
+        changed = true;
     }
+
+    if heights.is_empty() {
+        if ui
+            .button("Custom Heights")
+            .on_hover_text("Edit the heights of the steps")
+            .clicked()
+        {
+            *heights = graph::ir::terrace_heights(steps, &[]);
+            changed = true;
+        }
+    } else if ui.button("Add Height").clicked() {
+        heights.push(heights.last().map_or(0.0, |height| height + 0.1));
+        changed = true;
+    }
+
+    changed
 }
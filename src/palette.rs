@@ -0,0 +1,194 @@
+use crate::{
+    fragment::Fragment,
+    graph::Node,
+    nodes_snarl::NODES_BY_CATEGORY,
+    presets::{Preset, PresetLibrary},
+};
+
+const MAX_RESULTS: usize = 12;
+
+/// What was picked in the palette.
+pub enum PaletteItem {
+    Node(Node),
+    Preset(Fragment),
+}
+
+#[derive(Clone, Copy)]
+enum Entry<'a> {
+    Node(&'static Node),
+    Preset(&'a Preset),
+}
+
+impl Entry<'_> {
+    fn to_item(self) -> PaletteItem {
+        match self {
+            Entry::Node(node) => PaletteItem::Node(node.clone()),
+            Entry::Preset(preset) => PaletteItem::Preset(preset.fragment.clone()),
+        }
+    }
+}
+
+/// A search field that fuzzy filters all node types and presets by name, category and aliases.
+#[derive(Default)]
+pub struct Palette {
+    query: String,
+    selected: usize,
+    last_shown_pass: Option<u64>,
+}
+
+impl Palette {
+    /// Shows the search field and the matching node types and presets.
+    /// Returns what was picked with a click or <kbd>Enter</kbd>.
+    pub fn show(&mut self, ui: &mut egui::Ui, presets: &PresetLibrary) -> Option<PaletteItem> {
+        let pass = ui.ctx().cumulative_pass_nr();
+        let just_opened = self.last_shown_pass.is_none_or(|last| last + 1 < pass);
+        self.last_shown_pass = Some(pass);
+
+        if just_opened {
+            self.query.clear();
+            self.selected = 0;
+        }
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.query)
+                .hint_text("Search nodes…")
+                .desired_width(200.0),
+        );
+
+        if just_opened {
+            response.request_focus();
+        }
+
+        if response.changed() {
+            self.selected = 0;
+        }
+
+        let results = self.results(presets);
+
+        let (up, down, enter) = ui.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+            )
+        });
+
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+
+        if down {
+            self.selected += 1;
+        }
+
+        self.selected = self.selected.min(results.len().saturating_sub(1));
+
+        let mut picked = None;
+
+        ui.separator();
+
+        if results.is_empty() {
+            ui.weak("No matching nodes");
+        }
+
+        for (i, &(category, name, entry)) in results.iter().enumerate() {
+            let label = format!("{name}  ({category})");
+
+            if ui.selectable_label(i == self.selected, label).clicked() {
+                picked = Some(entry.to_item());
+            }
+        }
+
+        if enter {
+            if let Some(&(_, _, entry)) = results.get(self.selected) {
+                picked = Some(entry.to_item());
+            }
+        }
+
+        if picked.is_some() {
+            self.last_shown_pass = None;
+        }
+
+        picked
+    }
+
+    /// Returns the best matches for the query as `(category, name, entry)`.
+    fn results<'a>(&self, presets: &'a PresetLibrary) -> Vec<(&'a str, &'a str, Entry<'a>)> {
+        let mut results = Vec::new();
+
+        let nodes = NODES_BY_CATEGORY.iter().flat_map(|(category, nodes)| {
+            nodes
+                .iter()
+                .map(move |(name, node)| (*category, *name, node.aliases(), Entry::Node(node)))
+        });
+
+        let presets = presets.presets.iter().map(|preset| {
+            let no_aliases: &[&str] = &[];
+            (
+                preset.category.as_str(),
+                preset.name.as_str(),
+                no_aliases,
+                Entry::Preset(preset),
+            )
+        });
+
+        for (category, name, aliases, entry) in nodes.chain(presets) {
+            let score = std::iter::once(name)
+                .chain(aliases.iter().copied())
+                .chain(std::iter::once(category))
+                .filter_map(|text| fuzzy_score(&self.query, text))
+                .max();
+
+            if let Some(score) = score {
+                results.push((score, category, name, entry));
+            }
+        }
+
+        // stable sort, so equal scores keep the menu order
+        results.sort_by_key(|&(score, ..)| std::cmp::Reverse(score));
+        results.truncate(MAX_RESULTS);
+
+        results
+            .into_iter()
+            .map(|(_, category, name, entry)| (category, name, entry))
+            .collect()
+    }
+}
+
+/// Matches if all characters of `query` appear in order in `text`, ignoring case.
+/// Consecutive characters and characters at the start of words score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut text_chars = text.char_indices();
+    let mut prev_match_end = None;
+
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (i, t) = text_chars.next()?;
+
+            if !t.eq_ignore_ascii_case(&q) {
+                continue;
+            }
+
+            score += 1;
+
+            if i == 0 || text[..i].ends_with([' ', '-', '_']) {
+                score += 4;
+            }
+
+            if prev_match_end == Some(i) {
+                score += 2;
+            }
+
+            prev_match_end = Some(i + t.len_utf8());
+            break;
+        }
+    }
+
+    if score == 0 {
+        return Some(0);
+    }
+
+    // prefer shorter texts for the same matches
+    Some(score * 64 - text.len() as i32)
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use crate::fragment::Fragment;
+
+/// A selection of nodes the user saved to insert it again later.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub category: String,
+    pub fragment: Fragment,
+}
+
+/// The user's presets.
+///
+/// They are stored separately from the graph so they are available in every graph.
+#[derive(Default, Serialize, Deserialize)]
+pub struct PresetLibrary {
+    pub presets: Vec<Preset>,
+}
+
+impl PresetLibrary {
+    /// Adds the preset, replacing one with the same name and category.
+    pub fn insert(&mut self, preset: Preset) {
+        match self
+            .presets
+            .iter_mut()
+            .find(|p| p.name == preset.name && p.category == preset.category)
+        {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn merge(&mut self, other: PresetLibrary) {
+        for preset in other.presets {
+            self.insert(preset);
+        }
+    }
+
+    /// The categories of all presets in order of first appearance.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories = Vec::<&str>::new();
+
+        for preset in &self.presets {
+            if !categories.contains(&preset.category.as_str()) {
+                categories.push(&preset.category);
+            }
+        }
+
+        categories
+    }
+
+    pub fn to_text(&self) -> String {
+        ron::ser::to_string_pretty(self, Default::default()).expect("presets are serializable")
+    }
+
+    /// Reads presets, leaving out those that lack groups their nodes use.
+    pub fn from_text(text: &str) -> Result<Self, ron::error::SpannedError> {
+        let mut presets: Self = ron::from_str(text)?;
+        presets
+            .presets
+            .retain(|preset| preset.fragment.has_groups());
+        Ok(presets)
+    }
+}
@@ -0,0 +1,178 @@
+//! Samples noise on a grid for the preview and the command line renderer.
+
+#[cfg(feature = "gui")]
+use std::collections::HashMap;
+
+#[cfg(feature = "gui")]
+use crate::graph::{ir, Slot};
+use crate::graph::{Registers, Sampler};
+
+/// A rectangle in noise coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl Region {
+    /// The square from `-extent` to `extent` on both axes.
+    pub fn centered(extent: f32) -> Self {
+        Self {
+            min: [-extent; 2],
+            max: [extent; 2],
+        }
+    }
+}
+
+/// Samples the noise at the top left corner of every cell of a `width` by `height` grid over the region.
+///
+/// The values are in row-major order, the first row is at `region.min[1]`.
+#[cfg(any(feature = "gui", all(feature = "cli", not(target_arch = "wasm32"))))]
+pub fn sample_grid(sampler: &Sampler, size: [usize; 2], region: Region, seed: i32) -> Vec<f32> {
+    let (xs, ys) = grid_points(size, region);
+    let mut values = vec![0.0; xs.len()];
+    sampler.sample_batch(&xs, &ys, seed, &mut values, &mut Registers::default());
+    values
+}
+
+/// The coordinates of the points that [`sample_grid`] samples.
+fn grid_points([width, height]: [usize; 2], region: Region) -> (Vec<f32>, Vec<f32>) {
+    let scale_x = (region.max[0] - region.min[0]) / width as f32;
+    let scale_y = (region.max[1] - region.min[1]) / height as f32;
+
+    let mut xs = Vec::with_capacity(width * height);
+    let mut ys = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            xs.push(region.min[0] + x as f32 * scale_x);
+            ys.push(region.min[1] + y as f32 * scale_y);
+        }
+    }
+
+    (xs, ys)
+}
+
+/// The most values that a [`GridCache`] keeps, which take 64 MiB.
+#[cfg(feature = "gui")]
+const MAX_CACHED_VALUES: usize = 16 << 20;
+
+/// The grids of the nodes sampled for the last preview, so that changing a node
+/// doesn't resample the nodes before it.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct GridCache {
+    grids: HashMap<GridKey, Vec<f32>>,
+}
+
+#[cfg(feature = "gui")]
+#[derive(PartialEq, Eq, Hash)]
+struct GridKey {
+    /// The hash of the node's output op, see [`ir::Program::hashes`].
+    node: u64,
+    size: [usize; 2],
+    region: [u32; 4],
+    seed: i32,
+}
+
+#[cfg(feature = "gui")]
+impl GridCache {
+    /// Like [`sample_grid`], reusing the grids of the nodes that didn't change since the last call.
+    ///
+    /// Only the grids of the last call are kept, those of the nodes that run first
+    /// while they fit in [`MAX_CACHED_VALUES`].
+    pub fn sample_grid(
+        &mut self,
+        program: &ir::Program,
+        size: [usize; 2],
+        region: Region,
+        seed: i32,
+    ) -> Vec<f32> {
+        let sampler = Sampler::from_program(program);
+        let hashes = program.hashes();
+        let (xs, ys) = grid_points(size, region);
+        let mut values = vec![0.0; xs.len()];
+        let [x0, y0] = region.min;
+        let [x1, y1] = region.max;
+
+        let keys: Vec<GridKey> = sampler
+            .output_ops()
+            .iter()
+            .map(|&op| GridKey {
+                node: hashes[op],
+                size,
+                region: [x0, y0, x1, y1].map(f32::to_bits),
+                seed,
+            })
+            .collect();
+
+        let mut room = MAX_CACHED_VALUES;
+
+        let kept: Vec<bool> = sampler
+            .output_ops()
+            .iter()
+            .map(|&op| {
+                // not worth the memory
+                let cheap = matches!(
+                    program.ops[op],
+                    ir::Op::Constant(_) | ir::Op::Position(_) | ir::Op::SeedValue
+                );
+                let node = program.node_outputs.binary_search(&op).is_ok();
+                let kept = node && !cheap && room >= xs.len();
+
+                if kept {
+                    room -= xs.len();
+                }
+
+                kept
+            })
+            .collect();
+
+        let mut recorded: Vec<Option<Vec<f32>>> = keys
+            .iter()
+            .zip(&kept)
+            .map(|(key, &kept)| {
+                (kept && !self.grids.contains_key(key)).then(|| vec![0.0; xs.len()])
+            })
+            .collect();
+
+        let mut slots: Vec<Slot> = keys
+            .iter()
+            .zip(&mut recorded)
+            .map(|(key, recorded)| match (self.grids.get(key), recorded) {
+                (Some(grid), _) => Slot::Known(grid),
+                (None, Some(recorded)) => Slot::Record(recorded),
+                (None, None) => Slot::Compute,
+            })
+            .collect();
+
+        sampler.sample_batch_with(
+            &xs,
+            &ys,
+            seed,
+            &mut values,
+            &mut Registers::default(),
+            &mut slots,
+        );
+
+        let mut grids = HashMap::new();
+
+        for ((key, recorded), kept) in keys.into_iter().zip(recorded).zip(kept) {
+            if !kept {
+                continue;
+            }
+
+            if let Some(grid) = recorded.or_else(|| self.grids.remove(&key)) {
+                grids.insert(key, grid);
+            }
+        }
+
+        self.grids = grids;
+        values
+    }
+}
+
+/// Maps `min..max` to `0..1`, without clamping.
+pub fn normalize(value: f32, [min, max]: [f32; 2]) -> f32 {
+    (value - min) / (max - min)
+}